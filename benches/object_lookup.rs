@@ -0,0 +1,37 @@
+//! Compares key lookup in `JsonMap` with a linear scan over the same
+//! members, for objects of growing size.
+//!
+//! Run with `cargo bench --bench object_lookup`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use json_parser::libs::json_parser::JsonValue;
+use json_parser::libs::map::JsonMap;
+
+const LOOKUPS: usize = 20_000;
+
+fn main() {
+    println!("{:>8}  {:>14}  {:>14}", "keys", "JsonMap (ns)", "Vec scan (ns)");
+    for size in [10, 100, 1_000, 10_000, 50_000] {
+        let members: Vec<(String, JsonValue)> = (0..size)
+            .map(|i| (format!("key{}", i), JsonValue::Number(i as f64)))
+            .collect();
+        let map: JsonMap = members.iter().cloned().collect();
+        let keys: Vec<String> = (0..LOOKUPS).map(|i| format!("key{}", (i * 7919) % size)).collect();
+
+        let start = Instant::now();
+        for key in &keys {
+            black_box(map.get(black_box(key)));
+        }
+        let map_ns = start.elapsed().as_nanos() / LOOKUPS as u128;
+
+        let start = Instant::now();
+        for key in &keys {
+            black_box(members.iter().find(|(k, _)| k == black_box(key)));
+        }
+        let scan_ns = start.elapsed().as_nanos() / LOOKUPS as u128;
+
+        println!("{:>8}  {:>14}  {:>14}", size, map_ns, scan_ns);
+    }
+}
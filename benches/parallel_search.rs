@@ -0,0 +1,49 @@
+//! Time to search line-delimited input and a large top-level array with
+//! an increasing number of worker threads.
+//!
+//! Run with `cargo bench --bench parallel_search`.
+
+use std::time::Instant;
+
+use json_parser::libs::json_parser::ParseMode;
+use json_parser::libs::parallel::{default_jobs, search_records, Layout};
+
+const RECORDS: usize = 300_000;
+
+fn record(i: usize) -> String {
+    format!(
+        "{{\"id\": {}, \"user\": {{\"name\": \"user {}\", \"role\": \"{}\"}}, \"tags\": [\"a\", \"b\", \"c\"], \"score\": {}.5}}",
+        i,
+        i,
+        if i.is_multiple_of(100) { "admin" } else { "member" },
+        i % 1000
+    )
+}
+
+fn main() {
+    let records: Vec<String> = (0..RECORDS).map(record).collect();
+    let lines = records.join("\n");
+    let array = format!("[{}]", records.join(",\n"));
+
+    let mut jobs = vec![1, 2, 4, 8, default_jobs()];
+    jobs.sort_unstable();
+    jobs.dedup();
+
+    for (name, input, layout) in [("lines", &lines, Layout::Lines), ("array", &array, Layout::Array)] {
+        println!("{} ({} MB)", name, input.len() / 1_000_000);
+        let mut baseline = None;
+        for &n in &jobs {
+            let start = Instant::now();
+            let matches = search_records(input, layout, "admin", ParseMode::Strict, n).unwrap();
+            let elapsed = start.elapsed().as_secs_f64();
+            let base = *baseline.get_or_insert(elapsed);
+            println!(
+                "  jobs {:>3}: {:>8.1} ms  {:>5.2}x  ({} matches)",
+                n,
+                elapsed * 1000.0,
+                base / elapsed,
+                matches.len()
+            );
+        }
+    }
+}
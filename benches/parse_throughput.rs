@@ -0,0 +1,73 @@
+//! Parsing throughput, in MB/s, for documents of different shapes.
+//!
+//! Run with `cargo bench --bench parse_throughput`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use json_parser::libs::borrowed;
+use json_parser::libs::json_parser::{JsonParser, ParseMode};
+
+fn strings(count: usize) -> String {
+    let items: Vec<String> = (0..count)
+        .map(|i| format!("\"{}-{}\"", "lorem ipsum dolor sit amet consectetur ".repeat(4), i))
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+fn numbers(count: usize) -> String {
+    let items: Vec<String> = (0..count).map(|i| format!("{}.{}e-3", i * 7919, i % 97)).collect();
+    format!("[{}]", items.join(","))
+}
+
+fn records(count: usize) -> String {
+    let items: Vec<String> = (0..count)
+        .map(|i| {
+            format!(
+                "{{\"id\": {}, \"name\": \"user {}\", \"email\": \"user{}@example.com\", \"active\": {}, \"tags\": [\"a\", \"b\\n\"], \"score\": null}}",
+                i,
+                i,
+                i,
+                i % 2 == 0
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+fn pretty(count: usize) -> String {
+    let items: Vec<String> = (0..count)
+        .map(|i| format!("\n        {{\n            \"key\": \"value {}\",\n            \"n\": {}\n        }}", i, i))
+        .collect();
+    format!("{{\n    \"items\": [{}\n    ]\n}}", items.join(","))
+}
+
+/// Runs `parse` repeatedly for about half a second and returns MB/s.
+fn throughput(input: &str, parse: impl Fn(&str)) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < Duration::from_millis(500) {
+        parse(black_box(input));
+        runs += 1;
+    }
+    (input.len() * runs) as f64 / start.elapsed().as_secs_f64() / 1_000_000.0
+}
+
+fn main() {
+    let documents = [
+        ("strings", strings(20_000)),
+        ("numbers", numbers(100_000)),
+        ("records", records(20_000)),
+        ("pretty", pretty(20_000)),
+    ];
+    println!("{:>8}  {:>8}  {:>12}  {:>12}", "shape", "size", "owned MB/s", "borrowed MB/s");
+    for (name, input) in &documents {
+        let owned = throughput(input, |text| {
+            black_box(JsonParser::with_mode(text, ParseMode::Strict).parse().unwrap());
+        });
+        let borrowed = throughput(input, |text| {
+            black_box(borrowed::parse(text, ParseMode::Strict).unwrap());
+        });
+        println!("{:>8}  {:>7}K  {:>12.1}  {:>12.1}", name, input.len() / 1024, owned, borrowed);
+    }
+}
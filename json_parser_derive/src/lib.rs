@@ -0,0 +1,326 @@
+//! `#[derive(ToJson, FromJson)]` for the `json_parser` crate.
+//!
+//! Field attributes, written `#[json(...)]`:
+//! - `rename = "name"`: use another key for the field.
+//! - `default` or `default = "path::to::fn"`: fill a missing member.
+//! - `skip`: never write the field and fill it with `Default::default()`.
+//! - `flatten`: read and write the field's members inline in the parent object.
+//!
+//! Enum unit variants are written as strings, other variants as an object
+//! with the variant name as the only key.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Ident, LitStr, Path};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_json(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_json(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    default: Option<Option<Path>>,
+    skip: bool,
+    flatten: bool,
+}
+
+fn parse_attrs(attrs: &[syn::Attribute]) -> syn::Result<FieldAttrs> {
+    let mut parsed = FieldAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                parsed.default = Some(match meta.value() {
+                    Ok(value) => Some(value.parse::<LitStr>()?.parse()?),
+                    Err(_) => None,
+                });
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                parsed.flatten = true;
+            } else {
+                return Err(meta.error("expected rename, default, skip or flatten"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+struct FieldInfo {
+    /// The expression or binding the value is read from when writing.
+    binding: TokenStream2,
+    member: Option<Ident>,
+    key: String,
+    attrs: FieldAttrs,
+}
+
+fn field_infos(fields: &Fields, binding: impl Fn(usize, Option<&Ident>) -> TokenStream2) -> syn::Result<Vec<FieldInfo>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let attrs = parse_attrs(&field.attrs)?;
+            let name = field.ident.as_ref().map(|ident| ident.to_string());
+            let name = name.map(|name| name.trim_start_matches("r#").to_string());
+            Ok(FieldInfo {
+                binding: binding(i, field.ident.as_ref()),
+                member: field.ident.clone(),
+                key: attrs.rename.clone().or(name).unwrap_or_else(|| i.to_string()),
+                attrs,
+            })
+        })
+        .collect()
+}
+
+fn add_bounds(input: &mut DeriveInput, bound: Path) {
+    let params: Vec<Ident> = input.generics.type_params().map(|param| param.ident.clone()).collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+}
+
+fn fields_to_json(fields: &Fields, infos: &[FieldInfo]) -> TokenStream2 {
+    let to_json = quote!(::json_parser::libs::convert::ToJson::to_json);
+    let json_value = quote!(::json_parser::libs::json_parser::JsonValue);
+    match fields {
+        Fields::Named(_) => {
+            let members = infos.iter().filter(|info| !info.attrs.skip).map(|info| {
+                let binding = &info.binding;
+                let key = &info.key;
+                if info.attrs.flatten {
+                    quote! {
+                        if let #json_value::Object(inner) = #to_json(#binding) {
+                            members.extend(inner);
+                        }
+                    }
+                } else {
+                    quote!(members.insert(::std::string::String::from(#key), #to_json(#binding));)
+                }
+            });
+            quote! {{
+                let mut members = ::json_parser::libs::map::JsonMap::new();
+                #(#members)*
+                #json_value::Object(members)
+            }}
+        }
+        Fields::Unnamed(_) if infos.len() == 1 => {
+            let binding = &infos[0].binding;
+            quote!(#to_json(#binding))
+        }
+        Fields::Unnamed(_) => {
+            let elements = infos.iter().filter(|info| !info.attrs.skip).map(|info| {
+                let binding = &info.binding;
+                quote!(#to_json(#binding))
+            });
+            quote!(#json_value::Array(::std::vec![#(#elements),*]))
+        }
+        Fields::Unit => quote!(#json_value::Null),
+    }
+}
+
+/// An expression evaluating to `Result<Self, FromJsonError>` that builds
+/// `constructor` from `value`.
+fn fields_from_json(fields: &Fields, infos: &[FieldInfo], constructor: TokenStream2) -> TokenStream2 {
+    let convert = quote!(::json_parser::libs::convert);
+    let default_value = quote!(::std::default::Default::default());
+    match fields {
+        Fields::Named(_) => {
+            let members = infos.iter().map(|info| {
+                let member = &info.member;
+                let key = &info.key;
+                let value = if info.attrs.skip {
+                    default_value.clone()
+                } else if info.attrs.flatten {
+                    quote!(#convert::FromJson::from_json(value)?)
+                } else {
+                    match &info.attrs.default {
+                        Some(Some(function)) => quote!(#convert::field_or_else(object, #key, #function)?),
+                        Some(None) => quote!(#convert::field_or_else(object, #key, ::std::default::Default::default)?),
+                        None => quote!(#convert::field(object, #key)?),
+                    }
+                };
+                quote!(#member: #value)
+            });
+            quote! {{
+                let object = #convert::expect_object(value)?;
+                ::std::result::Result::Ok(#constructor { #(#members),* })
+            }}
+        }
+        Fields::Unnamed(_) if infos.len() == 1 => {
+            quote!(#convert::FromJson::from_json(value).map(#constructor))
+        }
+        Fields::Unnamed(_) => {
+            // Skipped fields take no element, so the others move up.
+            let len = infos.iter().filter(|info| !info.attrs.skip).count();
+            let mut index = 0usize;
+            let elements = infos.iter().map(|info| {
+                if info.attrs.skip {
+                    return default_value.clone();
+                }
+                index += 1;
+                let i = index - 1;
+                quote!(#convert::element(array, #i)?)
+            });
+            quote! {{
+                let array = #convert::expect_array(value, ::std::option::Option::Some(#len))?;
+                ::std::result::Result::Ok(#constructor(#(#elements),*))
+            }}
+        }
+        Fields::Unit => quote! {
+            match value {
+                ::json_parser::libs::json_parser::JsonValue::Null => ::std::result::Result::Ok(#constructor),
+                _ => ::std::result::Result::Err(#convert::FromJsonError::expected("null", value)),
+            }
+        },
+    }
+}
+
+fn variant_key(variant: &syn::Variant) -> syn::Result<String> {
+    Ok(parse_attrs(&variant.attrs)?.rename.unwrap_or_else(|| variant.ident.to_string()))
+}
+
+fn expand_to_json(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    add_bounds(&mut input, parse_quote!(::json_parser::libs::convert::ToJson));
+    let name = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let infos = field_infos(&data.fields, |i, ident| match ident {
+                Some(ident) => quote!(&self.#ident),
+                None => {
+                    let index = syn::Index::from(i);
+                    quote!(&self.#index)
+                }
+            })?;
+            fields_to_json(&data.fields, &infos)
+        }
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let key = variant_key(variant)?;
+                    let infos = field_infos(&variant.fields, |i, ident| {
+                        let binding = ident.cloned().unwrap_or_else(|| format_ident!("__field{}", i));
+                        quote!(#binding)
+                    })?;
+                    let named = infos.iter().filter(|info| !info.attrs.skip).map(|info| &info.binding);
+                    let unnamed = infos
+                        .iter()
+                        .map(|info| if info.attrs.skip { quote!(_) } else { info.binding.clone() });
+                    let pattern = match &variant.fields {
+                        Fields::Named(_) => quote!(Self::#ident { #(#named,)* .. }),
+                        Fields::Unnamed(_) => quote!(Self::#ident(#(#unnamed),*)),
+                        Fields::Unit => {
+                            return Ok(quote! {
+                                Self::#ident => ::json_parser::libs::json_parser::JsonValue::String(
+                                    ::std::string::String::from(#key),
+                                ),
+                            });
+                        }
+                    };
+                    let payload = fields_to_json(&variant.fields, &infos);
+                    Ok(quote! {
+                        #pattern => ::json_parser::libs::json_parser::JsonValue::Object(
+                            ::std::iter::once((::std::string::String::from(#key), #payload)).collect(),
+                        ),
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "ToJson cannot be derived for unions")),
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::json_parser::libs::convert::ToJson for #name #type_generics #where_clause {
+            fn to_json(&self) -> ::json_parser::libs::json_parser::JsonValue {
+                #body
+            }
+        }
+    })
+}
+
+fn expand_from_json(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    add_bounds(&mut input, parse_quote!(::json_parser::libs::convert::FromJson));
+    let name = &input.ident;
+    let convert = quote!(::json_parser::libs::convert);
+    let json_value = quote!(::json_parser::libs::json_parser::JsonValue);
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let infos = field_infos(&data.fields, |_, _| TokenStream2::new())?;
+            fields_from_json(&data.fields, &infos, quote!(Self))
+        }
+        Data::Enum(data) => {
+            let mut unit_arms = Vec::new();
+            let mut data_arms = Vec::new();
+            let mut keys = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let key = variant_key(variant)?;
+                keys.push(key.clone());
+                if let Fields::Unit = variant.fields {
+                    unit_arms.push(quote!(#key => ::std::result::Result::Ok(Self::#ident),));
+                    continue;
+                }
+                let infos = field_infos(&variant.fields, |_, _| TokenStream2::new())?;
+                let build = fields_from_json(&variant.fields, &infos, quote!(Self::#ident));
+                data_arms.push(quote! {
+                    #key => (|| -> ::std::result::Result<Self, #convert::FromJsonError> { #build })()
+                        .map_err(|e| e.at_key(#key)),
+                });
+            }
+            let expected = format!("expected one of {}", keys.join(", "));
+            quote! {
+                match value {
+                    #json_value::String(variant) => match variant.as_str() {
+                        #(#unit_arms)*
+                        _ => ::std::result::Result::Err(#convert::FromJsonError::new(
+                            ::std::format!("unknown variant '{}' ({})", variant, #expected),
+                        )),
+                    },
+                    #json_value::Object(members) if members.len() == 1 => {
+                        let (variant, value) = &members.as_slice()[0];
+                        match variant.as_str() {
+                            #(#data_arms)*
+                            _ => ::std::result::Result::Err(#convert::FromJsonError::new(
+                                ::std::format!("unknown variant '{}' ({})", variant, #expected),
+                            )),
+                        }
+                    }
+                    _ => ::std::result::Result::Err(#convert::FromJsonError::expected(
+                        "string or object with a single key",
+                        value,
+                    )),
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "FromJson cannot be derived for unions")),
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #convert::FromJson for #name #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn from_json(value: &#json_value) -> ::std::result::Result<Self, #convert::FromJsonError> {
+                #body
+            }
+        }
+    })
+}
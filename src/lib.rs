@@ -1,2 +1,10 @@
 pub mod libs;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+
+pub use libs::convert::{FromJson, ToJson};
+#[cfg(feature = "derive")]
+pub use json_parser_derive::{FromJson, ToJson};
+#[cfg(feature = "serde")]
+pub use libs::de::{from_str, Deserializer};
+#[cfg(feature = "serde")]
+pub use libs::ser::{to_string, to_string_pretty, Serializer};
\ No newline at end of file
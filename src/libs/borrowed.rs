@@ -0,0 +1,352 @@
+    use std::borrow::Cow;
+    use std::fmt;
+
+    use crate::libs::json_parser::{self, DuplicateKeys, JsonParser, ParseMode};
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{parse_path, PathSegment};
+
+    /// A parsed value whose keys and strings point into the input text.
+    ///
+    /// Only strings containing escapes are allocated, which makes this the
+    /// cheaper choice for read-only queries. Objects keep their members as
+    /// written; lookups see the last of any repeated key, as the owned
+    /// value does with the default [`DuplicateKeys::Last`].
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum JsonValue<'a> {
+        Object(Vec<(Cow<'a, str>, JsonValue<'a>)>),
+        Array(Vec<JsonValue<'a>>),
+        String(Cow<'a, str>),
+        Number(f64),
+        Boolean(bool),
+        Null,
+    }
+
+    /// Parses `input` without copying its strings.
+    pub fn parse(input: &str, mode: ParseMode) -> Result<JsonValue<'_>, String> {
+        let mut parser = JsonParser::with_mode(input, mode);
+        let value = parser.parse_borrowed()?;
+        parser.finish()?;
+        Ok(value)
+    }
+
+    impl<'a> JsonParser<'a> {
+        pub fn parse_borrowed(&mut self) -> Result<JsonValue<'a>, String> {
+            self.skip_trivia()?;
+            match self.peek_byte() {
+                Some(b'{') => self.parse_borrowed_object(),
+                Some(b'[') => self.parse_borrowed_array(),
+                Some(b'"') | Some(b'\'') => self.parse_string_cow().map(JsonValue::String),
+                Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'I' | b'N') => {
+                    match self.parse_number()? {
+                        json_parser::JsonValue::Number(n) => Ok(JsonValue::Number(n)),
+                        _ => Err("Invalid number format".to_string()),
+                    }
+                }
+                Some(b't') | Some(b'f') => match self.parse_boolean()? {
+                    json_parser::JsonValue::Boolean(b) => Ok(JsonValue::Boolean(b)),
+                    _ => Err("Invalid boolean".to_string()),
+                },
+                Some(b'n') => self.parse_null().map(|_| JsonValue::Null),
+                _ => Err("Invalid JSON format".to_string()),
+            }
+        }
+
+        fn parse_borrowed_object(&mut self) -> Result<JsonValue<'a>, String> {
+            self.position += 1;
+            let mut object: Vec<(Cow<'a, str>, JsonValue<'a>)> = Vec::new();
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b'}') {
+                self.position += 1;
+                return Ok(JsonValue::Object(object));
+            }
+            loop {
+                let key_position = self.position;
+                let key = self.parse_key_cow()?;
+
+                self.skip_trivia()?;
+                if self.consume_byte() != Some(b':') {
+                    return Err("Expected ':' after key".to_string());
+                }
+                self.skip_trivia()?;
+
+                let value = self.parse_borrowed()?;
+                // Repeats are only looked for when the policy needs them, so
+                // the default stays linear in the size of the object.
+                let repeated = self.duplicate_keys != DuplicateKeys::Last && object.iter().any(|(k, _)| *k == key);
+                match self.duplicate_keys {
+                    DuplicateKeys::First if repeated => {}
+                    DuplicateKeys::Error if repeated => {
+                        return Err(format!("Duplicate key '{}' at position {}", key, key_position));
+                    }
+                    _ => object.push((key, value)),
+                }
+
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b'}') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
+                    }
+                    Some(b'}') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err("Expected ',' or '}'".to_string()),
+                }
+            }
+            Ok(JsonValue::Object(object))
+        }
+
+        fn parse_borrowed_array(&mut self) -> Result<JsonValue<'a>, String> {
+            self.position += 1;
+            let mut array = Vec::new();
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b']') {
+                self.position += 1;
+                return Ok(JsonValue::Array(array));
+            }
+            loop {
+                array.push(self.parse_borrowed()?);
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b']') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
+                    }
+                    Some(b']') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err("Expected ',' or ']'".to_string()),
+                }
+            }
+            Ok(JsonValue::Array(array))
+        }
+    }
+
+    impl<'a> JsonValue<'a> {
+        /// Copies every borrowed string, detaching the value from the input.
+        pub fn into_owned(self) -> json_parser::JsonValue {
+            match self {
+                JsonValue::Object(obj) => json_parser::JsonValue::Object(
+                    obj.into_iter()
+                        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                        .collect::<JsonMap>(),
+                ),
+                JsonValue::Array(arr) => {
+                    json_parser::JsonValue::Array(arr.into_iter().map(JsonValue::into_owned).collect())
+                }
+                JsonValue::String(s) => json_parser::JsonValue::String(s.into_owned()),
+                JsonValue::Number(n) => json_parser::JsonValue::Number(n),
+                JsonValue::Boolean(b) => json_parser::JsonValue::Boolean(b),
+                JsonValue::Null => json_parser::JsonValue::Null,
+            }
+        }
+
+        pub fn get_index(&self, index: usize) -> Option<&JsonValue<'a>> {
+            match self {
+                JsonValue::Array(arr) => arr.get(index),
+                _ => None,
+            }
+        }
+
+        /// Looks up a path such as `details.city` or `users[0].name`.
+        pub fn get(&self, key_path: &str) -> Option<&JsonValue<'a>> {
+            self.get_path(&parse_path(key_path).ok()?)
+        }
+
+        pub fn get_path(&self, segments: &[PathSegment]) -> Option<&JsonValue<'a>> {
+            let mut current_value = self;
+            for segment in segments {
+                current_value = match (current_value, segment) {
+                    (JsonValue::Object(obj), PathSegment::Key(key)) => {
+                        obj.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)?
+                    }
+                    (JsonValue::Array(arr), PathSegment::Index(index)) => arr.get(*index)?,
+                    _ => return None,
+                };
+            }
+            Some(current_value)
+        }
+
+        /// Finds the strings equal to `target`, ignoring case, in the same
+        /// form as [`json_parser::JsonValue::get_all_occurrences`].
+        pub fn get_all_occurrences(&self, target: &str) -> (usize, Vec<String>, Vec<Option<usize>>) {
+            let (keys, indexes): (Vec<String>, Vec<Option<usize>>) =
+                self.find_strings(target).into_iter().map(|(key, index, _)| (key, index)).unzip();
+            (keys.len(), keys, indexes)
+        }
+
+        /// The strings equal to `target`, ignoring case, with the key path of
+        /// each, the index when the string is an array element, and the
+        /// string as written.
+        pub fn find_strings(&self, target: &str) -> Vec<(String, Option<usize>, &str)> {
+            let target = target.to_lowercase();
+            let mut found = Vec::new();
+
+            fn matches<'v>(value: &'v JsonValue, target: &str) -> Option<&'v str> {
+                let JsonValue::String(s) = value else {
+                    return None;
+                };
+                let equal = if s.is_ascii() {
+                    s.eq_ignore_ascii_case(target)
+                } else {
+                    s.to_lowercase() == target
+                };
+                equal.then_some(s)
+            }
+
+            // The path is kept as a stack of keys and only joined for matches.
+            fn search<'v>(
+                json: &'v JsonValue,
+                target: &str,
+                path: &mut Vec<&'v str>,
+                found: &mut Vec<(String, Option<usize>, &'v str)>,
+            ) {
+                match json {
+                    JsonValue::Object(obj) => {
+                        for (key, value) in obj {
+                            path.push(key);
+                            if let Some(s) = matches(value, target) {
+                                found.push((path.join("."), None, s));
+                            }
+                            search(value, target, path, found);
+                            path.pop();
+                        }
+                    }
+                    JsonValue::Array(arr) => {
+                        for (i, value) in arr.iter().enumerate() {
+                            if let Some(s) = matches(value, target) {
+                                found.push((path.join("."), Some(i), s));
+                            }
+                            search(value, target, path, found);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            search(self, &target, &mut Vec::new(), &mut found);
+            found
+        }
+    }
+
+    /// Same colored layout as the owned value.
+    impl fmt::Display for JsonValue<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.clone().into_owned())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"{"name": "Ada", "escaped": "a\"bé", "tags": ["x", "ADA"], "nested": {"name": "ada"}, "n": -1.5e2, "ok": true, "none": null}"#;
+
+        fn owned(input: &str, mode: ParseMode) -> Result<json_parser::JsonValue, String> {
+            JsonParser::with_mode(input, mode).parse()
+        }
+
+        #[test]
+        fn plain_strings_are_borrowed() {
+            let value = parse(DOCUMENT, ParseMode::Strict).unwrap();
+            let JsonValue::Object(members) = &value else {
+                panic!("expected an object");
+            };
+            assert!(members.iter().all(|(key, _)| matches!(key, Cow::Borrowed(_))));
+            assert!(matches!(value.get("name"), Some(JsonValue::String(Cow::Borrowed("Ada")))));
+            match value.get("escaped") {
+                Some(JsonValue::String(Cow::Owned(s))) => assert_eq!(s, "a\"bé"),
+                other => panic!("expected an owned string, found {:?}", other),
+            }
+        }
+
+        #[test]
+        fn values_match_the_owned_parser() {
+            let inputs = [
+                (DOCUMENT, ParseMode::Strict),
+                ("[1, [2, {}], []]", ParseMode::Strict),
+                ("// c\n{\"a\": [1, 2,], /* d */ \"b\": {},}", ParseMode::Jsonc),
+                ("{unquoted: 'single', hex: 0xff, lead: .5, trail: 5., inf: -Infinity, plus: +1,}", ParseMode::Json5),
+            ];
+            for (input, mode) in inputs {
+                let borrowed = parse(input, mode).unwrap().into_owned();
+                assert_eq!(borrowed.to_json_string(), owned(input, mode).unwrap().to_json_string(), "{}", input);
+            }
+        }
+
+        #[test]
+        fn errors_match_the_owned_parser() {
+            let inputs = [
+                ("[1, 2,]", ParseMode::Strict),
+                ("{'a': 1}", ParseMode::Jsonc),
+                ("[1] 2", ParseMode::Strict),
+                ("{\"a\" 1}", ParseMode::Strict),
+                ("[01]", ParseMode::Json5),
+                ("", ParseMode::Strict),
+            ];
+            for (input, mode) in inputs {
+                let error = parse(input, mode).unwrap_err();
+                assert_eq!(Err(error), owned(input, mode).map(|_| ()), "{}", input);
+            }
+        }
+
+        #[test]
+        fn duplicate_keys_follow_the_policy() {
+            let input = r#"{"a": 1, "a": 2}"#;
+            let value = parse(input, ParseMode::Strict).unwrap();
+            assert_eq!(value.get("a"), Some(&JsonValue::Number(2.0)));
+            assert_eq!(value.into_owned().to_json_string(), r#"{"a":2}"#);
+
+            let mut first = JsonParser::new(input).with_duplicate_keys(DuplicateKeys::First);
+            assert_eq!(first.parse_borrowed().unwrap().get("a"), Some(&JsonValue::Number(1.0)));
+            let mut error = JsonParser::new(input).with_duplicate_keys(DuplicateKeys::Error);
+            assert_eq!(error.parse_borrowed(), Err("Duplicate key 'a' at position 9".to_string()));
+        }
+
+        #[test]
+        fn paths_are_looked_up() {
+            let value = parse(DOCUMENT, ParseMode::Strict).unwrap();
+            assert_eq!(value.get("tags[1]"), Some(&JsonValue::String(Cow::Borrowed("ADA"))));
+            assert_eq!(value.get("nested.name"), Some(&JsonValue::String(Cow::Borrowed("ada"))));
+            assert_eq!(value.get("n"), Some(&JsonValue::Number(-150.0)));
+            assert_eq!(value.get("tags").and_then(|tags| tags.get_index(0)), Some(&JsonValue::String(Cow::Borrowed("x"))));
+            assert_eq!(value.get("tags[2]"), None);
+            assert_eq!(value.get("name.deeper"), None);
+            assert_eq!(value.get("tags["), None);
+        }
+
+        #[test]
+        fn search_matches_the_owned_value() {
+            let value = parse(DOCUMENT, ParseMode::Strict).unwrap();
+            let found = value.find_strings("ada");
+            assert_eq!(
+                found,
+                [
+                    ("name".to_string(), None, "Ada"),
+                    ("tags".to_string(), Some(1), "ADA"),
+                    ("nested.name".to_string(), None, "ada"),
+                ]
+            );
+            let owned = owned(DOCUMENT, ParseMode::Strict).unwrap();
+            let target = json_parser::JsonValue::String("ADA".to_string());
+            assert_eq!(value.get_all_occurrences("ADA"), owned.get_all_occurrences(&target));
+            assert_eq!(value.find_strings("missing"), []);
+            let unicode = parse(r#"["ÉTÉ", "été"]"#, ParseMode::Strict).unwrap();
+            assert_eq!(unicode.get_all_occurrences("Été").0, 2);
+        }
+    }
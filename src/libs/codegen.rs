@@ -0,0 +1,396 @@
+    use std::collections::HashSet;
+    use std::fmt::Write;
+    use std::str::FromStr;
+
+    use crate::libs::json_parser::JsonValue;
+
+    /// The languages type definitions can be generated for.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Language {
+        Rust,
+    }
+
+    impl FromStr for Language {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "rust" | "rs" => Ok(Language::Rust),
+                _ => Err(format!("Unsupported language '{}' (expected rust)", s)),
+            }
+        }
+    }
+
+    const RUST_KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn",
+        "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+        "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self", "static",
+        "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+        "where", "while", "yield",
+    ];
+
+    /// The type observed at one place of the sample, unified over every
+    /// value found there.
+    #[derive(Clone, Debug, PartialEq)]
+    enum Ty {
+        /// Only `null` or nothing at all was seen.
+        Unknown,
+        Bool,
+        Integer,
+        Float,
+        String,
+        Array(Box<Shape>),
+        Object(Vec<(String, Shape)>),
+        /// Incompatible types were seen.
+        Any,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Shape {
+        ty: Ty,
+        /// The value was `null` or missing at least once.
+        optional: bool,
+    }
+
+    impl Shape {
+        fn of(value: &JsonValue) -> Shape {
+            let ty = match value {
+                JsonValue::Null => return Shape { ty: Ty::Unknown, optional: true },
+                JsonValue::Boolean(_) => Ty::Bool,
+                JsonValue::Number(n) if n.fract() == 0.0 && n.abs() <= i64::MAX as f64 => Ty::Integer,
+                JsonValue::Number(_) => Ty::Float,
+                JsonValue::String(_) => Ty::String,
+                JsonValue::Array(arr) => {
+                    let items = arr
+                        .iter()
+                        .map(Shape::of)
+                        .fold(Shape { ty: Ty::Unknown, optional: false }, Shape::unify);
+                    Ty::Array(Box::new(items))
+                }
+                JsonValue::Object(obj) => {
+                    let mut fields: Vec<(String, Shape)> = Vec::new();
+                    for (key, child) in obj {
+                        let shape = Shape::of(child);
+                        match fields.iter_mut().find(|(k, _)| k == key) {
+                            Some((_, existing)) => *existing = existing.clone().unify(shape),
+                            None => fields.push((key.clone(), shape)),
+                        }
+                    }
+                    Ty::Object(fields)
+                }
+            };
+            Shape { ty, optional: false }
+        }
+
+        fn unify(self, other: Shape) -> Shape {
+            let optional = self.optional || other.optional;
+            let ty = match (self.ty, other.ty) {
+                (Ty::Unknown, ty) | (ty, Ty::Unknown) => ty,
+                (Ty::Integer, Ty::Float) | (Ty::Float, Ty::Integer) => Ty::Float,
+                (Ty::Array(a), Ty::Array(b)) => Ty::Array(Box::new(a.unify(*b))),
+                (Ty::Object(a), Ty::Object(b)) => {
+                    let mut b = b;
+                    let mut fields = Vec::new();
+                    for (key, shape) in a {
+                        match b.iter().position(|(k, _)| *k == key) {
+                            Some(i) => fields.push((key, shape.unify(b.remove(i).1))),
+                            None => fields.push((key, Shape { optional: true, ..shape })),
+                        }
+                    }
+                    fields.extend(b.into_iter().map(|(key, shape)| (key, Shape { optional: true, ..shape })));
+                    Ty::Object(fields)
+                }
+                (a, b) if a == b => a,
+                _ => Ty::Any,
+            };
+            Shape { ty, optional }
+        }
+    }
+
+    /// Splits a key into lowercase words at separators and case changes.
+    fn words(key: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut current = String::new();
+        let mut previous: Option<char> = None;
+        for c in key.chars() {
+            if !c.is_alphanumeric() {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                previous = None;
+                continue;
+            }
+            let boundary = match previous {
+                Some(p) => c.is_uppercase() && (p.is_lowercase() || p.is_numeric()),
+                None => false,
+            };
+            if boundary && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            current.extend(c.to_lowercase());
+            previous = Some(c);
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+        words
+    }
+
+    fn snake_case(key: &str) -> String {
+        let name = words(key).join("_");
+        if name.is_empty() {
+            "field".to_string()
+        } else if name.starts_with(|c: char| c.is_numeric()) {
+            format!("field_{}", name)
+        } else {
+            name
+        }
+    }
+
+    fn pascal_case(key: &str) -> String {
+        let name: String = words(key)
+            .iter()
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+            })
+            .collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_numeric()) {
+            format!("Type{}", name)
+        } else {
+            name
+        }
+    }
+
+    /// Strips a plural `s` so that `users: [...]` holds `User` elements.
+    fn singular(name: &str) -> String {
+        match name.strip_suffix("ies") {
+            Some(stem) if !stem.is_empty() => format!("{}y", stem),
+            _ => match name.strip_suffix('s') {
+                Some(stem) if !stem.is_empty() && !stem.ends_with(['s', 'u', 'i']) => stem.to_string(),
+                _ => format!("{}Item", name),
+            },
+        }
+    }
+
+    struct RustGenerator {
+        used_names: HashSet<String>,
+        /// Struct definitions still to be written, with their fields.
+        pending: Vec<(String, Vec<(String, Shape)>)>,
+    }
+
+    impl RustGenerator {
+        fn unique_name(&mut self, name: String) -> String {
+            let mut candidate = name.clone();
+            let mut n = 2;
+            while !self.used_names.insert(candidate.clone()) {
+                candidate = format!("{}{}", name, n);
+                n += 1;
+            }
+            candidate
+        }
+
+        fn type_name(&mut self, shape: &Shape, name: &str) -> String {
+            let inner = match &shape.ty {
+                Ty::Unknown | Ty::Any => "serde_json::Value".to_string(),
+                Ty::Bool => "bool".to_string(),
+                Ty::Integer => "i64".to_string(),
+                Ty::Float => "f64".to_string(),
+                Ty::String => "String".to_string(),
+                Ty::Array(items) => format!("Vec<{}>", self.type_name(items, &singular(name))),
+                Ty::Object(fields) => {
+                    let struct_name = self.unique_name(name.to_string());
+                    self.pending.push((struct_name.clone(), fields.clone()));
+                    struct_name
+                }
+            };
+            if shape.optional {
+                format!("Option<{}>", inner)
+            } else {
+                inner
+            }
+        }
+
+        fn write_struct(&mut self, out: &mut String, name: &str, fields: &[(String, Shape)]) {
+            let _ = writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]");
+            let _ = writeln!(out, "pub struct {} {{", name);
+            let mut field_names = HashSet::new();
+            for (key, shape) in fields {
+                let base = snake_case(key);
+                let mut field = base.clone();
+                let mut n = 2;
+                while !field_names.insert(field.clone()) {
+                    field = format!("{}_{}", base, n);
+                    n += 1;
+                }
+                let ty = self.type_name(shape, &pascal_case(key));
+                // Raw identifiers keep their name in serde, except for the
+                // keywords that cannot be written as raw identifiers.
+                let ident = match field.as_str() {
+                    "self" | "Self" | "super" | "crate" => format!("{}_", field),
+                    _ if RUST_KEYWORDS.contains(&field.as_str()) => format!("r#{}", field),
+                    _ => field,
+                };
+                if ident.trim_start_matches("r#") != key {
+                    let _ = writeln!(out, "    #[serde(rename = {:?})]", key);
+                }
+                let _ = writeln!(out, "    pub {}: {},", ident, ty);
+            }
+            let _ = writeln!(out, "}}");
+        }
+
+        fn generate(mut self, name: &str, shape: &Shape) -> String {
+            let mut out = String::from("use serde::{Deserialize, Serialize};\n");
+            self.used_names.insert(name.to_string());
+            match &shape.ty {
+                Ty::Object(fields) => self.pending.push((name.to_string(), fields.clone())),
+                _ => {
+                    let root = self.type_name(shape, name);
+                    let _ = write!(out, "\npub type {} = {};\n", name, root);
+                }
+            }
+            let mut i = 0;
+            while i < self.pending.len() {
+                let (struct_name, fields) = self.pending[i].clone();
+                out.push('\n');
+                self.write_struct(&mut out, &struct_name, &fields);
+                i += 1;
+            }
+            out
+        }
+    }
+
+    /// Generates type definitions describing every sample.
+    ///
+    /// Array elements and the samples themselves are unified into a single
+    /// shape, fields that are sometimes missing or `null` become optional,
+    /// numbers become integers unless a fraction was seen, and keys that are
+    /// not valid identifiers are renamed.
+    pub fn generate_types(samples: &[JsonValue], name: &str, language: Language) -> Result<String, String> {
+        let valid_name = name.starts_with(|c: char| c.is_ascii_uppercase())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !RUST_KEYWORDS.contains(&name);
+        if !valid_name {
+            return Err(format!("Invalid type name '{}' (expected a name such as Config)", name));
+        }
+        let shape = samples
+            .iter()
+            .map(Shape::of)
+            .reduce(Shape::unify)
+            .ok_or_else(|| "No sample to generate types from".to_string())?;
+        match language {
+            Language::Rust => {
+                let generator = RustGenerator {
+                    used_names: HashSet::new(),
+                    pending: Vec::new(),
+                };
+                Ok(generator.generate(name, &shape))
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn generate(samples: &[&str], name: &str) -> String {
+            let samples: Vec<JsonValue> = samples.iter().map(|sample| JsonParser::new(sample).parse().unwrap()).collect();
+            generate_types(&samples, name, Language::Rust).unwrap()
+        }
+
+        #[test]
+        fn languages_are_parsed() {
+            assert_eq!("rust".parse::<Language>(), Ok(Language::Rust));
+            assert_eq!("rs".parse::<Language>(), Ok(Language::Rust));
+            assert!("go".parse::<Language>().unwrap_err().contains("Unsupported language 'go'"));
+        }
+
+        #[test]
+        fn names_are_converted() {
+            assert_eq!(words("userID2Name"), ["user", "id2", "name"]);
+            assert_eq!(snake_case("firstName"), "first_name");
+            assert_eq!(snake_case("HTTP-status code"), "http_status_code");
+            assert_eq!(snake_case("2fa"), "field_2fa");
+            assert_eq!(snake_case("--"), "field");
+            assert_eq!(pascal_case("line_items"), "LineItems");
+            assert_eq!(pascal_case("3d"), "Type3d");
+            assert_eq!(singular("Users"), "User");
+            assert_eq!(singular("Entries"), "Entry");
+            assert_eq!(singular("Status"), "StatusItem");
+            assert_eq!(singular("Data"), "DataItem");
+        }
+
+        #[test]
+        fn objects_become_structs() {
+            let code = generate(&[r#"{"name": "a", "age": 3, "score": 1.5, "admin": true, "address": {"city": "x"}}"#], "User");
+            assert_eq!(
+                code,
+                "use serde::{Deserialize, Serialize};\n\
+                 \n\
+                 #[derive(Debug, Clone, Serialize, Deserialize)]\n\
+                 pub struct User {\n    \
+                     pub name: String,\n    \
+                     pub age: i64,\n    \
+                     pub score: f64,\n    \
+                     pub admin: bool,\n    \
+                     pub address: Address,\n\
+                 }\n\
+                 \n\
+                 #[derive(Debug, Clone, Serialize, Deserialize)]\n\
+                 pub struct Address {\n    \
+                     pub city: String,\n\
+                 }\n"
+            );
+        }
+
+        #[test]
+        fn samples_and_elements_are_unified() {
+            let code = generate(
+                &[
+                    r#"{"id": 1, "tags": ["a"], "items": [{"price": 1}, {"price": 2.5, "note": "x"}], "extra": null}"#,
+                    r#"{"id": 2, "tags": [], "items": [], "mixed": 1}"#,
+                    r#"{"id": 3, "tags": [], "items": [], "mixed": "one"}"#,
+                ],
+                "Order",
+            );
+            assert!(code.contains("    pub id: i64,\n"), "{}", code);
+            assert!(code.contains("    pub tags: Vec<String>,\n"), "{}", code);
+            assert!(code.contains("    pub items: Vec<Item>,\n"), "{}", code);
+            assert!(code.contains("    pub extra: Option<serde_json::Value>,\n"), "{}", code);
+            assert!(code.contains("    pub mixed: Option<serde_json::Value>,\n"), "{}", code);
+            assert!(code.contains("pub struct Item {\n    pub price: f64,\n    pub note: Option<String>,\n}"), "{}", code);
+        }
+
+        #[test]
+        fn keys_are_renamed_and_deduplicated() {
+            let code = generate(&[r#"{"type": 1, "self": 2, "first-name": "a", "firstName": "b", "user": {}, "users": [{"user": {}}]}"#], "Root");
+            assert!(code.contains("    pub r#type: i64,\n"), "{}", code);
+            assert!(code.contains("    #[serde(rename = \"self\")]\n    pub self_: i64,\n"), "{}", code);
+            assert!(code.contains("    #[serde(rename = \"first-name\")]\n    pub first_name: String,\n"), "{}", code);
+            assert!(code.contains("    #[serde(rename = \"firstName\")]\n    pub first_name_2: String,\n"), "{}", code);
+            for name in ["User", "User2", "User3"] {
+                assert!(code.contains(&format!("pub struct {} {{", name)), "{}", code);
+            }
+        }
+
+        #[test]
+        fn other_roots_become_aliases() {
+            assert_eq!(
+                generate(&["[1, 2]"], "Numbers"),
+                "use serde::{Deserialize, Serialize};\n\npub type Numbers = Vec<i64>;\n"
+            );
+            let code = generate(&[r#"[{"a": 1}]"#], "Rows");
+            assert!(code.contains("pub type Rows = Vec<Row>;\n"), "{}", code);
+            assert!(code.contains("pub struct Row {\n    pub a: i64,\n}"), "{}", code);
+        }
+
+        #[test]
+        fn invalid_input_is_rejected() {
+            for name in ["user", "Self", "My-Type", ""] {
+                let error = generate_types(&[JsonValue::Null], name, Language::Rust).unwrap_err();
+                assert!(error.starts_with("Invalid type name"), "{}", error);
+            }
+            assert_eq!(generate_types(&[], "Root", Language::Rust), Err("No sample to generate types from".to_string()));
+        }
+    }
@@ -0,0 +1,358 @@
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt;
+    use std::hash::BuildHasher;
+
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{format_path, PathSegment};
+
+    /// A value that could not be converted, with the path of the offending
+    /// member from the root of the document.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct FromJsonError {
+        pub path: Vec<PathSegment>,
+        pub message: String,
+    }
+
+    impl FromJsonError {
+        pub fn new(message: impl Into<String>) -> Self {
+            Self { path: Vec::new(), message: message.into() }
+        }
+
+        /// An error for a value of the wrong type.
+        pub fn expected(expected: &str, found: &JsonValue) -> Self {
+            Self::new(format!("expected {}, found {}", expected, found.type_name()))
+        }
+
+        /// Prefixes the path with the key of the member the error occurred in.
+        pub fn at_key(mut self, key: &str) -> Self {
+            self.path.insert(0, PathSegment::Key(key.to_string()));
+            self
+        }
+
+        /// Prefixes the path with the index of the element the error occurred in.
+        pub fn at_index(mut self, index: usize) -> Self {
+            self.path.insert(0, PathSegment::Index(index));
+            self
+        }
+    }
+
+    impl fmt::Display for FromJsonError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            if self.path.is_empty() {
+                write!(f, "(root): {}", self.message)
+            } else {
+                write!(f, "{}: {}", format_path(&self.path), self.message)
+            }
+        }
+    }
+
+    impl std::error::Error for FromJsonError {}
+
+    pub trait ToJson {
+        fn to_json(&self) -> JsonValue;
+    }
+
+    pub trait FromJson: Sized {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError>;
+
+        /// The value to use when an object member is absent, if absence is
+        /// allowed at all. `Option` uses this to turn a missing member into
+        /// `None`.
+        fn from_missing() -> Option<Self> {
+            None
+        }
+    }
+
+    /// Reads the member `key` of an object, reporting errors at its path.
+    pub fn field<T: FromJson>(object: &JsonMap, key: &str) -> Result<T, FromJsonError> {
+        match object.get(key) {
+            Some(value) => T::from_json(value).map_err(|e| e.at_key(key)),
+            None => T::from_missing().ok_or_else(|| FromJsonError::new("missing field").at_key(key)),
+        }
+    }
+
+    /// Like [`field`], but falls back to `default` when the member is absent.
+    pub fn field_or_else<T: FromJson>(
+        object: &JsonMap,
+        key: &str,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, FromJsonError> {
+        match object.get(key) {
+            Some(value) => T::from_json(value).map_err(|e| e.at_key(key)),
+            None => Ok(default()),
+        }
+    }
+
+    pub fn expect_object(value: &JsonValue) -> Result<&JsonMap, FromJsonError> {
+        match value {
+            JsonValue::Object(obj) => Ok(obj),
+            _ => Err(FromJsonError::expected("object", value)),
+        }
+    }
+
+    pub fn expect_array(value: &JsonValue, len: Option<usize>) -> Result<&[JsonValue], FromJsonError> {
+        match (value, len) {
+            (JsonValue::Array(arr), Some(len)) if arr.len() != len => Err(FromJsonError::new(format!(
+                "expected array of length {}, found array of length {}",
+                len,
+                arr.len()
+            ))),
+            (JsonValue::Array(arr), _) => Ok(arr),
+            _ => Err(FromJsonError::expected("array", value)),
+        }
+    }
+
+    /// Reads element `index` of an array whose length was already checked.
+    pub fn element<T: FromJson>(array: &[JsonValue], index: usize) -> Result<T, FromJsonError> {
+        T::from_json(&array[index]).map_err(|e| e.at_index(index))
+    }
+
+    impl ToJson for JsonValue {
+        fn to_json(&self) -> JsonValue {
+            self.clone()
+        }
+    }
+
+    impl FromJson for JsonValue {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            Ok(value.clone())
+        }
+    }
+
+    impl ToJson for bool {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::Boolean(*self)
+        }
+    }
+
+    impl FromJson for bool {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            match value {
+                JsonValue::Boolean(b) => Ok(*b),
+                _ => Err(FromJsonError::expected("boolean", value)),
+            }
+        }
+    }
+
+    impl ToJson for str {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::String(self.to_string())
+        }
+    }
+
+    impl ToJson for String {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::String(self.clone())
+        }
+    }
+
+    impl FromJson for String {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            match value {
+                JsonValue::String(s) => Ok(s.clone()),
+                _ => Err(FromJsonError::expected("string", value)),
+            }
+        }
+    }
+
+    impl ToJson for char {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::String(self.to_string())
+        }
+    }
+
+    impl FromJson for char {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            let JsonValue::String(s) = value else {
+                return Err(FromJsonError::expected("string", value));
+            };
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(FromJsonError::new(format!("expected a single character, found {:?}", s))),
+            }
+        }
+    }
+
+    macro_rules! impl_integer {
+        ($($t:ty),*) => {$(
+            impl ToJson for $t {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Number(*self as f64)
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+                    match value {
+                        JsonValue::Number(n) if n.fract() == 0.0 && *n >= <$t>::MIN as f64 && *n <= <$t>::MAX as f64 => {
+                            Ok(*n as $t)
+                        }
+                        JsonValue::Number(n) => Err(FromJsonError::new(format!(
+                            "expected {} between {} and {}, found {}",
+                            stringify!($t),
+                            <$t>::MIN,
+                            <$t>::MAX,
+                            n
+                        ))),
+                        _ => Err(FromJsonError::expected("integer", value)),
+                    }
+                }
+            }
+        )*};
+    }
+
+    impl_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+    macro_rules! impl_float {
+        ($($t:ty),*) => {$(
+            impl ToJson for $t {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Number(*self as f64)
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+                    match value {
+                        JsonValue::Number(n) => Ok(*n as $t),
+                        _ => Err(FromJsonError::expected("number", value)),
+                    }
+                }
+            }
+        )*};
+    }
+
+    impl_float!(f32, f64);
+
+    impl ToJson for () {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::Null
+        }
+    }
+
+    impl FromJson for () {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            match value {
+                JsonValue::Null => Ok(()),
+                _ => Err(FromJsonError::expected("null", value)),
+            }
+        }
+    }
+
+    impl<T: ToJson + ?Sized> ToJson for &T {
+        fn to_json(&self) -> JsonValue {
+            (**self).to_json()
+        }
+    }
+
+    impl<T: ToJson + ?Sized> ToJson for Box<T> {
+        fn to_json(&self) -> JsonValue {
+            (**self).to_json()
+        }
+    }
+
+    impl<T: FromJson> FromJson for Box<T> {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            T::from_json(value).map(Box::new)
+        }
+    }
+
+    impl<T: ToJson> ToJson for Option<T> {
+        fn to_json(&self) -> JsonValue {
+            match self {
+                Some(value) => value.to_json(),
+                None => JsonValue::Null,
+            }
+        }
+    }
+
+    impl<T: FromJson> FromJson for Option<T> {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            match value {
+                JsonValue::Null => Ok(None),
+                _ => T::from_json(value).map(Some),
+            }
+        }
+
+        fn from_missing() -> Option<Self> {
+            Some(None)
+        }
+    }
+
+    impl<T: ToJson> ToJson for [T] {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::Array(self.iter().map(ToJson::to_json).collect())
+        }
+    }
+
+    impl<T: ToJson> ToJson for Vec<T> {
+        fn to_json(&self) -> JsonValue {
+            self.as_slice().to_json()
+        }
+    }
+
+    impl<T: FromJson> FromJson for Vec<T> {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            let array = expect_array(value, None)?;
+            (0..array.len()).map(|i| element(array, i)).collect()
+        }
+    }
+
+    impl<V: ToJson> ToJson for BTreeMap<String, V> {
+        fn to_json(&self) -> JsonValue {
+            JsonValue::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+        }
+    }
+
+    impl<V: FromJson> FromJson for BTreeMap<String, V> {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            expect_object(value)?
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), V::from_json(v).map_err(|e| e.at_key(k))?)))
+                .collect()
+        }
+    }
+
+    /// Members are written sorted by key so that the output is stable.
+    impl<V: ToJson, S> ToJson for HashMap<String, V, S> {
+        fn to_json(&self) -> JsonValue {
+            let mut members: JsonMap = self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect();
+            members.sort_keys();
+            JsonValue::Object(members)
+        }
+    }
+
+    impl<V: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, V, S> {
+        fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+            expect_object(value)?
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), V::from_json(v).map_err(|e| e.at_key(k))?)))
+                .collect()
+        }
+    }
+
+    macro_rules! impl_tuple {
+        ($len:expr => $($name:ident $index:tt),+) => {
+            impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Array(vec![$(self.$index.to_json()),+])
+                }
+            }
+
+            impl<$($name: FromJson),+> FromJson for ($($name,)+) {
+                fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+                    let array = expect_array(value, Some($len))?;
+                    Ok(($(element::<$name>(array, $index)?,)+))
+                }
+            }
+        };
+    }
+
+    impl_tuple!(1 => A 0);
+    impl_tuple!(2 => A 0, B 1);
+    impl_tuple!(3 => A 0, B 1, C 2);
+    impl_tuple!(4 => A 0, B 1, C 2, D 3);
+    impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+    impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
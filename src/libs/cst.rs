@@ -0,0 +1,838 @@
+    use std::fmt;
+
+    use crate::libs::json_parser::{escape_string, JsonParser, JsonValue, ParseMode};
+    use crate::libs::path::{format_path, parse_path, PathSegment};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum TokenKind {
+        Whitespace,
+        LineComment,
+        BlockComment,
+        LBrace,
+        RBrace,
+        LBracket,
+        RBracket,
+        Colon,
+        Comma,
+        String,
+        Number,
+        /// `true`, `false`, `null`, `Infinity`, `NaN` and unquoted JSON5 keys.
+        Identifier,
+    }
+
+    impl TokenKind {
+        pub fn is_trivia(self) -> bool {
+            matches!(
+                self,
+                TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment
+            )
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct CstToken {
+        pub kind: TokenKind,
+        pub text: String,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum NodeKind {
+        Document,
+        Object,
+        Array,
+        /// A `key: value` pair. Trivia around the pair belongs to the object.
+        Member,
+        Scalar,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum CstElement {
+        Token(CstToken),
+        Node(CstNode),
+    }
+
+    /// A node of the concrete syntax tree.
+    ///
+    /// Every byte of the source is held by exactly one token, including
+    /// whitespace and comments, so printing a node gives back its source text.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct CstNode {
+        pub kind: NodeKind,
+        pub children: Vec<CstElement>,
+    }
+
+    impl CstNode {
+        fn new(kind: NodeKind) -> Self {
+            Self { kind, children: Vec::new() }
+        }
+
+        fn write_to(&self, out: &mut String) {
+            for child in &self.children {
+                match child {
+                    CstElement::Token(token) => out.push_str(&token.text),
+                    CstElement::Node(node) => node.write_to(out),
+                }
+            }
+        }
+
+        pub fn text(&self) -> String {
+            let mut out = String::new();
+            self.write_to(&mut out);
+            out
+        }
+
+        /// Converts the node into a value. Comments and formatting are dropped.
+        pub fn to_value(&self) -> Result<JsonValue, String> {
+            if self.kind == NodeKind::Member {
+                return self
+                    .member_value()
+                    .ok_or_else(|| "Member without a value".to_string())?
+                    .to_value();
+            }
+            let text = self.text();
+            JsonParser::with_mode(&text, ParseMode::Json5).parse()
+        }
+
+        /// Indexes into `children` of the members of an object or the
+        /// elements of an array.
+        fn element_positions(&self) -> Vec<usize> {
+            self.children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| matches!(child, CstElement::Node(_)))
+                .map(|(i, _)| i)
+                .collect()
+        }
+
+        /// Number of members of an object or elements of an array.
+        pub fn len(&self) -> usize {
+            self.element_positions().len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// The decoded key of a member node.
+        pub fn member_key(&self) -> Option<String> {
+            if self.kind != NodeKind::Member {
+                return None;
+            }
+            match self.children.first()? {
+                CstElement::Token(token) if token.kind == TokenKind::String => {
+                    let mut parser = JsonParser::with_mode(&token.text, ParseMode::Json5);
+                    match parser.parse_string() {
+                        Ok(JsonValue::String(key)) => Some(key),
+                        _ => None,
+                    }
+                }
+                CstElement::Token(token) => Some(token.text.clone()),
+                CstElement::Node(_) => None,
+            }
+        }
+
+        pub fn member_value(&self) -> Option<&CstNode> {
+            self.children.iter().find_map(|child| match child {
+                CstElement::Node(node) => Some(node),
+                _ => None,
+            })
+        }
+
+        fn member_value_mut(&mut self) -> Option<&mut CstNode> {
+            self.children.iter_mut().find_map(|child| match child {
+                CstElement::Node(node) => Some(node),
+                _ => None,
+            })
+        }
+
+        /// The value node of a document, skipping the surrounding trivia.
+        fn document_value_mut(&mut self) -> Option<&mut CstNode> {
+            self.member_value_mut()
+        }
+
+        fn child_node(&self, position: usize) -> Option<&CstNode> {
+            match self.children.get(position)? {
+                CstElement::Node(node) => Some(node),
+                _ => None,
+            }
+        }
+
+        /// Finds the position in `children` of the element for `segment`.
+        fn find_child(&self, segment: &PathSegment) -> Option<usize> {
+            let positions = self.element_positions();
+            match (self.kind, segment) {
+                (NodeKind::Object, PathSegment::Key(key)) => positions.into_iter().find(|&p| {
+                    self.child_node(p)
+                        .and_then(|member| member.member_key())
+                        .is_some_and(|k| &k == key)
+                }),
+                (NodeKind::Array, PathSegment::Index(index)) => positions.get(*index).copied(),
+                _ => None,
+            }
+        }
+
+        /// Returns the value node reached by following `segments`.
+        pub fn get(&self, segments: &[PathSegment]) -> Option<&CstNode> {
+            let mut current = self;
+            for segment in segments {
+                let node = current.child_node(current.find_child(segment)?)?;
+                current = match node.kind {
+                    NodeKind::Member => node.member_value()?,
+                    _ => node,
+                };
+            }
+            Some(current)
+        }
+
+        fn get_mut(&mut self, segments: &[PathSegment]) -> Option<&mut CstNode> {
+            let mut current = self;
+            for segment in segments {
+                let position = current.find_child(segment)?;
+                let node = match current.children.get_mut(position)? {
+                    CstElement::Node(node) => node,
+                    _ => return None,
+                };
+                current = if node.kind == NodeKind::Member {
+                    node.member_value_mut()?
+                } else {
+                    node
+                };
+            }
+            Some(current)
+        }
+
+        /// Whitespace tokens directly before `position`.
+        fn whitespace_before(&self, position: usize) -> Vec<CstElement> {
+            let mut start = position;
+            while start > 0 {
+                match &self.children[start - 1] {
+                    CstElement::Token(token) if token.kind == TokenKind::Whitespace => start -= 1,
+                    _ => break,
+                }
+            }
+            self.children[start..position].to_vec()
+        }
+
+        /// The whitespace used to separate elements of this container.
+        fn separator(&self, positions: &[usize]) -> Vec<CstElement> {
+            let separator = match positions {
+                [_, second, ..] => self.whitespace_before(*second),
+                [first] => self.whitespace_before(*first),
+                [] => Vec::new(),
+            };
+            if separator.is_empty() {
+                vec![token(TokenKind::Whitespace, " ")]
+            } else {
+                separator
+            }
+        }
+
+        /// Position of a trailing comma after the element at `position`.
+        fn trailing_comma(&self, position: usize) -> Option<usize> {
+            self.children[position + 1..]
+                .iter()
+                .position(|child| !is_trivia(child))
+                .map(|offset| position + 1 + offset)
+                .filter(|&p| is_token(&self.children[p], TokenKind::Comma))
+        }
+
+        /// The position after a `//` comment that ends the line of the
+        /// element or comma at `position`, or `position + 1` if there is none.
+        fn after_line_comment(&self, position: usize) -> usize {
+            let mut next = position + 1;
+            let same_line = |child: &CstElement| is_token(child, TokenKind::Whitespace) && !is_line_break(child);
+            if self.children.get(next).is_some_and(same_line) {
+                next += 1;
+            }
+            match self.children.get(next) {
+                Some(child) if is_token(child, TokenKind::LineComment) => next + 1,
+                _ => position + 1,
+            }
+        }
+
+        /// Inserts `element` so that it becomes element number `index`. Only
+        /// the tokens next to it change: comments stay with the elements
+        /// they describe.
+        fn insert_element(&mut self, index: usize, element: CstNode) {
+            let positions = self.element_positions();
+            if positions.is_empty() {
+                // After any comments, and before a line break that keeps the
+                // closing bracket on its own line.
+                let mut at = self.children.len() - 1;
+                if at >= 2
+                    && is_line_break(&self.children[at - 1])
+                    && !is_token(&self.children[at - 2], TokenKind::LineComment)
+                {
+                    at -= 1;
+                }
+                self.children.insert(at, CstElement::Node(element));
+                return;
+            }
+
+            let separator = self.separator(&positions);
+            if index < positions.len() {
+                // Right where the current element's own line starts, after
+                // the comment that ends the previous one.
+                let previous = match index {
+                    0 => 0,
+                    _ => self.trailing_comma(positions[index - 1]).unwrap_or(positions[index - 1]),
+                };
+                let mut at = self.after_line_comment(previous);
+                if at < positions[index] && is_token(&self.children[at], TokenKind::Whitespace) {
+                    at += 1;
+                }
+                let inserted: Vec<CstElement> = std::iter::once(CstElement::Node(element))
+                    .chain(std::iter::once(token(TokenKind::Comma, ",")))
+                    .chain(separator)
+                    .collect();
+                self.children.splice(at..at, inserted);
+                return;
+            }
+
+            let last = positions[positions.len() - 1];
+            let (comma, at) = match self.trailing_comma(last) {
+                Some(comma) => (None, self.after_line_comment(comma)),
+                None => (Some(last + 1), self.after_line_comment(last)),
+            };
+            // A line comment must still end before the new element.
+            let after_comment = is_token(&self.children[at - 1], TokenKind::LineComment);
+            let separator = if after_comment && !separator.iter().any(is_line_break) {
+                vec![token(TokenKind::Whitespace, "\n")]
+            } else {
+                separator
+            };
+            let mut inserted = separator;
+            inserted.push(CstElement::Node(element));
+            if comma.is_none() {
+                inserted.push(token(TokenKind::Comma, ","));
+            }
+            self.children.splice(at..at, inserted);
+            if let Some(comma) = comma {
+                self.children.insert(comma, token(TokenKind::Comma, ","));
+            }
+        }
+
+        /// Removes element number `index` together with its separating comma,
+        /// the comment that ends its line and the whitespace that led it.
+        fn remove_element(&mut self, index: usize) {
+            let positions = self.element_positions();
+            let position = positions[index];
+            let comma = self.trailing_comma(position);
+            let mut end = self.after_line_comment(comma.unwrap_or(position));
+
+            if positions.len() == 1 {
+                // Whitespace after a line comment holds the newline ending it,
+                // so it is only dropped when the whitespace before the
+                // closing bracket can take its place.
+                let mut start = position;
+                let before = start >= 2 && is_token(&self.children[start - 1], TokenKind::Whitespace);
+                let after = self.children.get(end).is_some_and(|child| is_token(child, TokenKind::Whitespace));
+                if before && (after || !is_token(&self.children[start - 2], TokenKind::LineComment)) {
+                    start -= 1;
+                } else if after {
+                    end += 1;
+                }
+                self.children.drain(start..end);
+            } else if index + 1 < positions.len() {
+                while end < positions[index + 1] && is_token(&self.children[end], TokenKind::Whitespace) {
+                    end += 1;
+                }
+                self.children.drain(position..end);
+            } else {
+                // The previous element keeps its comment; its comma goes
+                // unless this element had a trailing one.
+                let previous = positions[index - 1];
+                let previous_comma = self.trailing_comma(previous).unwrap_or(previous + 1);
+                let start = self.after_line_comment(previous_comma);
+                self.children.drain(start..end);
+                if comma.is_none() {
+                    self.children.remove(previous_comma);
+                }
+            }
+        }
+
+        /// Builds a `"key": value` member, copying the spacing after the colon
+        /// from the first existing member.
+        fn new_member(&self, key: &str, value: CstNode) -> CstNode {
+            let after_colon = self
+                .element_positions()
+                .first()
+                .and_then(|&p| self.child_node(p))
+                .map(|member| {
+                    member
+                        .children
+                        .iter()
+                        .skip_while(|child| !is_token(child, TokenKind::Colon))
+                        .skip(1)
+                        .take_while(|child| is_trivia(child))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_else(|| vec![token(TokenKind::Whitespace, " ")]);
+
+            let mut member = CstNode::new(NodeKind::Member);
+            member.children.push(token(TokenKind::String, &format!("\"{}\"", escape_string(key))));
+            member.children.push(token(TokenKind::Colon, ":"));
+            member.children.extend(after_colon);
+            member.children.push(CstElement::Node(value));
+            member
+        }
+    }
+
+    impl fmt::Display for CstNode {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.text())
+        }
+    }
+
+    fn token(kind: TokenKind, text: &str) -> CstElement {
+        CstElement::Token(CstToken { kind, text: text.to_string() })
+    }
+
+    fn is_token(element: &CstElement, kind: TokenKind) -> bool {
+        matches!(element, CstElement::Token(token) if token.kind == kind)
+    }
+
+    fn is_trivia(element: &CstElement) -> bool {
+        matches!(element, CstElement::Token(token) if token.kind.is_trivia())
+    }
+
+    fn is_line_break(element: &CstElement) -> bool {
+        matches!(element, CstElement::Token(token) if token.kind == TokenKind::Whitespace && token.text.contains('\n'))
+    }
+
+    /// Writes `key` as a key token in the style of `old`: unquoted or
+    /// single-quoted keys stay that way when the new key allows it.
+    fn key_token(old: &CstElement, key: &str) -> CstElement {
+        let CstElement::Token(old) = old else {
+            return token(TokenKind::String, &format!("\"{}\"", escape_string(key)));
+        };
+        let mut chars = key.chars();
+        let identifier = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+        if old.kind == TokenKind::Identifier && identifier {
+            token(TokenKind::Identifier, key)
+        } else if old.text.starts_with('\'') {
+            // Double quotes need no escape between single ones.
+            let escaped = escape_string(key).replace("\\\"", "\"").replace('\'', "\\'");
+            token(TokenKind::String, &format!("'{}'", escaped))
+        } else {
+            token(TokenKind::String, &format!("\"{}\"", escape_string(key)))
+        }
+    }
+
+    /// A parsed document that keeps every token and every piece of trivia.
+    ///
+    /// Printing an unmodified document reproduces the input byte for byte, and
+    /// the path based edits only rewrite the tokens they touch.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct CstDocument {
+        pub root: CstNode,
+        pub mode: ParseMode,
+    }
+
+    impl CstDocument {
+        pub fn parse(input: &str, mode: ParseMode) -> Result<Self, String> {
+            let mut builder = Builder { parser: JsonParser::with_mode(input, mode) };
+            let mut root = CstNode::new(NodeKind::Document);
+            builder.trivia(&mut root.children)?;
+            root.children.push(CstElement::Node(builder.value()?));
+            builder.trivia(&mut root.children)?;
+            if builder.parser.position < input.len() {
+                return Err(format!("Unexpected data after the value at position {}", builder.parser.position));
+            }
+            Ok(Self { root, mode })
+        }
+
+        /// Parses a value fragment to be spliced into this document.
+        fn fragment(&self, text: &str) -> Result<CstNode, String> {
+            let mut document = CstDocument::parse(text, self.mode)?;
+            document
+                .root
+                .document_value_mut()
+                .map(|value| value.clone())
+                .ok_or_else(|| "Empty value".to_string())
+        }
+
+        pub fn to_value(&self) -> Result<JsonValue, String> {
+            self.root.to_value()
+        }
+
+        /// The value node at `path`, if any.
+        pub fn get(&self, path: &str) -> Option<&CstNode> {
+            let segments = parse_path(path).ok()?;
+            self.root.member_value()?.get(&segments)
+        }
+
+        fn container_mut(&mut self, segments: &[PathSegment]) -> Result<&mut CstNode, String> {
+            self.root
+                .document_value_mut()
+                .and_then(|value| value.get_mut(segments))
+                .ok_or_else(|| format!("Path '{}' not found", format_path(segments)))
+        }
+
+        /// Replaces the value at `path`, adding the key if the parent object
+        /// does not have it yet.
+        pub fn set(&mut self, path: &str, value: &str) -> Result<(), String> {
+            let segments = parse_path(path)?;
+            let new_value = self.fragment(value)?;
+            let root = self.root.document_value_mut().ok_or("Empty document")?;
+            if let Some(target) = root.get_mut(&segments) {
+                *target = new_value;
+                return Ok(());
+            }
+            match segments.split_last() {
+                Some((PathSegment::Key(_), _)) => self.insert(path, value),
+                _ => Err(format!("Path '{}' not found", path)),
+            }
+        }
+
+        /// Inserts a value: a new key in an object, or an element at the
+        /// given index of an array, shifting the following elements.
+        pub fn insert(&mut self, path: &str, value: &str) -> Result<(), String> {
+            let segments = parse_path(path)?;
+            let (last, parent) = segments
+                .split_last()
+                .ok_or("Cannot insert at the document root")?;
+            let new_value = self.fragment(value)?;
+            let container = self.container_mut(parent)?;
+            match (container.kind, last) {
+                (NodeKind::Object, PathSegment::Key(key)) => {
+                    if container.find_child(last).is_some() {
+                        return Err(format!("Key '{}' already exists", path));
+                    }
+                    let member = container.new_member(key, new_value);
+                    let len = container.len();
+                    container.insert_element(len, member);
+                    Ok(())
+                }
+                (NodeKind::Array, PathSegment::Index(index)) => {
+                    if *index > container.len() {
+                        return Err(format!(
+                            "Index {} is out of bounds for '{}' (length {})",
+                            index,
+                            format_path(parent),
+                            container.len()
+                        ));
+                    }
+                    container.insert_element(*index, new_value);
+                    Ok(())
+                }
+                _ => Err(format!("Path '{}' does not match the document structure", path)),
+            }
+        }
+
+        /// Appends a value to the array at `path`.
+        pub fn append(&mut self, path: &str, value: &str) -> Result<(), String> {
+            let segments = parse_path(path)?;
+            let new_value = self.fragment(value)?;
+            let container = self.container_mut(&segments)?;
+            if container.kind != NodeKind::Array {
+                return Err(format!("'{}' is not an array", path));
+            }
+            let len = container.len();
+            container.insert_element(len, new_value);
+            Ok(())
+        }
+
+        /// Renames the object key at `path`. Only the key token is rewritten,
+        /// keeping its quotes.
+        pub fn rename(&mut self, path: &str, new_key: &str) -> Result<(), String> {
+            let segments = parse_path(path)?;
+            let (last, parent) = segments
+                .split_last()
+                .ok_or("Cannot rename the document root")?;
+            let container = self.container_mut(parent)?;
+            if container.kind != NodeKind::Object {
+                return Err(format!("'{}' is not an object key", path));
+            }
+            if container.find_child(&PathSegment::Key(new_key.to_string())).is_some() {
+                return Err(format!("Key '{}' already exists", new_key));
+            }
+            let position = container
+                .find_child(last)
+                .ok_or_else(|| format!("Path '{}' not found", path))?;
+            if let Some(CstElement::Node(member)) = container.children.get_mut(position) {
+                member.children[0] = key_token(&member.children[0], new_key);
+            }
+            Ok(())
+        }
+
+        /// Removes the member or element at `path`.
+        pub fn remove(&mut self, path: &str) -> Result<(), String> {
+            let segments = parse_path(path)?;
+            let (last, parent) = segments
+                .split_last()
+                .ok_or("Cannot remove the document root")?;
+            let container = self.container_mut(parent)?;
+            let position = container
+                .find_child(last)
+                .ok_or_else(|| format!("Path '{}' not found", path))?;
+            let index = container
+                .element_positions()
+                .iter()
+                .position(|&p| p == position)
+                .ok_or_else(|| format!("Path '{}' not found", path))?;
+            container.remove_element(index);
+            Ok(())
+        }
+    }
+
+    impl fmt::Display for CstDocument {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.root)
+        }
+    }
+
+    /// Builds the tree while [`JsonParser`] checks the input, so the text is
+    /// read once and every token is cut from the span the parser consumed.
+    struct Builder<'a> {
+        parser: JsonParser<'a>,
+    }
+
+    impl<'a> Builder<'a> {
+        /// Pushes the text consumed since `start` as a token of `kind`.
+        fn push(&self, kind: TokenKind, start: usize, out: &mut Vec<CstElement>) {
+            out.push(token(kind, &self.parser.input[start..self.parser.position]));
+        }
+
+        fn bump(&mut self, kind: TokenKind, out: &mut Vec<CstElement>) {
+            let start = self.parser.position;
+            self.parser.position += 1;
+            self.push(kind, start, out);
+        }
+
+        fn trivia(&mut self, out: &mut Vec<CstElement>) -> Result<(), String> {
+            loop {
+                let start = self.parser.position;
+                self.parser.skip_whitespace();
+                if self.parser.position > start {
+                    self.push(TokenKind::Whitespace, start, out);
+                }
+                let start = self.parser.position;
+                let rest = &self.parser.input[start..];
+                let kind = if rest.starts_with("//") {
+                    TokenKind::LineComment
+                } else if rest.starts_with("/*") {
+                    TokenKind::BlockComment
+                } else {
+                    return Ok(());
+                };
+                if !self.parser.mode.allows_comments() {
+                    return Err(self.parser.extension_error("Comments", ParseMode::Jsonc));
+                }
+                self.parser.position += match kind {
+                    TokenKind::LineComment => rest.find('\n').unwrap_or(rest.len()),
+                    _ => rest[2..].find("*/").ok_or("Unterminated block comment")? + 4,
+                };
+                self.push(kind, start, out);
+            }
+        }
+
+        fn value(&mut self) -> Result<CstNode, String> {
+            let start = self.parser.position;
+            let kind = match self.parser.peek_byte() {
+                Some(b'{') => return self.container(NodeKind::Object),
+                Some(b'[') => return self.container(NodeKind::Array),
+                Some(b'"' | b'\'') => {
+                    self.parser.parse_string_cow()?;
+                    TokenKind::String
+                }
+                Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'I' | b'N') => {
+                    self.parser.parse_number()?;
+                    match self.parser.input.as_bytes()[start] {
+                        b'I' | b'N' => TokenKind::Identifier,
+                        _ => TokenKind::Number,
+                    }
+                }
+                Some(b't' | b'f') => {
+                    self.parser.parse_boolean()?;
+                    TokenKind::Identifier
+                }
+                Some(b'n') => {
+                    self.parser.parse_null()?;
+                    TokenKind::Identifier
+                }
+                _ => return Err("Invalid JSON format".to_string()),
+            };
+            let mut node = CstNode::new(NodeKind::Scalar);
+            self.push(kind, start, &mut node.children);
+            Ok(node)
+        }
+
+        fn container(&mut self, kind: NodeKind) -> Result<CstNode, String> {
+            let (open_token, close, close_token) = match kind {
+                NodeKind::Object => (TokenKind::LBrace, b'}', TokenKind::RBrace),
+                _ => (TokenKind::LBracket, b']', TokenKind::RBracket),
+            };
+            let mut node = CstNode::new(kind);
+            self.bump(open_token, &mut node.children);
+            let mut after_comma = false;
+            loop {
+                self.trivia(&mut node.children)?;
+                if self.parser.peek_byte() == Some(close) {
+                    if after_comma && !self.parser.mode.allows_trailing_commas() {
+                        return Err(self.parser.extension_error("Trailing commas", ParseMode::Jsonc));
+                    }
+                    self.bump(close_token, &mut node.children);
+                    return Ok(node);
+                }
+                let element = if kind == NodeKind::Object {
+                    self.member()?
+                } else {
+                    self.value()?
+                };
+                node.children.push(CstElement::Node(element));
+                self.trivia(&mut node.children)?;
+                match self.parser.peek_byte() {
+                    Some(b',') => {
+                        self.bump(TokenKind::Comma, &mut node.children);
+                        after_comma = true;
+                    }
+                    Some(b) if b == close => {
+                        self.bump(close_token, &mut node.children);
+                        return Ok(node);
+                    }
+                    _ => return Err(format!("Expected ',' or '{}'", close as char)),
+                }
+            }
+        }
+
+        fn member(&mut self) -> Result<CstNode, String> {
+            let mut node = CstNode::new(NodeKind::Member);
+            let start = self.parser.position;
+            self.parser.parse_key_cow()?;
+            let kind = match self.parser.input.as_bytes()[start] {
+                b'"' | b'\'' => TokenKind::String,
+                _ => TokenKind::Identifier,
+            };
+            self.push(kind, start, &mut node.children);
+            self.trivia(&mut node.children)?;
+            if self.parser.peek_byte() != Some(b':') {
+                return Err("Expected ':' after key".to_string());
+            }
+            self.bump(TokenKind::Colon, &mut node.children);
+            self.trivia(&mut node.children)?;
+            let value = self.value()?;
+            node.children.push(CstElement::Node(value));
+            Ok(node)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn edit(input: &str, change: impl FnOnce(&mut CstDocument) -> Result<(), String>) -> String {
+            let mut document = CstDocument::parse(input, ParseMode::Json5).unwrap();
+            change(&mut document).unwrap();
+            document.to_string()
+        }
+
+        #[test]
+        fn printing_gives_back_the_input() {
+            for input in [
+                "{}",
+                "  [ ]  \n",
+                "{\"a\": [1, 2.50, -0e3], \"b\": {\"c\": null}}",
+                "// header\n{\n    \"a\": 1, // one\n    /* two */ \"b\": [true,\n\tfalse,],\n}\n",
+                "{unquoted: 'single', hex: 0x1F, inf: -Infinity, half: .5,}",
+                "\"caf\\u00e9 \\\" \u{e9}\"",
+            ] {
+                let document = CstDocument::parse(input, ParseMode::Json5).unwrap();
+                assert_eq!(document.to_string(), input);
+            }
+        }
+
+        #[test]
+        fn parsing_follows_the_mode() {
+            assert!(CstDocument::parse("// c\n[1,]", ParseMode::Jsonc).is_ok());
+            for input in ["// c\n[1]", "[1,]", "{a: 1}", "[01]", "[1] 2", "\"a\tb\""] {
+                assert!(CstDocument::parse(input, ParseMode::Strict).is_err(), "{}", input);
+            }
+            assert!(CstDocument::parse("{a: 1}", ParseMode::Jsonc).unwrap_err().contains("--mode json5"));
+        }
+
+        #[test]
+        fn set_replaces_only_the_value() {
+            let input = "{\n  // port\n  \"port\": 80, /* keep */\n  \"host\": \"x\"\n}";
+            let output = edit(input, |document| document.set("port", "8080"));
+            assert_eq!(output, input.replace("80,", "8080,"));
+        }
+
+        #[test]
+        fn insert_keeps_comments_and_layout() {
+            let input = "[\n  1, // one\n  // about two\n  2\n]";
+            assert_eq!(
+                edit(input, |document| document.insert("[1]", "9")),
+                "[\n  1, // one\n  9,\n  // about two\n  2\n]"
+            );
+            assert_eq!(
+                edit(input, |document| document.append("", "3")),
+                "[\n  1, // one\n  // about two\n  2,\n  3\n]"
+            );
+            assert_eq!(edit("[1, 2,]", |document| document.append("", "3")), "[1, 2, 3,]");
+            assert_eq!(edit("[1 // one\n]", |document| document.append("", "2")), "[1, // one\n2\n]");
+        }
+
+        #[test]
+        fn insert_into_empty_containers_keeps_their_trivia() {
+            assert_eq!(edit("{}", |document| document.insert("a", "1")), "{\"a\": 1}");
+            assert_eq!(edit("[ ]", |document| document.append("", "1")), "[ 1]");
+            assert_eq!(edit("{\n}", |document| document.insert("a", "1")), "{\"a\": 1\n}");
+            assert_eq!(
+                edit("{ // none yet\n}", |document| document.insert("a", "1")),
+                "{ // none yet\n\"a\": 1}"
+            );
+            assert_eq!(edit("[ /* c */ ]", |document| document.append("", "1")), "[ /* c */ 1]");
+        }
+
+        #[test]
+        fn remove_takes_the_element_with_its_comma_and_comment() {
+            let input = "{\n  \"a\": 1, // one\n  \"b\": 2, // two\n  \"c\": 3 // three\n}";
+            assert_eq!(
+                edit(input, |document| document.remove("a")),
+                "{\n  \"b\": 2, // two\n  \"c\": 3 // three\n}"
+            );
+            assert_eq!(
+                edit(input, |document| document.remove("b")),
+                "{\n  \"a\": 1, // one\n  \"c\": 3 // three\n}"
+            );
+            assert_eq!(
+                edit(input, |document| document.remove("c")),
+                "{\n  \"a\": 1, // one\n  \"b\": 2 // two\n}"
+            );
+            assert_eq!(edit("[1, 2,]", |document| document.remove("[1]")), "[1,]");
+            assert_eq!(edit("[1, 2]", |document| document.remove("[1]")), "[1]");
+        }
+
+        #[test]
+        fn removing_the_last_element_keeps_the_container_layout() {
+            assert_eq!(edit("{\n  \"a\": 1\n}", |document| document.remove("a")), "{\n}");
+            assert_eq!(edit("[ 1 ]", |document| document.remove("[0]")), "[ ]");
+            assert_eq!(edit("[1,]", |document| document.remove("[0]")), "[]");
+            assert_eq!(
+                edit("[ /* list */\n  1\n]", |document| document.remove("[0]")),
+                "[ /* list */\n]"
+            );
+            assert_eq!(
+                edit("{ // none left\n  \"a\": 1\n}", |document| document.remove("a")),
+                "{ // none left\n}"
+            );
+            assert_eq!(
+                edit("{ // none left\n  \"a\": 1}", |document| document.remove("a")),
+                "{ // none left\n  }"
+            );
+        }
+
+        #[test]
+        fn rename_keeps_the_key_style() {
+            assert_eq!(edit("{a: 1}", |document| document.rename("a", "b")), "{b: 1}");
+            assert_eq!(edit("{a: 1}", |document| document.rename("a", "b c")), "{\"b c\": 1}");
+            assert_eq!(edit("{'a': 1}", |document| document.rename("a", "it's \"b\"")), "{'it\\'s \"b\"': 1}");
+            assert_eq!(edit("{\"a\" : 1}", |document| document.rename("a", "b")), "{\"b\" : 1}");
+            let renamed = edit("{'a': 1}", |document| document.rename("a", "it's"));
+            let document = CstDocument::parse(&renamed, ParseMode::Json5).unwrap();
+            assert!(document.get("it's").is_some());
+        }
+    }
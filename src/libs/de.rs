@@ -0,0 +1,444 @@
+    use std::fmt;
+
+    use serde::de::{self, DeserializeSeed, Deserialize, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+    use serde::forward_to_deserialize_any;
+
+    use crate::libs::json_parser::{JsonParser, JsonValue, ParseMode};
+    use crate::libs::map::JsonMap;
+
+    /// An error raised while converting between JSON text and serde types.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Error {
+        message: String,
+        position: Option<usize>,
+    }
+
+    impl Error {
+        pub(crate) fn new(message: impl Into<String>) -> Self {
+            Self { message: message.into(), position: None }
+        }
+
+        /// The byte offset in the input the error was raised at, if any.
+        pub fn position(&self) -> Option<usize> {
+            self.position
+        }
+
+        fn at(mut self, position: usize) -> Self {
+            self.position.get_or_insert(position);
+            self
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            // Parser errors for extended syntax already mention the position.
+            match self.position {
+                Some(position) if !self.message.contains(" at position ") => {
+                    write!(f, "{} at position {}", self.message, position)
+                }
+                _ => write!(f, "{}", self.message),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl de::Error for Error {
+        fn custom<T: fmt::Display>(msg: T) -> Self {
+            Error::new(msg.to_string())
+        }
+    }
+
+    impl serde::ser::Error for Error {
+        fn custom<T: fmt::Display>(msg: T) -> Self {
+            Error::new(msg.to_string())
+        }
+    }
+
+    /// Drives serde directly from [`JsonParser`] input, without building a
+    /// `JsonValue` tree first.
+    pub struct Deserializer<'a> {
+        parser: JsonParser<'a>,
+    }
+
+    impl<'a> Deserializer<'a> {
+        pub fn new(input: &'a str) -> Self {
+            Self::with_mode(input, ParseMode::Strict)
+        }
+
+        pub fn with_mode(input: &'a str, mode: ParseMode) -> Self {
+            Self { parser: JsonParser::with_mode(input, mode) }
+        }
+
+        /// Checks that nothing but whitespace and comments follows the value.
+        pub fn end(&mut self) -> Result<(), Error> {
+            self.skip_trivia()?;
+            if self.parser.position < self.parser.input.len() {
+                return Err(self.error("Trailing characters after the JSON value"));
+            }
+            Ok(())
+        }
+
+        fn error(&self, message: impl Into<String>) -> Error {
+            Error::new(message).at(self.parser.position)
+        }
+
+        fn skip_trivia(&mut self) -> Result<(), Error> {
+            self.parser.skip_trivia().map_err(|e| self.error(e))
+        }
+
+        fn peek(&mut self) -> Result<Option<char>, Error> {
+            self.skip_trivia()?;
+            Ok(self.parser.peek_char())
+        }
+
+        fn expect(&mut self, expected: char) -> Result<(), Error> {
+            if self.peek()? == Some(expected) {
+                self.parser.consume_char();
+                Ok(())
+            } else {
+                Err(self.error(format!("Expected '{}'", expected)))
+            }
+        }
+
+        fn parse_string(&mut self) -> Result<String, Error> {
+            match self.parser.parse_string() {
+                Ok(JsonValue::String(s)) => Ok(s),
+                Ok(_) => Err(self.error("Expected a string")),
+                Err(e) => Err(self.error(e)),
+            }
+        }
+
+        /// Consumes the separator before the next element of an array or
+        /// object and reports whether another element follows.
+        fn has_next(&mut self, first: bool, close: char) -> Result<bool, Error> {
+            if self.peek()? == Some(close) {
+                return Ok(false);
+            }
+            if !first {
+                if self.peek()? != Some(',') {
+                    return Err(self.error(format!("Expected ',' or '{}'", close)));
+                }
+                self.parser.consume_char();
+                if self.peek()? == Some(close) {
+                    if !self.parser.mode.allows_trailing_commas() {
+                        let message = self.parser.extension_error("Trailing commas", ParseMode::Jsonc);
+                        return Err(self.error(message));
+                    }
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+
+        fn deserialize_number<'de, V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+            let start = self.parser.position;
+            let n = match self.parser.parse_number() {
+                Ok(JsonValue::Number(n)) => n,
+                Ok(_) => return Err(self.error("Expected a number")),
+                Err(e) => return Err(self.error(e)),
+            };
+            // Integers are read from the text so that values beyond 2^53 keep
+            // their precision.
+            let text = &self.parser.input[start..self.parser.position];
+            let text = text.strip_prefix('+').unwrap_or(text);
+            if let Ok(u) = text.parse::<u64>() {
+                visitor.visit_u64(u)
+            } else if let Ok(i) = text.parse::<i64>() {
+                visitor.visit_i64(i)
+            } else if n.fract() == 0.0 && !text.contains(['.', 'e', 'E']) && n.abs() < i64::MAX as f64 {
+                visitor.visit_i64(n as i64)
+            } else {
+                visitor.visit_f64(n)
+            }
+        }
+    }
+
+    /// Deserializes an instance of `T` from strict JSON text.
+    pub fn from_str<'a, T: Deserialize<'a>>(input: &'a str) -> Result<T, Error> {
+        from_str_with_mode(input, ParseMode::Strict)
+    }
+
+    /// Deserializes an instance of `T`, accepting the extensions of `mode`.
+    pub fn from_str_with_mode<'a, T: Deserialize<'a>>(input: &'a str, mode: ParseMode) -> Result<T, Error> {
+        let mut deserializer = Deserializer::with_mode(input, mode);
+        let value = T::deserialize(&mut deserializer).map_err(|e| e.at(deserializer.parser.position))?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    impl<'de> de::Deserializer<'de> for &mut Deserializer<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.peek()? {
+                Some('{') => {
+                    self.parser.consume_char();
+                    let value = visitor.visit_map(Compound { de: self, first: true })?;
+                    self.expect('}')?;
+                    Ok(value)
+                }
+                Some('[') => {
+                    self.parser.consume_char();
+                    let value = visitor.visit_seq(Compound { de: self, first: true })?;
+                    self.expect(']')?;
+                    Ok(value)
+                }
+                Some('"') | Some('\'') => visitor.visit_string(self.parse_string()?),
+                Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'I' | 'N') => self.deserialize_number(visitor),
+                Some('t') | Some('f') => match self.parser.parse_boolean() {
+                    Ok(JsonValue::Boolean(b)) => visitor.visit_bool(b),
+                    _ => Err(self.error("Invalid boolean")),
+                },
+                Some('n') => match self.parser.parse_null() {
+                    Ok(_) => visitor.visit_unit(),
+                    Err(e) => Err(self.error(e)),
+                },
+                Some(_) => Err(self.error("Invalid JSON format")),
+                None => Err(self.error("Unexpected end of input")),
+            }
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            if self.peek()? == Some('n') {
+                self.parser.parse_null().map_err(|e| self.error(e))?;
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(self)
+            }
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            match self.peek()? {
+                Some('"') | Some('\'') => {
+                    let variant: de::value::StringDeserializer<Error> = self.parse_string()?.into_deserializer();
+                    visitor.visit_enum(variant)
+                }
+                Some('{') => {
+                    self.parser.consume_char();
+                    let value = visitor.visit_enum(Enum { de: self })?;
+                    self.expect('}')?;
+                    Ok(value)
+                }
+                _ => Err(self.error("Expected a string or an object for an enum")),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+            identifier ignored_any
+        }
+    }
+
+    /// The elements of an array or the members of an object.
+    struct Compound<'a, 'b> {
+        de: &'b mut Deserializer<'a>,
+        first: bool,
+    }
+
+    impl<'de> SeqAccess<'de> for Compound<'_, '_> {
+        type Error = Error;
+
+        fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+            if !self.de.has_next(self.first, ']')? {
+                return Ok(None);
+            }
+            self.first = false;
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+
+    impl<'de> MapAccess<'de> for Compound<'_, '_> {
+        type Error = Error;
+
+        fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+            if !self.de.has_next(self.first, '}')? {
+                return Ok(None);
+            }
+            self.first = false;
+            let key = self.de.parser.parse_key().map_err(|e| self.de.error(e))?;
+            seed.deserialize(MapKey(key)).map(Some)
+        }
+
+        fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+            if self.de.peek()? != Some(':') {
+                return Err(self.de.error("Expected ':' after key"));
+            }
+            self.de.parser.consume_char();
+            seed.deserialize(&mut *self.de)
+        }
+    }
+
+    /// An object key, which may also stand for a number or a bool.
+    struct MapKey(String);
+
+    macro_rules! deserialize_key_from_str {
+        ($($method:ident => $visit:ident),*) => {$(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*};
+    }
+
+    impl<'de> de::Deserializer<'de> for MapKey {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_string(self.0)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_enum(self.0.into_deserializer())
+        }
+
+        deserialize_key_from_str! {
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64
+        }
+
+        forward_to_deserialize_any! {
+            i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+            tuple_struct map struct identifier ignored_any
+        }
+    }
+
+    /// An enum written as an object whose only key is the variant name.
+    struct Enum<'a, 'b> {
+        de: &'b mut Deserializer<'a>,
+    }
+
+    impl<'de> EnumAccess<'de> for Enum<'_, '_> {
+        type Error = Error;
+        type Variant = Self;
+
+        fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+            self.de.skip_trivia()?;
+            let key = self.de.parser.parse_key().map_err(|e| self.de.error(e))?;
+            let variant = seed.deserialize(MapKey(key))?;
+            self.de.expect(':')?;
+            Ok((variant, self))
+        }
+    }
+
+    impl<'de> VariantAccess<'de> for Enum<'_, '_> {
+        type Error = Error;
+
+        fn unit_variant(self) -> Result<(), Error> {
+            Deserialize::deserialize(&mut *self.de)
+        }
+
+        fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+            seed.deserialize(&mut *self.de)
+        }
+
+        fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+            de::Deserializer::deserialize_seq(&mut *self.de, visitor)
+        }
+
+        fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+            de::Deserializer::deserialize_map(&mut *self.de, visitor)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for JsonValue {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ValueVisitor;
+
+            impl<'de> Visitor<'de> for ValueVisitor {
+                type Value = JsonValue;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("any JSON value")
+                }
+
+                fn visit_bool<E>(self, b: bool) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Boolean(b))
+                }
+
+                fn visit_i64<E>(self, n: i64) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Number(n as f64))
+                }
+
+                fn visit_u64<E>(self, n: u64) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Number(n as f64))
+                }
+
+                fn visit_f64<E>(self, n: f64) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Number(n))
+                }
+
+                fn visit_str<E>(self, s: &str) -> Result<JsonValue, E> {
+                    Ok(JsonValue::String(s.to_string()))
+                }
+
+                fn visit_string<E>(self, s: String) -> Result<JsonValue, E> {
+                    Ok(JsonValue::String(s))
+                }
+
+                fn visit_unit<E>(self) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Null)
+                }
+
+                fn visit_none<E>(self) -> Result<JsonValue, E> {
+                    Ok(JsonValue::Null)
+                }
+
+                fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+                    Deserialize::deserialize(deserializer)
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+                    let mut array = Vec::new();
+                    while let Some(value) = seq.next_element()? {
+                        array.push(value);
+                    }
+                    Ok(JsonValue::Array(array))
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+                    let mut object = JsonMap::new();
+                    while let Some((key, value)) = map.next_entry()? {
+                        object.insert(key, value);
+                    }
+                    Ok(JsonValue::Object(object))
+                }
+            }
+
+            deserializer.deserialize_any(ValueVisitor)
+        }
+    }
@@ -0,0 +1,443 @@
+    use std::str::FromStr;
+
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::path::{format_path, PathSegment};
+
+    /// How elements of two arrays are paired up before being compared.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum ArrayDiffMode {
+        /// Element `i` is compared with element `i`.
+        Index,
+        /// Object elements are paired by the value of an identity key such as `id`.
+        Key(String),
+        /// Elements are aligned on their longest common subsequence, so an
+        /// insertion in the middle only reports the inserted element.
+        Lcs,
+    }
+
+    impl FromStr for ArrayDiffMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "index" => Ok(ArrayDiffMode::Index),
+                "lcs" => Ok(ArrayDiffMode::Lcs),
+                _ => match s.strip_prefix("key:") {
+                    Some(key) if !key.is_empty() => Ok(ArrayDiffMode::Key(key.to_string())),
+                    _ => Err(format!(
+                        "Unknown array mode '{}' (expected index, lcs or key:<field>)",
+                        s
+                    )),
+                },
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct DiffOptions {
+        pub arrays: ArrayDiffMode,
+        /// Numbers whose difference is at most this value are equal.
+        pub number_tolerance: f64,
+        /// Compares strings through `JsonValue::to_lowercase`.
+        pub ignore_case: bool,
+    }
+
+    impl Default for DiffOptions {
+        fn default() -> Self {
+            Self {
+                arrays: ArrayDiffMode::Index,
+                number_tolerance: 0.0,
+                ignore_case: false,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum DiffKind {
+        Added,
+        Removed,
+        Changed,
+    }
+
+    /// One difference between two documents. Paths of removed values index
+    /// into the old document, all other paths into the new one.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct DiffEntry<'a> {
+        pub path: Vec<PathSegment>,
+        pub kind: DiffKind,
+        pub old: Option<&'a JsonValue>,
+        pub new: Option<&'a JsonValue>,
+    }
+
+    /// Compares two documents structurally: key order never matters.
+    pub fn diff<'a>(old: &'a JsonValue, new: &'a JsonValue, options: &DiffOptions) -> Vec<DiffEntry<'a>> {
+        let mut entries = Vec::new();
+        let mut path = Vec::new();
+        diff_values(old, new, options, &mut path, &mut entries);
+        entries
+    }
+
+    /// Whether two values are equal under the given options.
+    pub fn equivalent(a: &JsonValue, b: &JsonValue, options: &DiffOptions) -> bool {
+        match (a, b) {
+            (JsonValue::Object(x), JsonValue::Object(y)) => {
+                x.len() == y.len()
+                    && x.iter().all(|(key, value)| {
+                        y.get(key).is_some_and(|other| equivalent(value, other, options))
+                    })
+            }
+            (JsonValue::Array(x), JsonValue::Array(y)) => {
+                x.len() == y.len() && x.iter().zip(y).all(|(a, b)| equivalent(a, b, options))
+            }
+            (JsonValue::Number(x), JsonValue::Number(y)) if options.number_tolerance > 0.0 => {
+                (x - y).abs() <= options.number_tolerance
+            }
+            (JsonValue::String(_), JsonValue::String(_)) if options.ignore_case => {
+                a.to_lowercase() == b.to_lowercase()
+            }
+            _ => a == b,
+        }
+    }
+
+    fn push<'a>(
+        entries: &mut Vec<DiffEntry<'a>>,
+        path: &[PathSegment],
+        kind: DiffKind,
+        old: Option<&'a JsonValue>,
+        new: Option<&'a JsonValue>,
+    ) {
+        entries.push(DiffEntry { path: path.to_vec(), kind, old, new });
+    }
+
+    fn diff_values<'a>(
+        old: &'a JsonValue,
+        new: &'a JsonValue,
+        options: &DiffOptions,
+        path: &mut Vec<PathSegment>,
+        entries: &mut Vec<DiffEntry<'a>>,
+    ) {
+        match (old, new) {
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                for (key, old_value) in a {
+                    path.push(PathSegment::Key(key.clone()));
+                    match b.get(key) {
+                        Some(new_value) => diff_values(old_value, new_value, options, path, entries),
+                        None => push(entries, path, DiffKind::Removed, Some(old_value), None),
+                    }
+                    path.pop();
+                }
+                for (key, new_value) in b {
+                    if !a.contains_key(key) {
+                        path.push(PathSegment::Key(key.clone()));
+                        push(entries, path, DiffKind::Added, None, Some(new_value));
+                        path.pop();
+                    }
+                }
+            }
+            (JsonValue::Array(a), JsonValue::Array(b)) => match &options.arrays {
+                ArrayDiffMode::Index => diff_by_index(a, b, options, path, entries),
+                ArrayDiffMode::Key(key) => diff_by_key(a, b, key, options, path, entries),
+                ArrayDiffMode::Lcs => diff_by_lcs(a, b, options, path, entries),
+            },
+            _ => {
+                if !equivalent(old, new, options) {
+                    push(entries, path, DiffKind::Changed, Some(old), Some(new));
+                }
+            }
+        }
+    }
+
+    fn diff_by_index<'a>(
+        a: &'a [JsonValue],
+        b: &'a [JsonValue],
+        options: &DiffOptions,
+        path: &mut Vec<PathSegment>,
+        entries: &mut Vec<DiffEntry<'a>>,
+    ) {
+        for i in 0..a.len().max(b.len()) {
+            path.push(PathSegment::Index(i));
+            match (a.get(i), b.get(i)) {
+                (Some(x), Some(y)) => diff_values(x, y, options, path, entries),
+                (Some(x), None) => push(entries, path, DiffKind::Removed, Some(x), None),
+                (None, Some(y)) => push(entries, path, DiffKind::Added, None, Some(y)),
+                (None, None) => {}
+            }
+            path.pop();
+        }
+    }
+
+    fn diff_by_key<'a>(
+        a: &'a [JsonValue],
+        b: &'a [JsonValue],
+        key: &str,
+        options: &DiffOptions,
+        path: &mut Vec<PathSegment>,
+        entries: &mut Vec<DiffEntry<'a>>,
+    ) {
+        let identity = |value: &'a JsonValue| match value {
+            JsonValue::Object(obj) => obj.get(key),
+            _ => None,
+        };
+        let find_in = |list: &'a [JsonValue], id: &JsonValue| {
+            list.iter()
+                .position(|value| identity(value).is_some_and(|other| equivalent(id, other, options)))
+        };
+        if !a.iter().chain(b).any(|value| identity(value).is_some()) {
+            return diff_by_index(a, b, options, path, entries);
+        }
+
+        for (i, old_value) in a.iter().enumerate() {
+            let matched = match identity(old_value) {
+                Some(id) => find_in(b, id),
+                None => None,
+            };
+            match matched {
+                Some(j) => {
+                    path.push(PathSegment::Index(j));
+                    diff_values(old_value, &b[j], options, path, entries);
+                }
+                None => {
+                    path.push(PathSegment::Index(i));
+                    push(entries, path, DiffKind::Removed, Some(old_value), None);
+                }
+            }
+            path.pop();
+        }
+        for (j, new_value) in b.iter().enumerate() {
+            let matched = identity(new_value).and_then(|id| find_in(a, id));
+            if matched.is_none() {
+                path.push(PathSegment::Index(j));
+                push(entries, path, DiffKind::Added, None, Some(new_value));
+                path.pop();
+            }
+        }
+    }
+
+    fn diff_by_lcs<'a>(
+        a: &'a [JsonValue],
+        b: &'a [JsonValue],
+        options: &DiffOptions,
+        path: &mut Vec<PathSegment>,
+        entries: &mut Vec<DiffEntry<'a>>,
+    ) {
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if equivalent(&a[i], &b[j], options) {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && equivalent(&a[i], &b[j], options) {
+                i += 1;
+                j += 1;
+                continue;
+            }
+            let removes = i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]);
+            let replaced = removes && j < b.len() && lcs[i + 1][j + 1] == lcs[i][j];
+            if replaced {
+                // An element removed where another one is added is reported
+                // as a change, so that nested differences stay visible.
+                path.push(PathSegment::Index(j));
+                diff_values(&a[i], &b[j], options, path, entries);
+                i += 1;
+                j += 1;
+            } else if removes {
+                path.push(PathSegment::Index(i));
+                push(entries, path, DiffKind::Removed, Some(&a[i]), None);
+                i += 1;
+            } else {
+                path.push(PathSegment::Index(j));
+                push(entries, path, DiffKind::Added, None, Some(&b[j]));
+                j += 1;
+            }
+            path.pop();
+        }
+    }
+
+    /// Renders the differences for a terminal, one line per entry.
+    pub fn format_diff_text(entries: &[DiffEntry], colors: bool) -> String {
+        let (green, red, yellow, reset) = if colors {
+            ("\x1b[32m", "\x1b[31m", "\x1b[33m", "\x1b[0m")
+        } else {
+            ("", "", "", "")
+        };
+        let mut output = String::new();
+        for entry in entries {
+            let path = if entry.path.is_empty() {
+                "(root)".to_string()
+            } else {
+                format_path(&entry.path)
+            };
+            let line = match (entry.kind, entry.old, entry.new) {
+                (DiffKind::Added, _, Some(new)) => {
+                    format!("{}+ {}: {}{}", green, path, new.to_json_string(), reset)
+                }
+                (DiffKind::Removed, Some(old), _) => {
+                    format!("{}- {}: {}{}", red, path, old.to_json_string(), reset)
+                }
+                (_, Some(old), Some(new)) => format!(
+                    "{}~ {}: {} -> {}{}",
+                    yellow,
+                    path,
+                    old.to_json_string(),
+                    new.to_json_string(),
+                    reset
+                ),
+                _ => continue,
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+        let count = |kind| entries.iter().filter(|e| e.kind == kind).count();
+        output.push_str(&format!(
+            "{} added, {} removed, {} changed\n",
+            count(DiffKind::Added),
+            count(DiffKind::Removed),
+            count(DiffKind::Changed)
+        ));
+        output
+    }
+
+    /// Converts the differences into a JSON array for other tools.
+    pub fn diff_to_json(entries: &[DiffEntry]) -> JsonValue {
+        let items = entries
+            .iter()
+            .map(|entry| {
+                let kind = match entry.kind {
+                    DiffKind::Added => "added",
+                    DiffKind::Removed => "removed",
+                    DiffKind::Changed => "changed",
+                };
+                let mut item = vec![
+                    ("kind".to_string(), JsonValue::String(kind.to_string())),
+                    ("path".to_string(), JsonValue::String(format_path(&entry.path))),
+                ];
+                if let Some(old) = entry.old {
+                    item.push(("old".to_string(), old.clone()));
+                }
+                if let Some(new) = entry.new {
+                    item.push(("new".to_string(), new.clone()));
+                }
+                JsonValue::Object(item.into())
+            })
+            .collect();
+        JsonValue::Array(items)
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        /// The differences as `+ path`, `- path` and `~ path` lines.
+        fn changes(old: &str, new: &str, options: &DiffOptions) -> Vec<String> {
+            let (old, new) = (parse(old), parse(new));
+            diff(&old, &new, options)
+                .iter()
+                .map(|entry| {
+                    let sign = match entry.kind {
+                        DiffKind::Added => '+',
+                        DiffKind::Removed => '-',
+                        DiffKind::Changed => '~',
+                    };
+                    format!("{} {}", sign, format_path(&entry.path))
+                })
+                .collect()
+        }
+
+        fn with_arrays(arrays: ArrayDiffMode) -> DiffOptions {
+            DiffOptions { arrays, ..DiffOptions::default() }
+        }
+
+        #[test]
+        fn key_order_does_not_matter() {
+            let options = DiffOptions::default();
+            assert!(changes(r#"{"a": 1, "b": [1, {"c": 2}]}"#, r#"{"b": [1, {"c": 2}], "a": 1}"#, &options).is_empty());
+        }
+
+        #[test]
+        fn objects_report_added_removed_and_changed_keys() {
+            let found = changes(
+                r#"{"name": "Ada", "age": 36, "address": {"city": "London"}}"#,
+                r#"{"name": "Ada", "address": {"city": "Paris"}, "mail": "ada@example.com"}"#,
+                &DiffOptions::default(),
+            );
+            assert_eq!(found, ["- age", "~ address.city", "+ mail"]);
+        }
+
+        #[test]
+        fn type_changes_are_changes() {
+            let found = changes(r#"{"a": [1], "b": "1"}"#, r#"{"a": {"0": 1}, "b": 1}"#, &DiffOptions::default());
+            assert_eq!(found, ["~ a", "~ b"]);
+        }
+
+        #[test]
+        fn arrays_by_index() {
+            let found = changes("[1, 2, 3]", "[1, 5]", &DiffOptions::default());
+            assert_eq!(found, ["~ [1]", "- [2]"]);
+        }
+
+        #[test]
+        fn arrays_by_longest_common_subsequence() {
+            let options = with_arrays(ArrayDiffMode::Lcs);
+            assert_eq!(changes("[1, 2, 3]", "[1, 9, 2, 3]", &options), ["+ [1]"]);
+            assert_eq!(changes("[1, 2, 3]", "[1, 3]", &options), ["- [1]"]);
+            // A replaced object shows its nested differences.
+            assert_eq!(changes(r#"[0, {"a": 1}]"#, r#"[0, {"a": 2}]"#, &options), ["~ [1].a"]);
+        }
+
+        #[test]
+        fn arrays_by_identity_key() {
+            let options = with_arrays(ArrayDiffMode::Key("id".to_string()));
+            let found = changes(
+                r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3}]"#,
+                r#"[{"id": 2, "v": "B"}, {"id": 1, "v": "a"}, {"id": 4}]"#,
+                &options,
+            );
+            assert_eq!(found, ["~ [0].v", "- [2]", "+ [2]"]);
+            // Without any identity, elements are compared by index.
+            assert_eq!(changes("[1, 2]", "[2, 1]", &options), ["~ [0]", "~ [1]"]);
+        }
+
+        #[test]
+        fn tolerance_and_case() {
+            let options = DiffOptions { number_tolerance: 0.01, ignore_case: true, ..DiffOptions::default() };
+            assert!(changes(r#"{"x": 1.0, "s": "Paris"}"#, r#"{"x": 1.005, "s": "PARIS"}"#, &options).is_empty());
+            assert_eq!(changes(r#"{"x": 1.0}"#, r#"{"x": 1.1}"#, &options), ["~ x"]);
+        }
+
+        #[test]
+        fn array_modes_parse() {
+            assert_eq!("index".parse(), Ok(ArrayDiffMode::Index));
+            assert_eq!("lcs".parse(), Ok(ArrayDiffMode::Lcs));
+            assert_eq!("key:id".parse(), Ok(ArrayDiffMode::Key("id".to_string())));
+            assert!("key:".parse::<ArrayDiffMode>().is_err());
+            assert!("other".parse::<ArrayDiffMode>().is_err());
+        }
+
+        #[test]
+        fn text_and_json_reports() {
+            let (old, new) = (parse(r#"{"a": 1, "b": 2}"#), parse(r#"{"a": 3, "c": 4}"#));
+            let entries = diff(&old, &new, &DiffOptions::default());
+            assert_eq!(
+                format_diff_text(&entries, false),
+                "~ a: 1 -> 3\n- b: 2\n+ c: 4\n1 added, 1 removed, 1 changed\n"
+            );
+            let json = diff_to_json(&entries);
+            assert_eq!(
+                json.to_json_string(),
+                r#"[{"kind":"changed","path":"a","old":1,"new":3},{"kind":"removed","path":"b","old":2},{"kind":"added","path":"c","new":4}]"#
+            );
+        }
+    }
@@ -0,0 +1,647 @@
+    use std::borrow::Cow;
+
+    use crate::libs::json_parser::{DuplicateKeys, JsonParser, JsonValue, ParseMode};
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{parse_path, PathSegment};
+    use crate::libs::serializer::{write_newline, write_number, write_string, SerializeOptions};
+
+    /// One entry of the tape. Containers are followed by their children,
+    /// and an object's children alternate between key and value.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Node {
+        Null,
+        Boolean(bool),
+        Number(f64),
+        /// A slice of the document's string buffer.
+        String { start: usize, len: u32 },
+        /// `end` is the index just past the last node of the subtree.
+        Array { len: u32, end: usize },
+        Object { len: u32, end: usize },
+    }
+
+    /// A whole JSON document stored in two contiguous buffers: a tape of
+    /// fixed-size nodes that refer to each other by index, and one string
+    /// buffer holding every key and string value.
+    ///
+    /// It is read-only and costs a fraction of the allocations of a
+    /// `JsonValue` tree, which makes it suited to very large inputs.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Document {
+        nodes: Vec<Node>,
+        strings: String,
+    }
+
+    /// Memory held by a [`Document`], in bytes.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct DocumentStats {
+        pub nodes: usize,
+        pub tape_bytes: usize,
+        pub string_bytes: usize,
+    }
+
+    impl DocumentStats {
+        pub fn total_bytes(&self) -> usize {
+            self.tape_bytes + self.string_bytes
+        }
+    }
+
+    impl Document {
+        pub fn parse(input: &str, mode: ParseMode) -> Result<Self, String> {
+            JsonParser::with_mode(input, mode).parse_document()
+        }
+
+        /// Panics if a string is longer than 4 GiB.
+        pub fn from_value(value: &JsonValue) -> Self {
+            let mut document = Document { nodes: Vec::new(), strings: String::new() };
+            document.push_value(value);
+            document
+        }
+
+        pub fn to_value(&self) -> JsonValue {
+            self.root().to_value()
+        }
+
+        pub fn root(&self) -> NodeRef<'_> {
+            self.node(0)
+        }
+
+        fn node(&self, index: usize) -> NodeRef<'_> {
+            NodeRef { document: self, index }
+        }
+
+        /// Looks up a path such as `details.city` or `users[0].name`.
+        pub fn get(&self, key_path: &str) -> Option<NodeRef<'_>> {
+            self.root().get_path(&parse_path(key_path).ok()?)
+        }
+
+        pub fn stats(&self) -> DocumentStats {
+            DocumentStats {
+                nodes: self.nodes.len(),
+                tape_bytes: self.nodes.capacity() * std::mem::size_of::<Node>(),
+                string_bytes: self.strings.capacity(),
+            }
+        }
+
+        pub fn serialize(&self, options: &SerializeOptions) -> String {
+            self.root().serialize(options)
+        }
+
+        pub fn to_json_string(&self) -> String {
+            self.serialize(&SerializeOptions::compact())
+        }
+
+        pub fn to_pretty_string(&self) -> String {
+            self.serialize(&SerializeOptions::default())
+        }
+
+        /// Finds the strings equal to `target`, ignoring case, in the same
+        /// form as `JsonValue::get_all_occurrences`.
+        pub fn get_all_occurrences(&self, target: &str) -> (usize, Vec<String>, Vec<Option<usize>>) {
+            let target = target.to_lowercase();
+            let mut keys: Vec<String> = Vec::new();
+            let mut indexes: Vec<Option<usize>> = Vec::new();
+
+            fn matches(node: NodeRef, target: &str) -> bool {
+                node.as_str().is_some_and(|s| s.to_lowercase() == target)
+            }
+
+            fn search(node: NodeRef, target: &str, path: &str, keys: &mut Vec<String>, indexes: &mut Vec<Option<usize>>) {
+                if node.is_object() {
+                    for (key, value) in node.members() {
+                        let new_path = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+                        if matches(value, target) {
+                            keys.push(new_path.clone());
+                            indexes.push(None);
+                        }
+                        search(value, target, &new_path, keys, indexes);
+                    }
+                } else {
+                    for (i, value) in node.elements().enumerate() {
+                        if matches(value, target) {
+                            keys.push(path.to_string());
+                            indexes.push(Some(i));
+                        }
+                        search(value, target, path, keys, indexes);
+                    }
+                }
+            }
+            search(self.root(), &target, "", &mut keys, &mut indexes);
+
+            (keys.len(), keys, indexes)
+        }
+
+        fn push_string(&mut self, s: &str) -> Result<Node, String> {
+            let len = u32::try_from(s.len()).map_err(|_| "String is too long for a document".to_string())?;
+            let start = self.strings.len();
+            self.strings.push_str(s);
+            Ok(Node::String { start, len })
+        }
+
+        /// Fills in the size of the container opened at `index`.
+        fn close(&mut self, index: usize, count: u32) {
+            let end = self.nodes.len();
+            self.nodes[index] = match self.nodes[index] {
+                Node::Array { .. } => Node::Array { len: count, end },
+                _ => Node::Object { len: count, end },
+            };
+        }
+
+        fn push_value(&mut self, value: &JsonValue) {
+            match value {
+                JsonValue::Object(obj) => {
+                    let index = self.nodes.len();
+                    self.nodes.push(Node::Object { len: 0, end: 0 });
+                    for (key, value) in obj {
+                        let key = self.push_string(key).expect("key length fits in u32");
+                        self.nodes.push(key);
+                        self.push_value(value);
+                    }
+                    self.close(index, obj.len() as u32);
+                }
+                JsonValue::Array(arr) => {
+                    let index = self.nodes.len();
+                    self.nodes.push(Node::Array { len: 0, end: 0 });
+                    for value in arr {
+                        self.push_value(value);
+                    }
+                    self.close(index, arr.len() as u32);
+                }
+                JsonValue::String(s) => {
+                    let node = self.push_string(s).expect("string length fits in u32");
+                    self.nodes.push(node);
+                }
+                JsonValue::Number(n) => self.nodes.push(Node::Number(*n)),
+                JsonValue::Boolean(b) => self.nodes.push(Node::Boolean(*b)),
+                JsonValue::Null => self.nodes.push(Node::Null),
+            }
+        }
+    }
+
+    impl<'a> JsonParser<'a> {
+        pub fn parse_document(&mut self) -> Result<Document, String> {
+            let mut document = Document { nodes: Vec::new(), strings: String::new() };
+            self.parse_into(&mut document)?;
+            self.finish()?;
+            document.nodes.shrink_to_fit();
+            document.strings.shrink_to_fit();
+            Ok(document)
+        }
+
+        /// Parses one value onto the end of the document's tape.
+        fn parse_into(&mut self, document: &mut Document) -> Result<(), String> {
+            self.skip_trivia()?;
+            let node = match self.peek_byte() {
+                Some(b'{') => return self.parse_object_into(document),
+                Some(b'[') => return self.parse_array_into(document),
+                Some(b'"') | Some(b'\'') => {
+                    let s: Cow<str> = self.parse_string_cow()?;
+                    document.push_string(&s)?
+                }
+                Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'I' | b'N') => match self.parse_number()? {
+                    JsonValue::Number(n) => Node::Number(n),
+                    _ => return Err("Invalid number format".to_string()),
+                },
+                Some(b't') | Some(b'f') => match self.parse_boolean()? {
+                    JsonValue::Boolean(b) => Node::Boolean(b),
+                    _ => return Err("Invalid boolean".to_string()),
+                },
+                Some(b'n') => self.parse_null().map(|_| Node::Null)?,
+                _ => return Err("Invalid JSON format".to_string()),
+            };
+            document.nodes.push(node);
+            Ok(())
+        }
+
+        fn parse_object_into(&mut self, document: &mut Document) -> Result<(), String> {
+            self.position += 1;
+            let index = document.nodes.len();
+            document.nodes.push(Node::Object { len: 0, end: 0 });
+            let mut count = 0u32;
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b'}') {
+                self.position += 1;
+                document.close(index, count);
+                return Ok(());
+            }
+            loop {
+                let key_position = self.position;
+                let key = self.parse_key_cow()?;
+
+                self.skip_trivia()?;
+                if self.consume_byte() != Some(b':') {
+                    return Err("Expected ':' after key".to_string());
+                }
+
+                let repeated = self.duplicate_keys != DuplicateKeys::Last
+                    && document.node(index).members().any(|(k, _)| k == key);
+                let member_start = document.nodes.len();
+                let string_start = document.strings.len();
+                let key_node = document.push_string(&key)?;
+                document.nodes.push(key_node);
+                self.parse_into(document)?;
+                match self.duplicate_keys {
+                    DuplicateKeys::First if repeated => {
+                        document.nodes.truncate(member_start);
+                        document.strings.truncate(string_start);
+                    }
+                    DuplicateKeys::Error if repeated => {
+                        return Err(format!("Duplicate key '{}' at position {}", key, key_position));
+                    }
+                    _ => {
+                        count += 1;
+                        // Keeps the open object valid for the repeat check.
+                        document.close(index, count);
+                    }
+                }
+
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b'}') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
+                    }
+                    Some(b'}') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err("Expected ',' or '}'".to_string()),
+                }
+            }
+            document.close(index, count);
+            Ok(())
+        }
+
+        fn parse_array_into(&mut self, document: &mut Document) -> Result<(), String> {
+            self.position += 1;
+            let index = document.nodes.len();
+            document.nodes.push(Node::Array { len: 0, end: 0 });
+            let mut count = 0u32;
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b']') {
+                self.position += 1;
+                document.close(index, count);
+                return Ok(());
+            }
+            loop {
+                self.parse_into(document)?;
+                count += 1;
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b']') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
+                    }
+                    Some(b']') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err("Expected ',' or ']'".to_string()),
+                }
+            }
+            document.close(index, count);
+            Ok(())
+        }
+    }
+
+    /// A node of a [`Document`]. Lookups that miss return `None`.
+    #[derive(Clone, Copy, Debug)]
+    pub struct NodeRef<'d> {
+        document: &'d Document,
+        index: usize,
+    }
+
+    impl<'d> NodeRef<'d> {
+        fn node(&self) -> Node {
+            self.document.nodes[self.index]
+        }
+
+        /// Index of the node after this one's subtree.
+        fn end(&self) -> usize {
+            match self.node() {
+                Node::Array { end, .. } | Node::Object { end, .. } => end,
+                _ => self.index + 1,
+            }
+        }
+
+        pub fn type_name(&self) -> &'static str {
+            match self.node() {
+                Node::Null => "null",
+                Node::Boolean(_) => "boolean",
+                Node::Number(n) if n.fract() == 0.0 => "integer",
+                Node::Number(_) => "number",
+                Node::String { .. } => "string",
+                Node::Array { .. } => "array",
+                Node::Object { .. } => "object",
+            }
+        }
+
+        pub fn is_null(&self) -> bool {
+            self.node() == Node::Null
+        }
+
+        pub fn is_array(&self) -> bool {
+            matches!(self.node(), Node::Array { .. })
+        }
+
+        pub fn is_object(&self) -> bool {
+            matches!(self.node(), Node::Object { .. })
+        }
+
+        pub fn as_bool(&self) -> Option<bool> {
+            match self.node() {
+                Node::Boolean(b) => Some(b),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64(&self) -> Option<f64> {
+            match self.node() {
+                Node::Number(n) => Some(n),
+                _ => None,
+            }
+        }
+
+        pub fn as_str(&self) -> Option<&'d str> {
+            match self.node() {
+                Node::String { start, len } => Some(&self.document.strings[start..start + len as usize]),
+                _ => None,
+            }
+        }
+
+        /// The number of elements or members of a container, 0 otherwise.
+        pub fn len(&self) -> usize {
+            match self.node() {
+                Node::Array { len, .. } | Node::Object { len, .. } => len as usize,
+                _ => 0,
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// The elements of an array; empty for any other node.
+        pub fn elements(&self) -> Children<'d> {
+            match self.node() {
+                Node::Array { len, .. } => Children { document: self.document, next: self.index + 1, remaining: len },
+                _ => Children { document: self.document, next: self.index, remaining: 0 },
+            }
+        }
+
+        /// The members of an object, in document order; empty for any other
+        /// node.
+        pub fn members(&self) -> Members<'d> {
+            match self.node() {
+                Node::Object { len, .. } => Members { document: self.document, next: self.index + 1, remaining: len },
+                _ => Members { document: self.document, next: self.index, remaining: 0 },
+            }
+        }
+
+        /// The value of `key`; the last one if the key is repeated.
+        pub fn get(&self, key: &str) -> Option<NodeRef<'d>> {
+            self.members().filter(|(k, _)| *k == key).last().map(|(_, v)| v)
+        }
+
+        pub fn get_index(&self, index: usize) -> Option<NodeRef<'d>> {
+            self.elements().nth(index)
+        }
+
+        pub fn get_path(&self, segments: &[PathSegment]) -> Option<NodeRef<'d>> {
+            let mut current = *self;
+            for segment in segments {
+                current = match segment {
+                    PathSegment::Key(key) => current.get(key)?,
+                    PathSegment::Index(index) => current.get_index(*index)?,
+                };
+            }
+            Some(current)
+        }
+
+        pub fn to_value(&self) -> JsonValue {
+            match self.node() {
+                Node::Null => JsonValue::Null,
+                Node::Boolean(b) => JsonValue::Boolean(b),
+                Node::Number(n) => JsonValue::Number(n),
+                Node::String { .. } => JsonValue::String(self.as_str().unwrap_or_default().to_string()),
+                Node::Array { .. } => JsonValue::Array(self.elements().map(|v| v.to_value()).collect()),
+                Node::Object { .. } => {
+                    JsonValue::Object(self.members().map(|(k, v)| (k.to_string(), v.to_value())).collect::<JsonMap>())
+                }
+            }
+        }
+
+        pub fn serialize(&self, options: &SerializeOptions) -> String {
+            let mut out = String::new();
+            self.write(options, 0, &mut out);
+            out
+        }
+
+        fn write(&self, options: &SerializeOptions, level: usize, out: &mut String) {
+            match self.node() {
+                Node::Object { len: 0, .. } => out.push_str("{}"),
+                Node::Object { .. } => {
+                    let mut members: Vec<(&str, NodeRef)> = self.members().collect();
+                    if options.sort_keys {
+                        members.sort_by(|a, b| a.0.cmp(b.0));
+                    }
+                    out.push('{');
+                    for (i, (key, value)) in members.into_iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        write_newline(options, level + 1, out);
+                        write_string(key, out);
+                        out.push_str(if options.indent.is_some() { ": " } else { ":" });
+                        value.write(options, level + 1, out);
+                    }
+                    write_newline(options, level, out);
+                    out.push('}');
+                }
+                Node::Array { len: 0, .. } => out.push_str("[]"),
+                Node::Array { .. } => {
+                    out.push('[');
+                    for (i, value) in self.elements().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        write_newline(options, level + 1, out);
+                        value.write(options, level + 1, out);
+                    }
+                    write_newline(options, level, out);
+                    out.push(']');
+                }
+                Node::String { .. } => write_string(self.as_str().unwrap_or_default(), out),
+                Node::Number(n) => write_number(n, options, out),
+                Node::Boolean(b) => out.push_str(if b { "true" } else { "false" }),
+                Node::Null => out.push_str("null"),
+            }
+        }
+    }
+
+    /// Iterator over the elements of an array node.
+    pub struct Children<'d> {
+        document: &'d Document,
+        next: usize,
+        remaining: u32,
+    }
+
+    impl<'d> Iterator for Children<'d> {
+        type Item = NodeRef<'d>;
+
+        fn next(&mut self) -> Option<NodeRef<'d>> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.remaining -= 1;
+            let node = self.document.node(self.next);
+            self.next = node.end();
+            Some(node)
+        }
+    }
+
+    /// Iterator over the members of an object node.
+    pub struct Members<'d> {
+        document: &'d Document,
+        next: usize,
+        remaining: u32,
+    }
+
+    impl<'d> Iterator for Members<'d> {
+        type Item = (&'d str, NodeRef<'d>);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.remaining -= 1;
+            let key = self.document.node(self.next).as_str().unwrap_or_default();
+            let value = self.document.node(self.next + 1);
+            self.next = value.end();
+            Some((key, value))
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::serializer::serialize;
+
+        const SAMPLE: &str = r#"{"name": "Ada", "tags": ["x", "", "é\n"], "nested": {"list": [[], {}, [1, [2.5, null]]], "ok": true}, "n": -3}"#;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        fn document(input: &str) -> Document {
+            Document::parse(input, ParseMode::Strict).unwrap()
+        }
+
+        #[test]
+        fn parsing_matches_the_tree_parser() {
+            for input in [SAMPLE, "[]", "{}", "0", "\"s\"", "null", "[[[[]]], {\"a\": {\"b\": {}}}]"] {
+                let parsed = document(input);
+                assert_eq!(parsed.to_value(), parse(input), "{}", input);
+                assert_eq!(parsed, Document::from_value(&parse(input)), "{}", input);
+                assert_eq!(parsed.to_json_string(), parse(input).to_json_string(), "{}", input);
+            }
+        }
+
+        #[test]
+        fn subtrees_are_skipped_by_their_end() {
+            let document = document(SAMPLE);
+            let root = document.root();
+            let keys: Vec<&str> = root.members().map(|(k, _)| k).collect();
+            assert_eq!(keys, ["name", "tags", "nested", "n"]);
+            assert_eq!(root.get("n").and_then(|n| n.as_f64()), Some(-3.0));
+            let list = document.get("nested.list").unwrap();
+            assert_eq!((list.len(), list.type_name()), (3, "array"));
+            assert_eq!(list.elements().map(|v| v.type_name()).collect::<Vec<_>>(), ["array", "object", "array"]);
+            assert!(list.get_index(0).unwrap().is_empty());
+            assert_eq!(document.get("nested.list[2][1][0]").unwrap().as_f64(), Some(2.5));
+            assert!(document.get("nested.list[2][1][1]").unwrap().is_null());
+            assert_eq!(document.get("nested.ok").unwrap().as_bool(), Some(true));
+            assert_eq!(document.get("tags[2]").unwrap().as_str(), Some("é\n"));
+            assert_eq!(document.get("tags[1]").unwrap().as_str(), Some(""));
+        }
+
+        #[test]
+        fn misses_and_mismatched_types_are_none() {
+            let document = document(SAMPLE);
+            for path in ["missing", "tags[3]", "name.first", "tags.x", "nested.list[0][0]", "[0]", "tags["] {
+                assert!(document.get(path).is_none(), "{}", path);
+            }
+            let name = document.get("name").unwrap();
+            assert_eq!((name.len(), name.elements().count(), name.members().count()), (0, 0, 0));
+            assert_eq!((name.as_f64(), name.as_bool()), (None, None));
+            assert_eq!(document.root().as_str(), None);
+            assert_eq!(document.get("tags").unwrap().members().count(), 0);
+        }
+
+        #[test]
+        fn duplicate_keys_follow_the_policy() {
+            let input = r#"{"a": [1, {"x": 1}], "b": 2, "a": {"c": 3}}"#;
+            let last = document(input);
+            assert_eq!(last.root().get("a").unwrap().to_value(), parse(r#"{"c": 3}"#));
+            assert_eq!(last.to_value(), parse(input));
+
+            let first = JsonParser::new(input).with_duplicate_keys(DuplicateKeys::First).parse_document().unwrap();
+            assert_eq!(first.to_json_string(), r#"{"a":[1,{"x":1}],"b":2}"#);
+            assert_eq!(first.root().len(), 2);
+            assert_eq!(first.get("b").unwrap().as_f64(), Some(2.0));
+
+            let err = JsonParser::new(input).with_duplicate_keys(DuplicateKeys::Error).parse_document().unwrap_err();
+            assert_eq!(err, "Duplicate key 'a' at position 29");
+            assert_eq!(JsonParser::new(input).with_duplicate_keys(DuplicateKeys::Error).parse().unwrap_err(), err);
+        }
+
+        #[test]
+        fn errors_and_modes_match_the_tree_parser() {
+            for input in ["[1,]", "{\"a\" 1}", "[1 2]", "\"open", "{\"a\": }", "[1] x", "", "tru"] {
+                let tree = JsonParser::new(input).parse().unwrap_err();
+                assert_eq!(Document::parse(input, ParseMode::Strict).unwrap_err(), tree, "{}", input);
+            }
+            let input = "// c\n{a: 'b', list: [0x10, .5,],}";
+            assert!(Document::parse(input, ParseMode::Jsonc).is_err());
+            let document = Document::parse(input, ParseMode::Json5).unwrap();
+            assert_eq!(document.to_json_string(), r#"{"a":"b","list":[16,0.5]}"#);
+        }
+
+        #[test]
+        fn output_and_search_agree_with_json_value() {
+            let value = parse(SAMPLE);
+            let sample = document(SAMPLE);
+            let sorted = SerializeOptions { sort_keys: true, ..SerializeOptions::default() };
+            assert_eq!(sample.serialize(&sorted), serialize(&value, &sorted));
+            assert_eq!(sample.to_pretty_string(), value.to_pretty_string());
+            let tree = document(r#"{"a": "X", "b": ["x", {"c": "x"}], "d": "y"}"#);
+            let (count, keys, indexes) = tree.get_all_occurrences("x");
+            assert_eq!(count, 3);
+            assert_eq!(keys, ["a", "b", "b.c"]);
+            assert_eq!(indexes, [None, Some(0), None]);
+        }
+
+        #[test]
+        fn stats_count_the_buffers() {
+            let document = document(r#"{"key": ["value", 1]}"#);
+            let stats = document.stats();
+            assert_eq!(stats.nodes, 5);
+            assert_eq!(stats.string_bytes, "keyvalue".len());
+            assert_eq!(stats.tape_bytes, 5 * std::mem::size_of::<Node>());
+            assert_eq!(stats.total_bytes(), stats.tape_bytes + stats.string_bytes);
+        }
+    }
@@ -0,0 +1,198 @@
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use crate::libs::path::parse_pointer;
+    use crate::libs::regex::Regex;
+
+    /// Checks a string against a JSON Schema `format`.
+    ///
+    /// Returns `None` for formats this crate does not know, which the
+    /// specification says must be ignored.
+    pub fn check_format(format: &str, s: &str) -> Option<bool> {
+        let valid = match format {
+            "date-time" => is_date_time(s),
+            "date" => is_date(s),
+            "time" => is_time(s),
+            "email" | "idn-email" => is_email(s),
+            "hostname" | "idn-hostname" => is_hostname(s),
+            "ipv4" => s.parse::<Ipv4Addr>().is_ok(),
+            "ipv6" => s.parse::<Ipv6Addr>().is_ok(),
+            "uri" | "iri" => is_uri(s),
+            "uri-reference" | "iri-reference" => !s.chars().any(|c| c.is_whitespace()),
+            "uuid" => is_uuid(s),
+            "regex" => Regex::new(s).is_ok(),
+            "json-pointer" => parse_pointer(s).is_ok(),
+            _ => return None,
+        };
+        Some(valid)
+    }
+
+    /// The most specific well-known format `s` conforms to, if any.
+    pub fn detect_format(s: &str) -> Option<&'static str> {
+        ["date-time", "date", "email", "uuid", "ipv4", "ipv6", "uri"]
+            .into_iter()
+            .find(|format| looks_like(format, s))
+    }
+
+    /// Whether `s` may be taken for a value of `format` when guessing one.
+    ///
+    /// Stricter than [`check_format`] for `uri`: any `word:text` is a valid
+    /// URI, so `localhost:8080` or `user:123` would be, and a guess needs
+    /// `//` after the scheme or one of the few schemes without it.
+    pub fn looks_like(format: &str, s: &str) -> bool {
+        if format == "uri" {
+            let Some((scheme, rest)) = s.split_once(':') else {
+                return false;
+            };
+            let known = matches!(scheme.to_ascii_lowercase().as_str(), "mailto" | "urn" | "tel" | "data");
+            return is_uri(s) && (rest.starts_with("//") || known);
+        }
+        check_format(format, s) == Some(true)
+    }
+
+    fn digits(s: &str, count: usize) -> Option<u32> {
+        if s.len() == count && s.chars().all(|c| c.is_ascii_digit()) {
+            s.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    fn is_date(s: &str) -> bool {
+        let parts: Vec<&str> = s.split('-').collect();
+        let [year, month, day] = parts[..] else {
+            return false;
+        };
+        let (Some(year), Some(month), Some(day)) = (digits(year, 4), digits(month, 2), digits(day, 2)) else {
+            return false;
+        };
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return false,
+        };
+        (1..=days).contains(&day)
+    }
+
+    fn is_time(s: &str) -> bool {
+        let upper = s.to_ascii_uppercase();
+        let (clock, offset) = if let Some(clock) = upper.strip_suffix('Z') {
+            (clock, None)
+        } else {
+            match upper.rfind(['+', '-']) {
+                Some(i) => (&upper[..i], Some(&upper[i + 1..])),
+                None => return false,
+            }
+        };
+        if let Some(offset) = offset {
+            let valid_offset = offset.split_once(':').is_some_and(|(h, m)| {
+                digits(h, 2).is_some_and(|h| h < 24) && digits(m, 2).is_some_and(|m| m < 60)
+            });
+            if !valid_offset {
+                return false;
+            }
+        }
+        let (clock, fraction) = clock.split_once('.').unwrap_or((clock, "0"));
+        if fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        let parts: Vec<&str> = clock.split(':').collect();
+        let [hour, minute, second] = parts[..] else {
+            return false;
+        };
+        digits(hour, 2).is_some_and(|h| h < 24)
+            && digits(minute, 2).is_some_and(|m| m < 60)
+            && digits(second, 2).is_some_and(|s| s <= 60)
+    }
+
+    fn is_date_time(s: &str) -> bool {
+        match s.find(['T', 't']) {
+            Some(i) => is_date(&s[..i]) && is_time(&s[i + 1..]),
+            None => false,
+        }
+    }
+
+    fn is_hostname(s: &str) -> bool {
+        let s = s.strip_suffix('.').unwrap_or(s);
+        !s.is_empty()
+            && s.len() <= 253
+            && s.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+            })
+    }
+
+    fn is_email(s: &str) -> bool {
+        let Some((local, domain)) = s.rsplit_once('@') else {
+            return false;
+        };
+        let local_valid = !local.is_empty()
+            && local.len() <= 64
+            && !local.starts_with('.')
+            && !local.ends_with('.')
+            && !local.contains("..")
+            && local
+                .chars()
+                .all(|c| c.is_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c));
+        local_valid && domain.contains('.') && is_hostname(domain)
+    }
+
+    fn is_uri(s: &str) -> bool {
+        let Some((scheme, rest)) = s.split_once(':') else {
+            return false;
+        };
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            && !rest.is_empty()
+            && !s.chars().any(|c| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\\'))
+    }
+
+    fn is_uuid(s: &str) -> bool {
+        let groups: Vec<&str> = s.split('-').collect();
+        groups.len() == 5
+            && groups
+                .iter()
+                .zip([8, 4, 4, 4, 12])
+                .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn formats_are_checked() {
+            assert_eq!(check_format("date-time", "2024-02-29T23:59:60.5+01:00"), Some(true));
+            assert_eq!(check_format("date", "2023-02-29"), Some(false));
+            assert_eq!(check_format("time", "24:00:00Z"), Some(false));
+            assert_eq!(check_format("email", "a.b@example.com"), Some(true));
+            assert_eq!(check_format("email", "a..b@example.com"), Some(false));
+            assert_eq!(check_format("hostname", "-bad.example"), Some(false));
+            assert_eq!(check_format("ipv6", "::1"), Some(true));
+            assert_eq!(check_format("uuid", "123e4567-e89b-12d3-a456-426614174000"), Some(true));
+            assert_eq!(check_format("json-pointer", "/a~2"), Some(false));
+            assert_eq!(check_format("unknown", "x"), None);
+        }
+
+        #[test]
+        fn uris_are_valid_more_often_than_guessed() {
+            for uri in ["localhost:8080", "user:123"] {
+                assert_eq!(check_format("uri", uri), Some(true));
+                assert!(!looks_like("uri", uri), "{}", uri);
+                assert_eq!(detect_format(uri), None);
+            }
+            for uri in ["https://example.com/a?b#c", "file:///tmp/x", "mailto:a@example.com", "urn:isbn:0451450523"] {
+                assert_eq!(detect_format(uri), Some("uri"), "{}", uri);
+            }
+            assert!(!looks_like("uri", "http://has space"));
+            assert_eq!(detect_format("a@example.com"), Some("email"));
+            assert_eq!(detect_format("2024-01-01"), Some("date"));
+        }
+    }
@@ -0,0 +1,271 @@
+    use std::collections::BTreeSet;
+
+    use crate::libs::formats::{detect_format, looks_like};
+    use crate::libs::json_parser::JsonValue;
+
+    /// Strings with at most this many distinct values become an `enum`.
+    const ENUM_MAX_VALUES: usize = 5;
+
+    /// Everything observed at one location of the sample documents.
+    #[derive(Default)]
+    struct Shape {
+        nulls: usize,
+        booleans: usize,
+        integers: usize,
+        numbers: usize,
+        strings: usize,
+        arrays: usize,
+        objects: usize,
+        /// Distinct string values, kept until there are too many for an enum.
+        string_values: Option<BTreeSet<String>>,
+        /// The format every string seen so far conforms to.
+        string_format: Option<&'static str>,
+        items: Option<Box<Shape>>,
+        /// Properties in the order they were first seen. The shape of each
+        /// property counts how many objects contained it.
+        properties: Vec<(String, Shape)>,
+    }
+
+    impl Shape {
+        fn observations(&self) -> usize {
+            self.nulls + self.booleans + self.integers + self.numbers + self.strings + self.arrays + self.objects
+        }
+
+        fn observe_string(&mut self, s: &str) {
+            if self.strings == 0 {
+                self.string_values = Some(BTreeSet::new());
+                self.string_format = detect_format(s);
+            } else if let Some(format) = self.string_format {
+                if !looks_like(format, s) {
+                    self.string_format = None;
+                }
+            }
+            self.strings += 1;
+            if let Some(values) = &mut self.string_values {
+                values.insert(s.to_string());
+                if values.len() > ENUM_MAX_VALUES {
+                    self.string_values = None;
+                }
+            }
+        }
+
+        fn types(&self) -> Vec<&'static str> {
+            let mut types = Vec::new();
+            let counts = [
+                (self.objects, "object"),
+                (self.arrays, "array"),
+                (self.strings, "string"),
+                (self.numbers, "number"),
+                (self.integers, if self.numbers > 0 { "" } else { "integer" }),
+                (self.booleans, "boolean"),
+                (self.nulls, "null"),
+            ];
+            for (count, name) in counts {
+                if count > 0 && !name.is_empty() {
+                    types.push(name);
+                }
+            }
+            types
+        }
+
+        fn to_schema(&self) -> JsonValue {
+            let mut schema = Vec::new();
+            let types = self.types();
+            match types.as_slice() {
+                [] => {}
+                [single] => schema.push(("type".to_string(), JsonValue::String(single.to_string()))),
+                many => schema.push((
+                    "type".to_string(),
+                    JsonValue::Array(many.iter().map(|t| JsonValue::String(t.to_string())).collect()),
+                )),
+            }
+
+            if self.strings > 0 {
+                if let Some(format) = self.string_format {
+                    schema.push(("format".to_string(), JsonValue::String(format.to_string())));
+                } else if let Some(values) = &self.string_values {
+                    // A value seen only once tells nothing about the set of
+                    // allowed values, so an enum needs repeated values.
+                    if types.len() == 1 && self.strings > values.len() {
+                        let values = values.iter().map(|v| JsonValue::String(v.clone())).collect();
+                        schema.push(("enum".to_string(), JsonValue::Array(values)));
+                    }
+                }
+            }
+
+            if let Some(items) = &self.items {
+                schema.push(("items".to_string(), items.to_schema()));
+            }
+
+            if self.objects > 0 {
+                let properties = self
+                    .properties
+                    .iter()
+                    .map(|(key, shape)| (key.clone(), shape.to_schema()))
+                    .collect();
+                schema.push(("properties".to_string(), JsonValue::Object(properties)));
+                let required: Vec<JsonValue> = self
+                    .properties
+                    .iter()
+                    .filter(|(_, shape)| shape.observations() == self.objects)
+                    .map(|(key, _)| JsonValue::String(key.clone()))
+                    .collect();
+                if !required.is_empty() {
+                    schema.push(("required".to_string(), JsonValue::Array(required)));
+                }
+            }
+            JsonValue::Object(schema.into())
+        }
+    }
+
+    /// Builds a draft JSON Schema from sample documents.
+    ///
+    /// Types seen at the same location are merged across samples, properties
+    /// present in every object are required, low-cardinality strings become
+    /// an `enum`, and common formats (date-time, date, email, uuid, ip
+    /// addresses and uri) are recognised.
+    #[derive(Default)]
+    pub struct SchemaInferrer {
+        root: Shape,
+    }
+
+    impl SchemaInferrer {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn add_sample(&mut self, sample: &JsonValue) {
+            fn observe(json: &JsonValue, shape: &mut Shape) {
+                match json {
+                    JsonValue::Object(obj) => {
+                        shape.objects += 1;
+                        for (key, value) in obj {
+                            let position = shape.properties.iter().position(|(k, _)| k == key);
+                            let index = position.unwrap_or_else(|| {
+                                shape.properties.push((key.clone(), Shape::default()));
+                                shape.properties.len() - 1
+                            });
+                            observe(value, &mut shape.properties[index].1);
+                        }
+                    }
+                    JsonValue::Array(arr) => {
+                        shape.arrays += 1;
+                        let items = shape.items.get_or_insert_with(Box::default);
+                        for value in arr {
+                            observe(value, items);
+                        }
+                    }
+                    JsonValue::String(s) => shape.observe_string(s),
+                    JsonValue::Number(n) if n.fract() == 0.0 => shape.integers += 1,
+                    JsonValue::Number(_) => shape.numbers += 1,
+                    JsonValue::Boolean(_) => shape.booleans += 1,
+                    JsonValue::Null => shape.nulls += 1,
+                }
+            }
+            observe(sample, &mut self.root);
+        }
+
+        pub fn schema(&self) -> JsonValue {
+            let mut schema = vec![(
+                "$schema".to_string(),
+                JsonValue::String("https://json-schema.org/draft/2020-12/schema".to_string()),
+            )];
+            if let JsonValue::Object(inferred) = self.root.to_schema() {
+                schema.extend(inferred);
+            }
+            JsonValue::Object(schema.into())
+        }
+    }
+
+    pub fn infer_schema(samples: &[JsonValue]) -> JsonValue {
+        let mut inferrer = SchemaInferrer::new();
+        for sample in samples {
+            inferrer.add_sample(sample);
+        }
+        inferrer.schema()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        /// The inferred schema without `$schema`, as compact JSON.
+        fn inferred(samples: &[&str]) -> String {
+            let samples: Vec<JsonValue> = samples.iter().map(|sample| parse(sample)).collect();
+            let JsonValue::Object(mut schema) = infer_schema(&samples) else {
+                panic!("the schema is not an object");
+            };
+            assert_eq!(
+                schema.remove("$schema"),
+                Some(JsonValue::String("https://json-schema.org/draft/2020-12/schema".to_string()))
+            );
+            JsonValue::Object(schema).to_json_string()
+        }
+
+        #[test]
+        fn types_are_merged() {
+            assert_eq!(inferred(&["1", "2"]), r#"{"type":"integer"}"#);
+            assert_eq!(inferred(&["1", "2.5"]), r#"{"type":"number"}"#);
+            assert_eq!(inferred(&["1", "null", "true"]), r#"{"type":["integer","boolean","null"]}"#);
+            assert_eq!(inferred(&["[1, \"a\"]"]), r#"{"type":"array","items":{"type":["string","integer"]}}"#);
+            assert_eq!(inferred(&["[]"]), r#"{"type":"array","items":{}}"#);
+            assert_eq!(inferred(&[]), "{}");
+        }
+
+        #[test]
+        fn properties_in_every_object_are_required() {
+            assert_eq!(
+                inferred(&[r#"{"id": 1, "name": "a"}"#, r#"{"id": 2, "tag": null}"#]),
+                r#"{"type":"object","properties":{"id":{"type":"integer"},"name":{"type":"string"},"tag":{"type":"null"}},"required":["id"]}"#
+            );
+            assert_eq!(
+                inferred(&[r#"[{"a": 1}, {"a": 2, "b": 3}]"#]),
+                r#"{"type":"array","items":{"type":"object","properties":{"a":{"type":"integer"},"b":{"type":"integer"}},"required":["a"]}}"#
+            );
+        }
+
+        #[test]
+        fn repeated_strings_become_an_enum() {
+            assert_eq!(inferred(&[r#"["red", "blue", "red"]"#]), r#"{"type":"array","items":{"type":"string","enum":["blue","red"]}}"#);
+            // Every value seen once says nothing about the allowed values.
+            assert_eq!(inferred(&[r#"["red", "blue"]"#]), r#"{"type":"array","items":{"type":"string"}}"#);
+            let many = r#"["a", "b", "c", "d", "e", "f", "a"]"#;
+            assert_eq!(inferred(&[many]), r#"{"type":"array","items":{"type":"string"}}"#);
+            assert_eq!(inferred(&[r#"["a", "a", 1]"#]), r#"{"type":"array","items":{"type":["string","integer"]}}"#);
+        }
+
+        #[test]
+        fn formats_shared_by_every_string_are_kept() {
+            assert_eq!(
+                inferred(&[r#""2024-01-01""#, r#""2024-02-03""#]),
+                r#"{"type":"string","format":"date"}"#
+            );
+            assert_eq!(inferred(&[r#""2024-01-01""#, r#""soon""#]), r#"{"type":"string"}"#);
+            assert_eq!(
+                inferred(&[r#""https://example.com""#, r#""mailto:a@example.com""#]),
+                r#"{"type":"string","format":"uri"}"#
+            );
+            assert_eq!(inferred(&[r#""https://example.com""#, r#""localhost:8080""#]), r#"{"type":"string"}"#);
+            assert_eq!(inferred(&[r#""user:123""#, r#""user:456""#]), r#"{"type":"string"}"#);
+        }
+
+        #[test]
+        fn samples_validate_against_their_schema() {
+            use crate::libs::schema::JsonSchema;
+
+            let samples = [
+                parse(r#"{"id": 1, "when": "2024-01-01T00:00:00Z", "tags": ["x"], "owner": {"email": "a@b.io"}}"#),
+                parse(r#"{"id": 2.5, "when": "2024-02-01T10:00:00Z", "tags": [], "owner": null}"#),
+            ];
+            let schema = JsonSchema::new(infer_schema(&samples)).unwrap();
+            for sample in &samples {
+                assert_eq!(schema.validate(sample), []);
+            }
+            assert!(!schema.is_valid(&parse(r#"{"id": "3", "when": "2024-01-01T00:00:00Z", "tags": []}"#)));
+        }
+    }
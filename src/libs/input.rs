@@ -0,0 +1,245 @@
+    use std::fmt;
+    use std::fs::File;
+    use std::io::{self, Read};
+
+    /// The encodings a JSON text may arrive in. RFC 8259 requires UTF-8
+    /// for exchanged JSON; the others are recognized so that they can be
+    /// transcoded instead of failing as garbled UTF-8.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Encoding {
+        Utf8,
+        Utf16Le,
+        Utf16Be,
+        Utf32Le,
+        Utf32Be,
+    }
+
+    impl fmt::Display for Encoding {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                Encoding::Utf8 => "UTF-8",
+                Encoding::Utf16Le => "UTF-16LE",
+                Encoding::Utf16Be => "UTF-16BE",
+                Encoding::Utf32Le => "UTF-32LE",
+                Encoding::Utf32Be => "UTF-32BE",
+            };
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Detects the encoding of a JSON text and the length of its byte order
+    /// mark, if any.
+    ///
+    /// Without a BOM, the encoding follows from where the zero bytes fall
+    /// in the first four bytes, since a JSON text starts with ASCII
+    /// (RFC 8259 section 8.1, RFC 4627 section 3).
+    pub fn detect_encoding(bytes: &[u8]) -> (Encoding, usize) {
+        match bytes {
+            [0x00, 0x00, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+            [0xFF, 0xFE, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+            [0x00, 0x00, 0x00, _, ..] => (Encoding::Utf32Be, 0),
+            [_, 0x00, 0x00, 0x00, ..] => (Encoding::Utf32Le, 0),
+            [0x00, _, 0x00, _, ..] | [0x00, _] | [0x00, _, _] => (Encoding::Utf16Be, 0),
+            [_, 0x00, _, 0x00, ..] | [_, 0x00] | [_, 0x00, _] => (Encoding::Utf16Le, 0),
+            _ => (Encoding::Utf8, 0),
+        }
+    }
+
+    /// Turns raw input into text, dropping any byte order mark.
+    ///
+    /// UTF-8 is validated in place, without copying; UTF-16 and UTF-32 are
+    /// transcoded. Errors give the byte offset in `bytes`.
+    pub fn decode(mut bytes: Vec<u8>) -> Result<String, String> {
+        let (encoding, bom) = detect_encoding(&bytes);
+        match encoding {
+            Encoding::Utf8 => {
+                if let Err(e) = std::str::from_utf8(&bytes[bom..]) {
+                    return Err(format!("Invalid UTF-8 at byte {}", bom + e.valid_up_to()));
+                }
+                bytes.drain(..bom);
+                Ok(String::from_utf8(bytes).expect("validated above"))
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => decode_utf16(&bytes, bom, encoding),
+            Encoding::Utf32Le | Encoding::Utf32Be => decode_utf32(&bytes, bom, encoding),
+        }
+    }
+
+    fn decode_utf16(bytes: &[u8], bom: usize, encoding: Encoding) -> Result<String, String> {
+        let body = &bytes[bom..];
+        if !body.len().is_multiple_of(2) {
+            return Err(format!("Truncated {} at byte {}", encoding, bytes.len() - 1));
+        }
+        let units = body.chunks_exact(2).map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if encoding == Encoding::Utf16Le {
+                u16::from_le_bytes(pair)
+            } else {
+                u16::from_be_bytes(pair)
+            }
+        });
+        let mut text = String::with_capacity(body.len() / 2);
+        let mut offset = bom;
+        for c in char::decode_utf16(units) {
+            match c {
+                Ok(c) => {
+                    text.push(c);
+                    offset += c.len_utf16() * 2;
+                }
+                Err(_) => return Err(format!("Invalid {} at byte {}", encoding, offset)),
+            }
+        }
+        Ok(text)
+    }
+
+    fn decode_utf32(bytes: &[u8], bom: usize, encoding: Encoding) -> Result<String, String> {
+        let body = &bytes[bom..];
+        if !body.len().is_multiple_of(4) {
+            return Err(format!("Truncated {} at byte {}", encoding, bytes.len() - body.len() % 4));
+        }
+        let mut text = String::with_capacity(body.len() / 4);
+        for (i, unit) in body.chunks_exact(4).enumerate() {
+            let unit = [unit[0], unit[1], unit[2], unit[3]];
+            let code = if encoding == Encoding::Utf32Le {
+                u32::from_le_bytes(unit)
+            } else {
+                u32::from_be_bytes(unit)
+            };
+            match char::from_u32(code) {
+                Some(c) => text.push(c),
+                None => return Err(format!("Invalid {} at byte {}", encoding, bom + i * 4)),
+            }
+        }
+        Ok(text)
+    }
+
+    /// Why an input could not be read as text.
+    #[derive(Debug)]
+    pub enum ReadError {
+        /// Reading the bytes failed.
+        Io(io::Error),
+        /// The bytes are not text in any of the [`Encoding`]s.
+        Decode(String),
+    }
+
+    impl fmt::Display for ReadError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ReadError::Io(err) => write!(f, "{}", err),
+                ReadError::Decode(message) => write!(f, "{}", message),
+            }
+        }
+    }
+
+    impl std::error::Error for ReadError {}
+
+    /// Reads everything from `reader` and decodes it with [`decode`].
+    pub fn read_text(reader: impl Read) -> Result<String, ReadError> {
+        read_with_capacity(reader, 0)
+    }
+
+    /// Reads a whole file into a buffer sized from its metadata, so the
+    /// text is held only once.
+    pub fn read_file(path: &str) -> Result<String, ReadError> {
+        let file = File::open(path).map_err(ReadError::Io)?;
+        let capacity = file.metadata().map_or(0, |metadata| metadata.len() as usize);
+        read_with_capacity(file, capacity)
+    }
+
+    fn read_with_capacity(mut reader: impl Read, capacity: usize) -> Result<String, ReadError> {
+        let mut bytes = Vec::with_capacity(capacity);
+        reader.read_to_end(&mut bytes).map_err(ReadError::Io)?;
+        decode(bytes).map_err(ReadError::Decode)
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const TEXT: &str = "{\"name\": \"Zoë 😀\", \"list\": [1, \"\u{ffff}\"]}";
+
+        fn encode(text: &str, encoding: Encoding, bom: bool) -> Vec<u8> {
+            let text = if bom { format!("\u{feff}{}", text) } else { text.to_string() };
+            match encoding {
+                Encoding::Utf8 => text.into_bytes(),
+                Encoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+                Encoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+                Encoding::Utf32Le => text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect(),
+                Encoding::Utf32Be => text.chars().flat_map(|c| (c as u32).to_be_bytes()).collect(),
+            }
+        }
+
+        const ENCODINGS: [Encoding; 5] =
+            [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be];
+
+        #[test]
+        fn every_encoding_is_detected_and_decoded() {
+            for encoding in ENCODINGS {
+                for bom in [false, true] {
+                    let bytes = encode(TEXT, encoding, bom);
+                    let bom_len = if bom { encode("", encoding, true).len() } else { 0 };
+                    assert_eq!(detect_encoding(&bytes), (encoding, bom_len), "{} {}", encoding, bom);
+                    assert_eq!(decode(bytes), Ok(TEXT.to_string()), "{} {}", encoding, bom);
+                }
+            }
+        }
+
+        #[test]
+        fn short_inputs_are_detected() {
+            for text in ["1", "12", "123", "[]", "\"\""] {
+                for encoding in ENCODINGS {
+                    let bytes = encode(text, encoding, false);
+                    assert_eq!(detect_encoding(&bytes).0, encoding, "{:?} {}", text, encoding);
+                    assert_eq!(decode(bytes), Ok(text.to_string()), "{:?} {}", text, encoding);
+                }
+            }
+            assert_eq!(detect_encoding(b""), (Encoding::Utf8, 0));
+            assert_eq!(decode(Vec::new()), Ok(String::new()));
+            assert_eq!(decode(vec![0xEF, 0xBB, 0xBF]), Ok(String::new()));
+        }
+
+        #[test]
+        fn only_the_leading_bom_is_dropped() {
+            let bytes = encode("\u{feff}\"\u{feff}\"", Encoding::Utf8, true);
+            assert_eq!(decode(bytes), Ok("\u{feff}\"\u{feff}\"".to_string()));
+            assert_eq!(Encoding::Utf16Be.to_string(), "UTF-16BE");
+        }
+
+        #[test]
+        fn errors_give_the_byte_offset() {
+            assert_eq!(decode(b"[\"a\xff\"]".to_vec()), Err("Invalid UTF-8 at byte 3".to_string()));
+            assert_eq!(decode(b"\xEF\xBB\xBF[\xc3]".to_vec()), Err("Invalid UTF-8 at byte 4".to_string()));
+
+            let mut lone = encode("[\"", Encoding::Utf16Le, true);
+            lone.extend([0x00, 0xD8, b'"', 0x00]);
+            assert_eq!(decode(lone), Err("Invalid UTF-16LE at byte 6".to_string()));
+            let mut odd = encode("[1]", Encoding::Utf16Be, false);
+            odd.push(0);
+            assert_eq!(decode(odd), Err("Truncated UTF-16BE at byte 6".to_string()));
+
+            let mut surrogate = encode("[", Encoding::Utf32Be, false);
+            surrogate.extend(0xD800u32.to_be_bytes());
+            assert_eq!(decode(surrogate), Err("Invalid UTF-32BE at byte 4".to_string()));
+            let mut truncated = encode("[1]", Encoding::Utf32Le, true);
+            truncated.extend([0x20, 0x00]);
+            assert_eq!(decode(truncated), Err("Truncated UTF-32LE at byte 16".to_string()));
+        }
+
+        #[test]
+        fn readers_decode_their_bytes() {
+            let bytes = encode(TEXT, Encoding::Utf16Le, true);
+            assert_eq!(read_text(bytes.as_slice()).unwrap(), TEXT);
+            let err = read_text(&b"\xff\xff\xff"[..]).unwrap_err();
+            assert!(matches!(err, ReadError::Decode(_)), "{}", err);
+
+            let path = std::env::temp_dir().join(format!("json_parser_input_{}.json", std::process::id()));
+            std::fs::write(&path, encode(TEXT, Encoding::Utf32Be, false)).unwrap();
+            let read = read_file(path.to_str().unwrap());
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(read.unwrap(), TEXT);
+            assert!(matches!(read_file(path.to_str().unwrap()), Err(ReadError::Io(_))));
+        }
+    }
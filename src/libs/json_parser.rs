@@ -1,7 +1,13 @@
+    use std::borrow::Cow;
     use std::fmt;
+    use std::str::FromStr;
 
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{format_path, parse_path, PathSegment};
+
+    #[derive(Clone, Debug)]
     pub enum JsonValue {
-        Object(Vec<(String, JsonValue)>),
+        Object(JsonMap),
         Array(Vec<JsonValue>),
         String(String),
         Number(f64),
@@ -9,61 +15,189 @@
         Null,
     }
 
+    /// Which JSON dialect the parser accepts.
+    ///
+    /// `Strict` is RFC 8259 JSON. `Jsonc` adds `//` and `/* */` comments and
+    /// trailing commas. `Json5` additionally accepts unquoted keys,
+    /// single-quoted strings, hexadecimal numbers, leading `+`, leading or
+    /// trailing decimal points, `Infinity` and `NaN`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum ParseMode {
+        #[default]
+        Strict,
+        Jsonc,
+        Json5,
+    }
+
+    impl ParseMode {
+        pub fn allows_comments(self) -> bool {
+            self != ParseMode::Strict
+        }
+
+        pub fn allows_trailing_commas(self) -> bool {
+            self != ParseMode::Strict
+        }
+
+        pub fn is_json5(self) -> bool {
+            self == ParseMode::Json5
+        }
+    }
+
+    impl FromStr for ParseMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_ascii_lowercase().as_str() {
+                "strict" | "json" => Ok(ParseMode::Strict),
+                "jsonc" => Ok(ParseMode::Jsonc),
+                "json5" => Ok(ParseMode::Json5),
+                other => Err(format!(
+                    "Unknown parse mode '{}' (expected strict, jsonc or json5)",
+                    other
+                )),
+            }
+        }
+    }
+
+    impl fmt::Display for ParseMode {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ParseMode::Strict => write!(f, "strict"),
+                ParseMode::Jsonc => write!(f, "jsonc"),
+                ParseMode::Json5 => write!(f, "json5"),
+            }
+        }
+    }
+
+    /// What the parser does when an object repeats a key.
+    ///
+    /// With `First` and `Last` the key keeps the position of its first
+    /// occurrence; only the value that is kept differs.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum DuplicateKeys {
+        First,
+        #[default]
+        Last,
+        Error,
+    }
+
     pub struct JsonParser<'a> {
         pub input: &'a str,
         pub position: usize,
+        pub mode: ParseMode,
+        pub duplicate_keys: DuplicateKeys,
     }
 
     impl<'a> JsonParser<'a> {
         pub fn new(input: &'a str) -> Self {
-            Self { input, position: 0 }
+            Self::with_mode(input, ParseMode::Strict)
+        }
+
+        pub fn with_mode(input: &'a str, mode: ParseMode) -> Self {
+            Self { input, position: 0, mode, duplicate_keys: DuplicateKeys::default() }
         }
 
+        /// Parses raw bytes, which are checked to be UTF-8 once up front so
+        /// that scanning can then work on bytes.
+        pub fn from_bytes(input: &'a [u8], mode: ParseMode) -> Result<Self, String> {
+            let input = std::str::from_utf8(input)
+                .map_err(|e| format!("Invalid UTF-8 at byte {}", e.valid_up_to()))?;
+            Ok(Self::with_mode(input, mode))
+        }
+
+        pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+            self.duplicate_keys = duplicate_keys;
+            self
+        }
+
+        /// Builds the error for input that only an extended mode accepts.
+        pub(crate) fn extension_error(&self, what: &str, needed: ParseMode) -> String {
+            let modes = if needed == ParseMode::Jsonc {
+                "--mode jsonc or --mode json5"
+            } else {
+                "--mode json5"
+            };
+            format!(
+                "{} at position {} are not allowed in {} mode (use {} to accept them)",
+                what, self.position, self.mode, modes
+            )
+        }
+
+        /// Parses the whole input as one value. Only whitespace and, when
+        /// the mode allows them, comments may follow it.
         pub fn parse(&mut self) -> Result<JsonValue, String> {
-            self.skip_whitespace();
-            match self.peek_char() {
-                Some('{') => self.parse_object(),
-                Some('[') => self.parse_array(),
-                Some('"') => self.parse_string(),
-                Some(c) if c.is_digit(10) || c == '-' => self.parse_number(),
-                Some('t') | Some('f') => self.parse_boolean(),
-                Some('n') => self.parse_null(),
+            let value = self.parse_value()?;
+            self.finish()?;
+            Ok(value)
+        }
+
+        /// Fails unless only trivia is left after the current position.
+        pub fn finish(&mut self) -> Result<(), String> {
+            self.skip_trivia()?;
+            if self.position < self.input.len() {
+                return Err(format!("Unexpected data after the value at position {}", self.position));
+            }
+            Ok(())
+        }
+
+        /// Parses the value at the current position and stops after it.
+        pub fn parse_value(&mut self) -> Result<JsonValue, String> {
+            self.skip_trivia()?;
+            match self.peek_byte() {
+                Some(b'{') => self.parse_object(),
+                Some(b'[') => self.parse_array(),
+                Some(b'"') | Some(b'\'') => self.parse_string(),
+                Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'I' | b'N') => self.parse_number(),
+                Some(b't') | Some(b'f') => self.parse_boolean(),
+                Some(b'n') => self.parse_null(),
                 _ => Err("Invalid JSON format".to_string()),
             }
         }
 
         pub fn parse_object(&mut self) -> Result<JsonValue, String> {
-            self.consume_char();
-            let mut object = Vec::new();
+            self.position += 1;
+            let mut object = JsonMap::new();
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b'}') {
+                self.position += 1;
+                return Ok(JsonValue::Object(object));
+            }
             loop {
-                self.skip_whitespace();
-                if self.peek_char() == Some('}') {
-                    self.consume_char();
-                    break;
-                }
+                let key_position = self.position;
+                let key = self.parse_key()?;
 
-                let key = if let JsonValue::String(key) = self.parse_string()? {
-                    key
-                } else {
-                    return Err("Expected a string key".to_string());
-                };
-
-                self.skip_whitespace();
-                if self.consume_char() != Some(':') {
+                self.skip_trivia()?;
+                if self.consume_byte() != Some(b':') {
                     return Err("Expected ':' after key".to_string());
                 }
-                self.skip_whitespace();
+                self.skip_trivia()?;
 
-                let value = self.parse()?;
-                object.push((key, value));
+                let value = self.parse_value()?;
+                match self.duplicate_keys {
+                    DuplicateKeys::First if object.contains_key(&key) => {}
+                    DuplicateKeys::Error if object.contains_key(&key) => {
+                        return Err(format!("Duplicate key '{}' at position {}", key, key_position));
+                    }
+                    _ => {
+                        object.insert(key, value);
+                    }
+                }
 
-                self.skip_whitespace();
-                match self.peek_char() {
-                    Some(',') => {
-                        self.consume_char();
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b'}') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
                     }
-                    Some('}') => {
-                        self.consume_char();
+                    Some(b'}') => {
+                        self.position += 1;
                         break;
                     }
                     _ => return Err("Expected ',' or '}'".to_string()),
@@ -72,24 +206,57 @@
             Ok(JsonValue::Object(object))
         }
 
+        pub(crate) fn parse_key(&mut self) -> Result<String, String> {
+            self.parse_key_cow().map(Cow::into_owned)
+        }
+
+        /// Parses an object key, borrowing it from the input when possible.
+        pub(crate) fn parse_key_cow(&mut self) -> Result<Cow<'a, str>, String> {
+            match self.peek_char() {
+                Some('"') | Some('\'') => self.parse_string_cow(),
+                Some(c) if is_identifier_start(c) => {
+                    if !self.mode.is_json5() {
+                        return Err(self.extension_error("Unquoted keys", ParseMode::Json5));
+                    }
+                    let start = self.position;
+                    while let Some(c) = self.peek_char() {
+                        if !is_identifier_part(c) {
+                            break;
+                        }
+                        self.consume_char();
+                    }
+                    Ok(Cow::Borrowed(&self.input[start..self.position]))
+                }
+                _ => Err("Expected a string key".to_string()),
+            }
+        }
+
         pub fn parse_array(&mut self) -> Result<JsonValue, String> {
-            self.consume_char();
+            self.position += 1;
             let mut array = Vec::new();
+            self.skip_trivia()?;
+            if self.peek_byte() == Some(b']') {
+                self.position += 1;
+                return Ok(JsonValue::Array(array));
+            }
             loop {
-                self.skip_whitespace();
-                if self.peek_char() == Some(']') {
-                    self.consume_char();
-                    break;
-                }
-                let value = self.parse()?;
+                let value = self.parse_value()?;
                 array.push(value);
-                self.skip_whitespace();
-                match self.peek_char() {
-                    Some(',') => {
-                        self.consume_char();
+                self.skip_trivia()?;
+                match self.peek_byte() {
+                    Some(b',') => {
+                        self.position += 1;
+                        self.skip_trivia()?;
+                        if self.peek_byte() == Some(b']') {
+                            if !self.mode.allows_trailing_commas() {
+                                return Err(self.extension_error("Trailing commas", ParseMode::Jsonc));
+                            }
+                            self.position += 1;
+                            break;
+                        }
                     }
-                    Some(']') => {
-                        self.consume_char();
+                    Some(b']') => {
+                        self.position += 1;
                         break;
                     }
                     _ => return Err("Expected ',' or ']'".to_string()),
@@ -99,28 +266,191 @@
         }
 
         pub fn parse_string(&mut self) -> Result<JsonValue, String> {
-            self.consume_char();
+            self.parse_string_cow().map(|s| JsonValue::String(s.into_owned()))
+        }
+
+        /// Parses a string literal. The result borrows from the input unless
+        /// the literal contains escapes.
+        pub(crate) fn parse_string_cow(&mut self) -> Result<Cow<'a, str>, String> {
+            let quote = match self.peek_char() {
+                Some('\'') if !self.mode.is_json5() => {
+                    return Err(self.extension_error("Single-quoted strings", ParseMode::Json5));
+                }
+                Some(c) => c,
+                None => return Err("Unterminated string".to_string()),
+            };
+            let quote = quote as u8;
+            self.position += 1;
             let start = self.position;
-            while let Some(c) = self.peek_char() {
-                if c == '"' {
-                    let s = &self.input[start..self.position];
-                    self.consume_char();
-                    return Ok(JsonValue::String(s.to_string()));
+            let end = self.find_string_special(quote)?;
+            if self.input.as_bytes()[end] == quote {
+                self.position = end + 1;
+                return Ok(Cow::Borrowed(&self.input[start..end]));
+            }
+            let mut s = String::with_capacity(end - start + 16);
+            s.push_str(&self.input[start..end]);
+            self.position = end;
+            self.parse_escaped_string(quote, s).map(Cow::Owned)
+        }
+
+        /// Finishes a string literal from its first escape, into `s`.
+        fn parse_escaped_string(&mut self, quote: u8, mut s: String) -> Result<String, String> {
+            loop {
+                let end = self.find_string_special(quote)?;
+                s.push_str(&self.input[self.position..end]);
+                self.position = end + 1;
+                if self.input.as_bytes()[end] == quote {
+                    return Ok(s);
                 }
-                self.consume_char();
+                self.parse_escape(&mut s)?;
             }
-            Err("Unterminated string".to_string())
+        }
+
+        /// Position of the next quote or backslash from the current
+        /// position, failing on unescaped line breaks and end of input.
+        /// Other control characters are only allowed raw in JSON5.
+        fn find_string_special(&self, quote: u8) -> Result<usize, String> {
+            let bytes = self.input.as_bytes();
+            let mut from = self.position;
+            loop {
+                let Some(offset) = find_string_special(&bytes[from..], quote) else {
+                    return Err("Unterminated string".to_string());
+                };
+                let at = from + offset;
+                match bytes[at] {
+                    b'\n' | b'\r' => return Err("Unescaped line break in string".to_string()),
+                    b if b < 0x20 && !self.mode.is_json5() => {
+                        return Err(format!("Unescaped control character U+{:04X} in string at position {}", b, at));
+                    }
+                    b if b < 0x20 => from = at + 1,
+                    _ => return Ok(at),
+                }
+            }
+        }
+
+        fn parse_escape(&mut self, out: &mut String) -> Result<(), String> {
+            match self.consume_char() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('/') => out.push('/'),
+                Some('b') => out.push('\u{8}'),
+                Some('f') => out.push('\u{c}'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('u') => {
+                    let high = self.parse_hex_digits(4)?;
+                    let code = if (0xD800..0xDC00).contains(&high)
+                        && self.input[self.position..].starts_with("\\u")
+                    {
+                        self.position += 2;
+                        let low = self.parse_hex_digits(4)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err("Invalid unicode surrogate pair".to_string());
+                        }
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    } else {
+                        high
+                    };
+                    out.push(char::from_u32(code).ok_or("Invalid unicode escape")?);
+                }
+                Some(c) if self.mode.is_json5() => match c {
+                    '\'' => out.push('\''),
+                    'v' => out.push('\u{b}'),
+                    '0' => out.push('\0'),
+                    'x' => {
+                        let code = self.parse_hex_digits(2)?;
+                        out.push(char::from_u32(code).ok_or("Invalid hex escape")?);
+                    }
+                    '\r' => {
+                        if self.peek_char() == Some('\n') {
+                            self.consume_char();
+                        }
+                    }
+                    '\n' | '\u{2028}' | '\u{2029}' => {}
+                    c => out.push(c),
+                },
+                Some('\'') => {
+                    return Err(self.extension_error("Escaped single quotes", ParseMode::Json5));
+                }
+                Some(c) => return Err(format!("Invalid escape sequence '\\{}'", c)),
+                None => return Err("Unterminated string".to_string()),
+            }
+            Ok(())
+        }
+
+        fn parse_hex_digits(&mut self, count: usize) -> Result<u32, String> {
+            let digits = self
+                .input
+                .get(self.position..self.position + count)
+                .filter(|d| d.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or("Invalid hex digits in escape sequence")?;
+            self.position += count;
+            u32::from_str_radix(digits, 16).map_err(|e| e.to_string())
         }
 
         pub fn parse_number(&mut self) -> Result<JsonValue, String> {
             let start = self.position;
-            while let Some(c) = self.peek_char() {
-                if !c.is_digit(10) && c != '.' && c != '-' {
-                    break;
+            let mut sign = 1.0;
+            match self.peek_byte() {
+                Some(b'-') => {
+                    sign = -1.0;
+                    self.position += 1;
                 }
-                self.consume_char();
+                Some(b'+') => {
+                    if !self.mode.is_json5() {
+                        return Err(self.extension_error("Leading '+' signs", ParseMode::Json5));
+                    }
+                    self.position += 1;
+                }
+                _ => {}
+            }
+
+            let rest = &self.input[self.position..];
+            for (word, value) in [("Infinity", f64::INFINITY), ("NaN", f64::NAN)] {
+                if rest.starts_with(word) {
+                    if !self.mode.is_json5() {
+                        return Err(self.extension_error("Infinity and NaN literals", ParseMode::Json5));
+                    }
+                    self.position += word.len();
+                    return Ok(JsonValue::Number(sign * value));
+                }
+            }
+            if rest.starts_with("0x") || rest.starts_with("0X") {
+                if !self.mode.is_json5() {
+                    return Err(self.extension_error("Hexadecimal numbers", ParseMode::Json5));
+                }
+                self.position += 2;
+                let digits_start = self.position;
+                while self.peek_byte().is_some_and(|b| b.is_ascii_hexdigit()) {
+                    self.position += 1;
+                }
+                let number = u64::from_str_radix(&self.input[digits_start..self.position], 16)
+                    .map_err(|_| "Invalid number format".to_string())?;
+                return Ok(JsonValue::Number(sign * number as f64));
+            }
+
+            let digits_start = self.position;
+            while let Some(b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-') = self.peek_byte() {
+                self.position += 1;
+            }
+            let digits = &self.input[digits_start..self.position];
+            if digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit() {
+                return Err(format!("Leading zeros are not allowed at position {}", digits_start));
+            }
+            // JSON needs a digit on both sides of the point, as in `1.0e5`;
+            // `.5`, `5.` and `5.e3` are JSON5 only.
+            let bare_point = digits.find('.').is_some_and(|i| {
+                i == 0 || !digits.as_bytes().get(i + 1).is_some_and(u8::is_ascii_digit)
+            });
+            if !self.mode.is_json5() && bare_point {
+                return Err(self.extension_error("Leading or trailing decimal points", ParseMode::Json5));
+            }
+            if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                return Err("Invalid number format".to_string());
             }
             let number: f64 = self.input[start..self.position]
+                .trim_start_matches('+')
                 .parse()
                 .map_err(|_| "Invalid number format".to_string())?;
             Ok(JsonValue::Number(number))
@@ -147,30 +477,150 @@
             }
         }
 
+        /// Skips the four JSON whitespace bytes; JSON5 also allows the
+        /// other Unicode spaces, line separators and the byte order mark.
         pub fn skip_whitespace(&mut self) {
-            while let Some(c) = self.peek_char() {
-                if c.is_whitespace() {
-                    self.consume_char();
+            let bytes = self.input.as_bytes();
+            while let Some(&b) = bytes.get(self.position) {
+                match b {
+                    b' ' | b'\t' | b'\n' | b'\r' => self.position += 1,
+                    _ if self.mode.is_json5() => match self.peek_char() {
+                        Some(c) if c.is_whitespace() || c == '\u{feff}' => self.position += c.len_utf8(),
+                        _ => break,
+                    },
+                    _ => break,
+                }
+            }
+        }
+
+        /// Skips whitespace and, when the mode allows them, comments.
+        pub fn skip_trivia(&mut self) -> Result<(), String> {
+            loop {
+                self.skip_whitespace();
+                let rest = &self.input[self.position..];
+                if !rest.starts_with("//") && !rest.starts_with("/*") {
+                    return Ok(());
+                }
+                if !self.mode.allows_comments() {
+                    return Err(self.extension_error("Comments", ParseMode::Jsonc));
+                }
+                if rest.starts_with("//") {
+                    let end = rest.find('\n').unwrap_or(rest.len());
+                    self.position += end;
                 } else {
-                    break;
+                    let end = rest[2..].find("*/").ok_or("Unterminated block comment")?;
+                    self.position += end + 4;
                 }
             }
         }
 
         pub fn peek_char(&self) -> Option<char> {
-            self.input[self.position..].chars().next()
+            match self.peek_byte()? {
+                b if b.is_ascii() => Some(b as char),
+                _ => self.input[self.position..].chars().next(),
+            }
         }
 
         pub fn consume_char(&mut self) -> Option<char> {
-            let mut iter = self.input[self.position..].char_indices();
-            let (_, c) = iter.next()?;
-            let (next_index, _) = iter.next().unwrap_or((1, ' '));
-            self.position += next_index;
+            let c = self.peek_char()?;
+            self.position += c.len_utf8();
             Some(c)
         }
+
+        pub fn peek_byte(&self) -> Option<u8> {
+            self.input.as_bytes().get(self.position).copied()
+        }
+
+        /// Consumes one byte; only for bytes known to be ASCII, so that the
+        /// position stays on a character boundary.
+        pub(crate) fn consume_byte(&mut self) -> Option<u8> {
+            let b = self.peek_byte().filter(u8::is_ascii)?;
+            self.position += 1;
+            Some(b)
+        }
+    }
+
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGHS: u64 = 0x8080_8080_8080_8080;
+
+    /// Marks the bytes of `word` equal to `byte` by setting their high bit.
+    /// Only the lowest mark is exact, which is the only one used.
+    fn mark_byte(word: u64, byte: u8) -> u64 {
+        let x = word ^ (ONES * byte as u64);
+        x.wrapping_sub(ONES) & !x & HIGHS
+    }
+
+    /// Marks the bytes of `word` below 0x20, with the same caveat as
+    /// [`mark_byte`].
+    fn mark_control(word: u64) -> u64 {
+        word.wrapping_sub(ONES * 0x20) & !word & HIGHS
+    }
+
+    /// Finds the first quote, backslash or control character (line breaks
+    /// included) in `bytes`, eight bytes at a time.
+    pub(crate) fn find_string_special(bytes: &[u8], quote: u8) -> Option<usize> {
+        let mut chunks = bytes.chunks_exact(8);
+        let mut offset = 0;
+        for chunk in chunks.by_ref() {
+            let word = u64::from_le_bytes(chunk.try_into().unwrap());
+            let marks = mark_byte(word, quote) | mark_byte(word, b'\\') | mark_control(word);
+            if marks != 0 {
+                return Some(offset + marks.trailing_zeros() as usize / 8);
+            }
+            offset += 8;
+        }
+        chunks
+            .remainder()
+            .iter()
+            .position(|&b| b == quote || b == b'\\' || b < 0x20)
+            .map(|i| offset + i)
+    }
+
+    /// Escapes a string for output between double quotes.
+    ///
+    /// Only the characters JSON requires to be escaped are touched: the
+    /// quote, the backslash and control characters.
+    pub fn escape_string(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '\u{8}' => escaped.push_str("\\b"),
+                '\u{c}' => escaped.push_str("\\f"),
+                c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    fn is_identifier_start(c: char) -> bool {
+        c.is_alphabetic() || c == '_' || c == '$'
+    }
+
+    fn is_identifier_part(c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || c == '$'
     }
 
     impl JsonValue {
+        /// The JSON Schema name of the value's type; whole numbers are
+        /// reported as `integer`.
+        pub fn type_name(&self) -> &'static str {
+            match self {
+                JsonValue::Object(_) => "object",
+                JsonValue::Array(_) => "array",
+                JsonValue::String(_) => "string",
+                JsonValue::Number(n) if n.fract() == 0.0 => "integer",
+                JsonValue::Number(_) => "number",
+                JsonValue::Boolean(_) => "boolean",
+                JsonValue::Null => "null",
+            }
+        }
+
         pub fn to_lowercase(&self) -> JsonValue {
             match self {
                 JsonValue::Object(obj) => {
@@ -225,7 +675,7 @@
                     }
                     JsonValue::Array(arr) => {
                         for (i, value) in arr.iter().enumerate() {
-                            let new_path = format!("{}", path);
+                            let new_path = path.clone();
                             if value.to_lowercase() == target.to_lowercase() {
                                 *count += 1;
                                 keys.push(new_path.clone());
@@ -237,34 +687,11 @@
                     _ => {}
                 }
             }
-            search(self, &target, String::new(), &mut count, &mut keys, &mut indexes);
+            search(self, target, String::new(), &mut count, &mut keys, &mut indexes);
 
             (count, keys, indexes)
         }
 
-        pub fn handle_json_logic(&self, key: Option<&String>, index: Option<usize>) {
-            if let Some(k) = key {
-                if let Some(v) = self.get(k) {
-                    if let Some(idx) = index {
-                        if let Some(item) = v.get_index(idx - 1) {
-                            println!("{}", item);
-                        } else {
-                            println!(
-                                "Element not found in '{}' at index {}.\nThe JSON :\n {}",
-                                k, idx, self
-                            );
-                        }
-                    } else {
-                        println!("{}", v);
-                    }
-                } else {
-                    println!("Key '{}' not found.\nThe JSON file: \n{}", k, self);
-                }
-            } else {
-                println!("{}", self);
-            }
-        }
-
         pub fn get_index(&self, index: usize) -> Option<&JsonValue> {
             if let JsonValue::Array(arr) = self {
                 arr.get(index)
@@ -273,23 +700,140 @@
             }
         }
 
+        /// Looks up a path such as `details.city` or `users[0].name`.
         pub fn get(&self, key_path: &str) -> Option<&JsonValue> {
-            let keys: Vec<&str> = key_path.split('.').collect();
+            self.get_path(&parse_path(key_path).ok()?)
+        }
+
+        pub fn get_mut(&mut self, key_path: &str) -> Option<&mut JsonValue> {
+            self.get_path_mut(&parse_path(key_path).ok()?)
+        }
+
+        pub fn get_path(&self, segments: &[PathSegment]) -> Option<&JsonValue> {
+            let mut current_value = self;
+            for segment in segments {
+                current_value = match (current_value, segment) {
+                    (JsonValue::Object(obj), PathSegment::Key(key)) => obj.get(key)?,
+                    (JsonValue::Array(arr), PathSegment::Index(index)) => arr.get(*index)?,
+                    _ => return None,
+                };
+            }
+            Some(current_value)
+        }
+
+        pub fn get_path_mut(&mut self, segments: &[PathSegment]) -> Option<&mut JsonValue> {
             let mut current_value = self;
+            for segment in segments {
+                current_value = match (current_value, segment) {
+                    (JsonValue::Object(obj), PathSegment::Key(key)) => obj.get_mut(key)?,
+                    (JsonValue::Array(arr), PathSegment::Index(index)) => arr.get_mut(*index)?,
+                    _ => return None,
+                };
+            }
+            Some(current_value)
+        }
 
-            for key in keys {
-                if let JsonValue::Object(obj) = current_value {
-                    if let Some((_, value)) = obj.iter().find(|(k, _)| k == key) {
-                        current_value = value;
-                    } else {
-                        return None;
+        /// Splits a path into its parent container and the last segment.
+        fn parent_mut(&mut self, key_path: &str) -> Result<(&mut JsonValue, PathSegment), String> {
+            let mut segments = parse_path(key_path)?;
+            let last = segments
+                .pop()
+                .ok_or_else(|| "The document root has no parent".to_string())?;
+            let parent = self
+                .get_path_mut(&segments)
+                .ok_or_else(|| format!("Key '{}' not found", format_path(&segments)))?;
+            Ok((parent, last))
+        }
+
+        /// Sets the value at `key_path` and returns the value it replaced.
+        ///
+        /// A missing key is added to its parent object; array elements must
+        /// already exist.
+        pub fn set(&mut self, key_path: &str, value: JsonValue) -> Result<Option<JsonValue>, String> {
+            if key_path.is_empty() {
+                return Ok(Some(std::mem::replace(self, value)));
+            }
+            let (parent, last) = self.parent_mut(key_path)?;
+            match (parent, last) {
+                (JsonValue::Object(obj), PathSegment::Key(key)) => Ok(obj.insert(key, value)),
+                (JsonValue::Array(arr), PathSegment::Index(index)) => {
+                    let len = arr.len();
+                    let existing = arr.get_mut(index).ok_or_else(|| {
+                        format!("Index {} is out of bounds in '{}' (length {})", index, key_path, len)
+                    })?;
+                    Ok(Some(std::mem::replace(existing, value)))
+                }
+                _ => Err(format!("Path '{}' does not match the document structure", key_path)),
+            }
+        }
+
+        /// Inserts a new key into an object, or an element into an array at
+        /// the given index, shifting the following elements.
+        pub fn insert(&mut self, key_path: &str, value: JsonValue) -> Result<(), String> {
+            let (parent, last) = self.parent_mut(key_path)?;
+            match (parent, last) {
+                (JsonValue::Object(obj), PathSegment::Key(key)) => {
+                    if obj.contains_key(&key) {
+                        return Err(format!("Key '{}' already exists", key_path));
                     }
-                } else {
-                    return None;
+                    obj.insert(key, value);
+                    Ok(())
+                }
+                (JsonValue::Array(arr), PathSegment::Index(index)) => {
+                    if index > arr.len() {
+                        return Err(format!(
+                            "Index {} is out of bounds in '{}' (length {})",
+                            index,
+                            key_path,
+                            arr.len()
+                        ));
+                    }
+                    arr.insert(index, value);
+                    Ok(())
                 }
+                _ => Err(format!("Path '{}' does not match the document structure", key_path)),
             }
+        }
 
-            Some(current_value)
+        /// Appends a value to the array at `key_path`.
+        pub fn push(&mut self, key_path: &str, value: JsonValue) -> Result<(), String> {
+            match self.get_mut(key_path) {
+                Some(JsonValue::Array(arr)) => {
+                    arr.push(value);
+                    Ok(())
+                }
+                Some(_) => Err(format!("'{}' is not an array", key_path)),
+                None => Err(format!("Key '{}' not found", key_path)),
+            }
+        }
+
+        /// Removes the member or element at `key_path` and returns it.
+        pub fn remove(&mut self, key_path: &str) -> Result<JsonValue, String> {
+            let (parent, last) = self.parent_mut(key_path)?;
+            match (parent, last) {
+                (JsonValue::Object(obj), PathSegment::Key(key)) => obj
+                    .remove(&key)
+                    .ok_or_else(|| format!("Key '{}' not found", key_path)),
+                (JsonValue::Array(arr), PathSegment::Index(index)) if index < arr.len() => {
+                    Ok(arr.remove(index))
+                }
+                _ => Err(format!("Key '{}' not found", key_path)),
+            }
+        }
+
+        /// Renames the object key at `key_path`, keeping its position.
+        pub fn rename_key(&mut self, key_path: &str, new_key: &str) -> Result<(), String> {
+            let (parent, last) = self.parent_mut(key_path)?;
+            let (JsonValue::Object(obj), PathSegment::Key(key)) = (parent, last) else {
+                return Err(format!("'{}' is not an object key", key_path));
+            };
+            if obj.contains_key(new_key) {
+                return Err(format!("Key '{}' already exists", new_key));
+            }
+            if !obj.rename(&key, new_key) {
+                return Err(format!("Key '{}' not found", key_path));
+            }
+            Ok(())
         }
     }
 
@@ -328,7 +872,7 @@
                                 "{}{}\"{}\"{}: {}",
                                 inner_padding,
                                 color_key,
-                                key,
+                                escape_string(key),
                                 reset,
                                 format_json(value, indent + 4, colors)
                             );
@@ -361,7 +905,7 @@
                             reset
                         )
                     }
-                    JsonValue::String(s) => format!("{}\"{}\"{}", color_string, s, reset),
+                    JsonValue::String(s) => format!("{}\"{}\"{}", color_string, escape_string(s), reset),
                     JsonValue::Number(n) => format!("{}{}{}", color_number, n, reset),
                     JsonValue::Boolean(b) => format!("{}{}{}", color_boolean, b, reset),
                     JsonValue::Null => format!("{}null{}", color_null, reset),
@@ -393,4 +937,264 @@
                 _ => false,
             }
         }
-    }
\ No newline at end of file
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn parse(input: &str, mode: ParseMode) -> Result<JsonValue, String> {
+            JsonParser::with_mode(input, mode).parse()
+        }
+
+        const MODES: [ParseMode; 3] = [ParseMode::Strict, ParseMode::Jsonc, ParseMode::Json5];
+
+        #[test]
+        fn strict_mode_rejects_the_extensions() {
+            for input in [
+                "[1, 2,]",
+                "{\"a\": 1,}",
+                "// note\n1",
+                "/* note */ 1",
+                "{a: 1}",
+                "'text'",
+                "0x1F",
+                "+1",
+                ".5",
+                "5.",
+                "1.e5",
+                "{\"a\":1.E-5}",
+                "Infinity",
+                "NaN",
+            ] {
+                assert!(parse(input, ParseMode::Strict).is_err(), "{}", input);
+            }
+        }
+
+        #[test]
+        fn jsonc_adds_comments_and_trailing_commas_only() {
+            let value = parse("// note\n{\"a\": [1, 2,], /* inline */ \"b\": true,}", ParseMode::Jsonc).unwrap();
+            assert_eq!(value.get("a").unwrap().get_index(1), Some(&JsonValue::Number(2.0)));
+            assert_eq!(value.get("b"), Some(&JsonValue::Boolean(true)));
+            for input in ["{a: 1}", "'text'", "0x1F", "Infinity"] {
+                let err = parse(input, ParseMode::Jsonc).unwrap_err();
+                assert!(err.contains("--mode json5"), "{}: {}", input, err);
+            }
+        }
+
+        #[test]
+        fn json5_accepts_its_literals() {
+            let value = parse("{a: 'it\\'s', hex: 0x1F, plus: +1, half: .5, whole: 5., inf: -Infinity}", ParseMode::Json5).unwrap();
+            assert_eq!(value.get("a"), Some(&JsonValue::String("it's".to_string())));
+            assert_eq!(value.get("hex"), Some(&JsonValue::Number(31.0)));
+            assert_eq!(value.get("plus"), Some(&JsonValue::Number(1.0)));
+            assert_eq!(value.get("half"), Some(&JsonValue::Number(0.5)));
+            assert_eq!(value.get("whole"), Some(&JsonValue::Number(5.0)));
+            assert!(matches!(value.get("inf"), Some(JsonValue::Number(n)) if *n == f64::NEG_INFINITY));
+            assert!(matches!(parse("NaN", ParseMode::Json5), Ok(JsonValue::Number(n)) if n.is_nan()));
+            assert_eq!(parse("1.e5", ParseMode::Json5), Ok(JsonValue::Number(100000.0)));
+        }
+
+        #[test]
+        fn fractions_and_exponents_need_digits() {
+            for mode in [ParseMode::Strict, ParseMode::Jsonc] {
+                let err = parse("1.e5", mode).unwrap_err();
+                assert!(err.starts_with("Leading or trailing decimal points"), "{}: {}", mode, err);
+                assert_eq!(parse("1.0e5", mode), Ok(JsonValue::Number(100000.0)));
+                assert_eq!(parse("-1.5E+2", mode), Ok(JsonValue::Number(-150.0)));
+            }
+            for mode in MODES {
+                for input in ["1e", "1e+", "1E-", "1.5e", "-"] {
+                    assert!(parse(input, mode).is_err(), "{} in {}", input, mode);
+                }
+            }
+        }
+
+        #[test]
+        fn leading_zeros_are_rejected_in_every_mode() {
+            for mode in MODES {
+                for input in ["01", "-01", "[00.5]", "{\"a\": 007}"] {
+                    let err = parse(input, mode).unwrap_err();
+                    assert!(err.starts_with("Leading zeros"), "{} in {}: {}", input, mode, err);
+                }
+                for input in ["0", "-0", "0.5", "0e3", "10"] {
+                    assert!(parse(input, mode).is_ok(), "{} in {}", input, mode);
+                }
+            }
+        }
+
+        #[test]
+        fn raw_control_characters_need_json5() {
+            for mode in [ParseMode::Strict, ParseMode::Jsonc] {
+                for input in ["\"a\tb\"", "\"\u{1}\"", "[\"long enough to scan by words \u{1f}\"]"] {
+                    let err = parse(input, mode).unwrap_err();
+                    assert!(err.contains("control character"), "{:?} in {}: {}", input, mode, err);
+                }
+            }
+            assert_eq!(parse("\"a\tb\"", ParseMode::Json5), Ok(JsonValue::String("a\tb".to_string())));
+            assert_eq!(parse("\"a\\tb\"", ParseMode::Strict), Ok(JsonValue::String("a\tb".to_string())));
+            for mode in MODES {
+                assert!(parse("\"a\nb\"", mode).unwrap_err().contains("line break"));
+            }
+        }
+
+        #[test]
+        fn trailing_data_is_rejected() {
+            for mode in MODES {
+                for input in ["[1, 2] garbage", "{} {}", "1 2", "true false", "\"a\" ,"] {
+                    let err = parse(input, mode).unwrap_err();
+                    assert!(err.starts_with("Unexpected data after the value"), "{} in {}: {}", input, mode, err);
+                }
+            }
+            assert!(parse("[1] // done\n", ParseMode::Jsonc).is_ok());
+            assert!(parse("[1] // done\n", ParseMode::Strict).is_err());
+            assert!(parse(" [1] \n\t", ParseMode::Strict).is_ok());
+        }
+
+        #[test]
+        fn string_scanning_matches_byte_by_byte() {
+            let input = b"0123456789abcdef\"";
+            for start in 0..input.len() {
+                assert_eq!(find_string_special(&input[start..], b'"'), Some(input.len() - 1 - start));
+            }
+            for special in [b'\\', b'\n', b'\r', 0x00, 0x1f, b'\''] {
+                for at in 0..20 {
+                    let mut bytes = vec![b'x'; 24];
+                    bytes[at] = special;
+                    let found = find_string_special(&bytes, b'\'');
+                    assert_eq!(found, Some(at), "{:#x} at {}", special, at);
+                }
+            }
+            assert_eq!(find_string_special(b"no specials here, \x7f\x20 ~", b'"'), None);
+        }
+
+        #[test]
+        fn string_scanning_agrees_with_a_naive_scan() {
+            let naive = |bytes: &[u8], quote: u8| bytes.iter().position(|&b| b == quote || b == b'\\' || b < 0x20);
+            // Bytes next to the specials catch borrows leaking between lanes.
+            let alphabet = [b'a', b'"', b'\'', b'\\', 0x00, 0x1f, 0x20, 0x21, 0x5b, 0x5d, 0x7f, 0x80, 0xc3, 0xff];
+            let mut seed = 0x2545_f491_4f6c_dd1du64;
+            for len in 0..40 {
+                for _ in 0..50 {
+                    let bytes: Vec<u8> = (0..len)
+                        .map(|_| {
+                            seed ^= seed << 13;
+                            seed ^= seed >> 7;
+                            seed ^= seed << 17;
+                            alphabet[(seed % alphabet.len() as u64) as usize]
+                        })
+                        .collect();
+                    for quote in [b'"', b'\''] {
+                        assert_eq!(find_string_special(&bytes, quote), naive(&bytes, quote), "{:?}", bytes);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn strings_spanning_words_parse_in_every_mode() {
+            for len in 0..24 {
+                let text = "é€😀x".repeat(len);
+                let input = format!("[\"{}\", \"{}\\n\"]", text, text);
+                for mode in MODES {
+                    let value = parse(&input, mode).unwrap();
+                    assert_eq!(value.get_index(0), Some(&JsonValue::String(text.clone())));
+                    assert_eq!(value.get_index(1), Some(&JsonValue::String(format!("{}\n", text))));
+                }
+                let unterminated = format!("\"{}", text);
+                assert_eq!(parse(&unterminated, ParseMode::Strict).unwrap_err(), "Unterminated string");
+            }
+            let err = parse(&format!("\"{}\u{1}\"", "x".repeat(13)), ParseMode::Strict).unwrap_err();
+            assert!(err.ends_with("at position 14"), "{}", err);
+        }
+
+        #[test]
+        fn long_whitespace_runs_are_skipped() {
+            for len in [0, 7, 8, 9, 64, 1000] {
+                let space = " \t\r\n".repeat(len);
+                let input = format!("{}[{}1{},{}2]{}", space, space, space, space, space);
+                for mode in MODES {
+                    assert_eq!(parse(&input, mode).unwrap().to_json_string(), "[1,2]");
+                }
+            }
+            assert!(parse("[1,\u{a0}2]", ParseMode::Strict).is_err());
+            assert!(parse("\u{feff}\u{2028}[1,\u{a0}2]", ParseMode::Json5).is_ok());
+        }
+
+        #[test]
+        fn bytes_must_be_utf8() {
+            let parsed = JsonParser::from_bytes("[\"é\"]".as_bytes(), ParseMode::Strict).unwrap().parse();
+            assert_eq!(parsed.unwrap().to_json_string(), "[\"é\"]");
+            let err = JsonParser::from_bytes(b"[\"ab\xff\"]", ParseMode::Strict).err().unwrap();
+            assert_eq!(err, "Invalid UTF-8 at byte 4");
+            assert!(JsonParser::from_bytes(b"\"\xc3\"", ParseMode::Json5).is_err());
+        }
+
+        fn document() -> JsonValue {
+            parse(r#"{"a": {"b": 1}, "list": [1, 2]}"#, ParseMode::Strict).unwrap()
+        }
+
+        #[test]
+        fn set_replaces_or_adds_values() {
+            let mut value = document();
+            assert_eq!(value.set("a.b", JsonValue::Number(2.0)), Ok(Some(JsonValue::Number(1.0))));
+            assert_eq!(value.set("a.c", JsonValue::Null), Ok(None));
+            assert_eq!(value.set("list[1]", JsonValue::Boolean(true)), Ok(Some(JsonValue::Number(2.0))));
+            assert_eq!(value.to_json_string(), r#"{"a":{"b":2,"c":null},"list":[1,true]}"#);
+
+            let err = value.set("list[2]", JsonValue::Null).unwrap_err();
+            assert_eq!(err, "Index 2 is out of bounds in 'list[2]' (length 2)");
+            assert_eq!(value.set("missing.b", JsonValue::Null).unwrap_err(), "Key 'missing' not found");
+            assert!(value.set("list.b", JsonValue::Null).is_err());
+            let root = value.set("", JsonValue::Null).unwrap().unwrap();
+            assert_eq!(root.to_json_string(), r#"{"a":{"b":2,"c":null},"list":[1,true]}"#);
+            assert_eq!(value, JsonValue::Null);
+        }
+
+        #[test]
+        fn insert_adds_new_keys_and_shifts_elements() {
+            let mut value = document();
+            value.insert("a.c", JsonValue::Null).unwrap();
+            value.insert("list[0]", JsonValue::Number(0.0)).unwrap();
+            value.insert("list[3]", JsonValue::Number(3.0)).unwrap();
+            assert_eq!(value.to_json_string(), r#"{"a":{"b":1,"c":null},"list":[0,1,2,3]}"#);
+
+            assert_eq!(value.insert("a.b", JsonValue::Null).unwrap_err(), "Key 'a.b' already exists");
+            let err = value.insert("list[5]", JsonValue::Null).unwrap_err();
+            assert_eq!(err, "Index 5 is out of bounds in 'list[5]' (length 4)");
+            assert_eq!(value.insert("", JsonValue::Null).unwrap_err(), "The document root has no parent");
+        }
+
+        #[test]
+        fn push_appends_to_arrays_only() {
+            let mut value = document();
+            value.push("list", JsonValue::Number(3.0)).unwrap();
+            assert_eq!(value.get("list").unwrap().get_index(2), Some(&JsonValue::Number(3.0)));
+            assert_eq!(value.push("a", JsonValue::Null).unwrap_err(), "'a' is not an array");
+            assert_eq!(value.push("missing", JsonValue::Null).unwrap_err(), "Key 'missing' not found");
+        }
+
+        #[test]
+        fn remove_returns_the_removed_value() {
+            let mut value = document();
+            assert_eq!(value.remove("a.b"), Ok(JsonValue::Number(1.0)));
+            assert_eq!(value.remove("list[0]"), Ok(JsonValue::Number(1.0)));
+            assert_eq!(value.to_json_string(), r#"{"a":{},"list":[2]}"#);
+
+            assert_eq!(value.remove("a.b").unwrap_err(), "Key 'a.b' not found");
+            assert_eq!(value.remove("list[1]").unwrap_err(), "Key 'list[1]' not found");
+            assert_eq!(value.remove("").unwrap_err(), "The document root has no parent");
+        }
+
+        #[test]
+        fn rename_key_keeps_the_position() {
+            let mut value = document();
+            value.rename_key("a", "z").unwrap();
+            assert_eq!(value.to_json_string(), r#"{"z":{"b":1},"list":[1,2]}"#);
+
+            assert_eq!(value.rename_key("z", "list").unwrap_err(), "Key 'list' already exists");
+            assert_eq!(value.rename_key("a", "y").unwrap_err(), "Key 'a' not found");
+            assert_eq!(value.rename_key("list[0]", "y").unwrap_err(), "'list[0]' is not an object key");
+            assert_eq!(value.rename_key("", "y").unwrap_err(), "The document root has no parent");
+        }
+    }
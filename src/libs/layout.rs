@@ -0,0 +1,333 @@
+    use crate::libs::cst::{CstDocument, CstElement, CstNode, CstToken, NodeKind, TokenKind};
+    use crate::libs::json_parser::{JsonParser, JsonValue, ParseMode};
+    use crate::libs::serializer::{write_newline, write_number_lexeme, write_string, SerializeOptions};
+
+    /// Lays a parsed document out again with `options`, as [`serialize`]
+    /// would, without losing what a `JsonValue` cannot hold: comments stay
+    /// with the elements they describe, repeated keys are all kept, and a
+    /// number keeps its lexeme when an `f64` cannot hold it exactly.
+    ///
+    /// With `comments` off, comments are dropped instead.
+    ///
+    /// [`serialize`]: crate::libs::serializer::serialize
+    pub fn format_document(document: &CstDocument, options: &SerializeOptions, comments: bool) -> String {
+        let mut writer = Writer { options, comments, out: String::new() };
+        writer.document(&document.root);
+        // Compact text only ends in a line break after a `//` comment, which
+        // the end of the text closes as well.
+        if writer.out.ends_with('\n') {
+            writer.out.pop();
+        }
+        writer.out
+    }
+
+    /// An element of a container with the comments around it.
+    struct Item<'a> {
+        /// Comments on the lines before the element.
+        leading: Vec<&'a CstToken>,
+        node: &'a CstNode,
+        /// Comments on the line where the element ends.
+        trailing: Vec<&'a CstToken>,
+    }
+
+    /// Splits the children of a container, or of the document, into its
+    /// elements and the comments left after the last one. Members are
+    /// sorted by key with `sort_keys`, taking their comments along.
+    fn items<'a>(node: &'a CstNode, comments: bool, sort_keys: bool) -> (Vec<Item<'a>>, Vec<&'a CstToken>) {
+        let mut items: Vec<Item> = Vec::new();
+        let mut pending = Vec::new();
+        let mut same_line = false;
+        for child in &node.children {
+            match child {
+                CstElement::Node(element) => {
+                    let leading = std::mem::take(&mut pending);
+                    items.push(Item { leading, node: element, trailing: Vec::new() });
+                    same_line = true;
+                }
+                CstElement::Token(token) => match token.kind {
+                    TokenKind::Whitespace if token.text.contains('\n') => same_line = false,
+                    TokenKind::LineComment | TokenKind::BlockComment if comments => match items.last_mut() {
+                        Some(item) if same_line => item.trailing.push(token),
+                        _ => pending.push(token),
+                    },
+                    _ => {}
+                },
+            }
+        }
+        if sort_keys && node.kind == NodeKind::Object {
+            items.sort_by_cached_key(|item| item.node.member_key().unwrap_or_default());
+        }
+        (items, pending)
+    }
+
+    fn has_comments(node: &CstNode) -> bool {
+        node.children.iter().any(|child| match child {
+            CstElement::Token(token) => matches!(token.kind, TokenKind::LineComment | TokenKind::BlockComment),
+            CstElement::Node(node) => has_comments(node),
+        })
+    }
+
+    struct Writer<'o> {
+        options: &'o SerializeOptions,
+        comments: bool,
+        out: String,
+    }
+
+    impl Writer<'_> {
+        fn newline(&mut self, level: usize) {
+            write_newline(self.options, level, &mut self.out);
+        }
+
+        fn space(&mut self) {
+            if self.options.indent.is_some() {
+                self.out.push(' ');
+            }
+        }
+
+        /// Writes a comment. Without indentation there is no line break to
+        /// end a `//` comment, so one is added.
+        fn comment(&mut self, token: &CstToken) {
+            self.out.push_str(token.text.trim_end());
+            if token.kind == TokenKind::LineComment && self.options.indent.is_none() {
+                self.out.push('\n');
+            }
+        }
+
+        /// Comments before the value go on their own lines, as do those
+        /// after it that did not share its last line.
+        fn document(&mut self, root: &CstNode) {
+            let (items, dangling) = items(root, self.comments, false);
+            for item in items {
+                for comment in item.leading {
+                    self.comment(comment);
+                    self.newline(0);
+                }
+                self.value(item.node, 0);
+                for comment in item.trailing {
+                    self.space();
+                    self.comment(comment);
+                }
+            }
+            for comment in dangling {
+                self.newline(0);
+                self.comment(comment);
+            }
+        }
+
+        fn value(&mut self, node: &CstNode, level: usize) {
+            match node.kind {
+                NodeKind::Object | NodeKind::Array => self.container(node, level),
+                NodeKind::Member => self.member(node, level),
+                _ => self.scalar(node),
+            }
+        }
+
+        /// Strings are written with only the escapes JSON requires and
+        /// numbers as [`write_number_lexeme`] does; literals stay as written.
+        fn scalar(&mut self, node: &CstNode) {
+            let Some(CstElement::Token(token)) = node.children.first() else {
+                return;
+            };
+            let mut parser = JsonParser::with_mode(&token.text, ParseMode::Json5);
+            match token.kind {
+                TokenKind::String => match parser.parse_string() {
+                    Ok(JsonValue::String(s)) => write_string(&s, &mut self.out),
+                    _ => self.out.push_str(&token.text),
+                },
+                TokenKind::Number => match parser.parse_number() {
+                    Ok(JsonValue::Number(n)) => write_number_lexeme(&token.text, n, self.options, &mut self.out),
+                    _ => self.out.push_str(&token.text),
+                },
+                _ => self.out.push_str(&token.text),
+            }
+        }
+
+        fn container(&mut self, node: &CstNode, level: usize) {
+            let (open, close) = if node.kind == NodeKind::Object { ('{', '}') } else { ('[', ']') };
+            let (items, dangling) = items(node, self.comments, self.options.sort_keys);
+            if items.is_empty() && dangling.is_empty() {
+                self.out.push(open);
+                self.out.push(close);
+                return;
+            }
+            if node.kind == NodeKind::Array && self.fits_on_line(node) {
+                return;
+            }
+            self.out.push(open);
+            let count = items.len();
+            for (i, item) in items.into_iter().enumerate() {
+                for comment in item.leading {
+                    self.newline(level + 1);
+                    self.comment(comment);
+                }
+                self.newline(level + 1);
+                self.value(item.node, level + 1);
+                if i + 1 < count {
+                    self.out.push(',');
+                }
+                for comment in item.trailing {
+                    self.space();
+                    self.comment(comment);
+                }
+            }
+            for comment in dangling {
+                self.newline(level + 1);
+                self.comment(comment);
+            }
+            self.newline(level);
+            self.out.push(close);
+        }
+
+        /// Comments between the key and the value follow the colon.
+        fn member(&mut self, node: &CstNode, level: usize) {
+            write_string(&node.member_key().unwrap_or_default(), &mut self.out);
+            self.out.push(':');
+            self.space();
+            for child in &node.children {
+                match child {
+                    CstElement::Token(token) if self.comments && token.kind == TokenKind::LineComment => {
+                        self.comment(token);
+                        self.newline(level);
+                    }
+                    CstElement::Token(token) if self.comments && token.kind == TokenKind::BlockComment => {
+                        self.comment(token);
+                        self.space();
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(value) = node.member_value() {
+                self.value(value, level);
+            }
+        }
+
+        /// With a wrap width, writes an array without comments on the
+        /// current line if it fits there.
+        fn fits_on_line(&mut self, node: &CstNode) -> bool {
+            let (Some(width), Some(_)) = (self.options.wrap_width, &self.options.indent) else {
+                return false;
+            };
+            if self.comments && has_comments(node) {
+                return false;
+            }
+            let column = self.out[self.out.rfind('\n').map_or(0, |i| i + 1)..].chars().count();
+            let room = width.saturating_sub(column);
+            let mut line = Writer { options: self.options, comments: false, out: String::new() };
+            // A character takes at most 4 bytes, so the line is over the
+            // width once it exceeds 4 bytes per column left.
+            line.inline(node, room * 4);
+            if line.out.chars().count() > room {
+                return false;
+            }
+            self.out.push_str(&line.out);
+            true
+        }
+
+        /// Writes `node` on one line with a space after each separator,
+        /// giving up once the text is longer than `limit` bytes.
+        fn inline(&mut self, node: &CstNode, limit: usize) {
+            let (open, close) = match node.kind {
+                NodeKind::Object => ('{', '}'),
+                NodeKind::Array => ('[', ']'),
+                _ => return self.scalar(node),
+            };
+            self.out.push(open);
+            for (i, item) in items(node, false, self.options.sort_keys).0.into_iter().enumerate() {
+                if self.out.len() > limit {
+                    return;
+                }
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+                match item.node.member_value() {
+                    Some(value) if item.node.kind == NodeKind::Member => {
+                        write_string(&item.node.member_key().unwrap_or_default(), &mut self.out);
+                        self.out.push_str(": ");
+                        self.inline(value, limit);
+                    }
+                    _ => self.inline(item.node, limit),
+                }
+            }
+            self.out.push(close);
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::serializer::serialize;
+
+        fn format(input: &str, options: &SerializeOptions) -> String {
+            format_document(&CstDocument::parse(input, ParseMode::Json5).unwrap(), options, true)
+        }
+
+        #[test]
+        fn plain_documents_match_the_serializer() {
+            let input = r#"{"b": [1.0, 0.50, -0, 1e21, 1E-7], "a": {"x": "café \/", "y": []}, "c": [{}, [true, null]]}"#;
+            let value = JsonParser::new(input).parse().unwrap();
+            for options in [
+                SerializeOptions::default(),
+                SerializeOptions::compact(),
+                SerializeOptions::minified(),
+                SerializeOptions { sort_keys: true, wrap_width: Some(30), ..SerializeOptions::pretty(2) },
+            ] {
+                assert_eq!(format(input, &options), serialize(&value, &options), "{:?}", options);
+            }
+        }
+
+        #[test]
+        fn numbers_keep_digits_an_f64_cannot_hold() {
+            let options = SerializeOptions::compact();
+            assert_eq!(
+                format("[12345678901234567890, 3.141592653589793238, 1e400, -1e400, 0.1, 1.10, 100]", &options),
+                "[12345678901234567890,3.141592653589793238,1e400,-1e400,0.1,1.1,100]"
+            );
+            assert_eq!(format("[1000, 0.00000010, 12345678901234567890]", &SerializeOptions::minified()), "[1e3,1e-7,12345678901234567890]");
+            assert_eq!(
+                format("[0x1F, +1, .5, 5., 0x20000000000001, Infinity, -Infinity, NaN]", &options),
+                "[31,1,0.5,5,0x20000000000001,Infinity,-Infinity,NaN]"
+            );
+        }
+
+        #[test]
+        fn comments_stay_with_their_elements() {
+            let input = "// header\n{\n  \"b\": 1, // one\n  /* about a */\n  \"a\": [2, // two\n  3],\n  \"c\": { // none\n  },\n  \"d\": /* inline */ 4\n} // end\n// tail\n";
+            let expected = "// header\n{\n    \"b\": 1, // one\n    /* about a */\n    \"a\": [\n        2, // two\n        3\n    ],\n    \"c\": {\n        // none\n    },\n    \"d\": /* inline */ 4\n} // end\n// tail";
+            assert_eq!(format(input, &SerializeOptions::default()), expected);
+            // Formatting is stable.
+            assert_eq!(format(expected, &SerializeOptions::default()), expected);
+
+            let sorted = SerializeOptions { sort_keys: true, ..SerializeOptions::pretty(2) };
+            assert_eq!(
+                format("{\"b\": 1, // one\n/* about a */ \"a\": 2}", &sorted),
+                "{\n  /* about a */\n  \"a\": 2,\n  \"b\": 1 // one\n}"
+            );
+        }
+
+        #[test]
+        fn compact_text_ends_line_comments() {
+            let input = "// header\n[1, // one\n/* two */ 2] // end";
+            let compact = format(input, &SerializeOptions::compact());
+            assert_eq!(compact, "// header\n[1,// one\n/* two */2]// end");
+            let reparsed = CstDocument::parse(&compact, ParseMode::Jsonc).unwrap().to_value().unwrap();
+            assert_eq!(reparsed.to_json_string(), "[1,2]");
+
+            let document = CstDocument::parse(input, ParseMode::Jsonc).unwrap();
+            assert_eq!(format_document(&document, &SerializeOptions::compact(), false), "[1,2]");
+        }
+
+        #[test]
+        fn arrays_with_comments_are_not_wrapped() {
+            let options = SerializeOptions { wrap_width: Some(80), ..SerializeOptions::pretty(2) };
+            assert_eq!(format("{\"a\": [1, 2], \"b\": [1, /* c */ 2]}", &options), "{\n  \"a\": [1, 2],\n  \"b\": [\n    1, /* c */\n    2\n  ]\n}");
+            let options = SerializeOptions { wrap_width: Some(10), ..SerializeOptions::pretty(2) };
+            assert_eq!(format("[[1, 2], [12345678901234567890]]", &options), "[\n  [1, 2],\n  [\n    12345678901234567890\n  ]\n]");
+        }
+
+        #[test]
+        fn repeated_keys_and_json5_keys_are_kept() {
+            let output = format("{a: 1, 'b': 2, \"a\": 3}", &SerializeOptions::compact());
+            assert_eq!(output, r#"{"a":1,"b":2,"a":3}"#);
+        }
+    }
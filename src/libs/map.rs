@@ -0,0 +1,373 @@
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use crate::libs::json_parser::JsonValue;
+
+    /// Objects up to this size are searched linearly, which is faster than
+    /// hashing and spares building the index for the many small objects
+    /// of a typical document.
+    const INDEX_THRESHOLD: usize = 8;
+
+    /// The members of a JSON object: kept in insertion order, looked up by
+    /// hash once there are more than a few of them.
+    ///
+    /// Keys are unique. Inserting an existing key replaces its value in
+    /// place, so the key keeps its original position.
+    #[derive(Clone, Default)]
+    pub struct JsonMap {
+        entries: Vec<(String, JsonValue)>,
+        /// Position of each key; empty while the map is small enough to scan.
+        index: HashMap<String, usize>,
+    }
+
+    impl JsonMap {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn with_capacity(capacity: usize) -> Self {
+            Self { entries: Vec::with_capacity(capacity), index: HashMap::new() }
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        pub fn clear(&mut self) {
+            self.entries.clear();
+            self.index.clear();
+        }
+
+        pub fn get(&self, key: &str) -> Option<&JsonValue> {
+            self.get_index_of(key).map(|i| &self.entries[i].1)
+        }
+
+        pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+            self.get_index_of(key).map(|i| &mut self.entries[i].1)
+        }
+
+        pub fn contains_key(&self, key: &str) -> bool {
+            self.get_index_of(key).is_some()
+        }
+
+        /// The position of `key` in iteration order.
+        pub fn get_index_of(&self, key: &str) -> Option<usize> {
+            if self.entries.len() <= INDEX_THRESHOLD {
+                self.entries.iter().position(|(k, _)| k == key)
+            } else {
+                self.index.get(key).copied()
+            }
+        }
+
+        pub fn get_index(&self, index: usize) -> Option<(&String, &JsonValue)> {
+            self.entries.get(index).map(|(k, v)| (k, v))
+        }
+
+        pub fn get_index_mut(&mut self, index: usize) -> Option<(&String, &mut JsonValue)> {
+            self.entries.get_mut(index).map(|(k, v)| (&*k, v))
+        }
+
+        /// Adds a member at the end, or replaces the value of an existing key
+        /// and returns the previous value.
+        pub fn insert(&mut self, key: String, value: JsonValue) -> Option<JsonValue> {
+            if let Some(i) = self.get_index_of(&key) {
+                return Some(std::mem::replace(&mut self.entries[i].1, value));
+            }
+            if self.entries.len() == INDEX_THRESHOLD {
+                self.entries.push((key, value));
+                self.reindex();
+            } else {
+                if self.entries.len() > INDEX_THRESHOLD {
+                    self.index.insert(key.clone(), self.entries.len());
+                }
+                self.entries.push((key, value));
+            }
+            None
+        }
+
+        /// Removes a member, keeping the order of the others.
+        pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+            let i = self.get_index_of(key)?;
+            let (_, value) = self.entries.remove(i);
+            if self.entries.len() <= INDEX_THRESHOLD {
+                self.index.clear();
+            } else {
+                self.index.remove(key);
+                for (k, _) in &self.entries[i..] {
+                    if let Some(position) = self.index.get_mut(k) {
+                        *position -= 1;
+                    }
+                }
+            }
+            Some(value)
+        }
+
+        /// Renames `old` to `new` in place. Returns `false` if `old` is
+        /// missing or `new` is already a key.
+        pub fn rename(&mut self, old: &str, new: &str) -> bool {
+            if self.contains_key(new) {
+                return false;
+            }
+            let Some(i) = self.get_index_of(old) else {
+                return false;
+            };
+            self.entries[i].0 = new.to_string();
+            if self.index.remove(old).is_some() {
+                self.index.insert(new.to_string(), i);
+            }
+            true
+        }
+
+        /// Keeps the members for which `f` returns `true`.
+        pub fn retain(&mut self, mut f: impl FnMut(&String, &mut JsonValue) -> bool) {
+            self.entries.retain_mut(|(k, v)| f(k, v));
+            self.reindex();
+        }
+
+        /// Sorts the members by key.
+        pub fn sort_keys(&mut self) {
+            self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+            self.reindex();
+        }
+
+        fn reindex(&mut self) {
+            self.index.clear();
+            if self.entries.len() <= INDEX_THRESHOLD {
+                return;
+            }
+            for (i, (k, _)) in self.entries.iter().enumerate() {
+                self.index.insert(k.clone(), i);
+            }
+        }
+
+        pub fn iter(&self) -> std::slice::Iter<'_, (String, JsonValue)> {
+            self.entries.iter()
+        }
+
+        pub fn iter_mut(&mut self) -> IterMut<'_> {
+            IterMut(self.entries.iter_mut())
+        }
+
+        pub fn keys(&self) -> impl Iterator<Item = &String> {
+            self.entries.iter().map(|(k, _)| k)
+        }
+
+        pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+            self.entries.iter().map(|(_, v)| v)
+        }
+
+        pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JsonValue> {
+            self.entries.iter_mut().map(|(_, v)| v)
+        }
+
+        /// The members in order, as key-value pairs.
+        pub fn as_slice(&self) -> &[(String, JsonValue)] {
+            &self.entries
+        }
+    }
+
+    /// Mutable iteration over the members; keys stay read-only so that the
+    /// index cannot go stale.
+    pub struct IterMut<'a>(std::slice::IterMut<'a, (String, JsonValue)>);
+
+    impl<'a> Iterator for IterMut<'a> {
+        type Item = (&'a String, &'a mut JsonValue);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next().map(|(k, v)| (&*k, v))
+        }
+    }
+
+    impl PartialEq for JsonMap {
+        fn eq(&self, other: &Self) -> bool {
+            self.entries == other.entries
+        }
+    }
+
+    impl fmt::Debug for JsonMap {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_map().entries(self.entries.iter().map(|(k, v)| (k, v))).finish()
+        }
+    }
+
+    /// Later duplicates replace the value of earlier ones.
+    impl FromIterator<(String, JsonValue)> for JsonMap {
+        fn from_iter<I: IntoIterator<Item = (String, JsonValue)>>(iter: I) -> Self {
+            let mut map = JsonMap::new();
+            map.extend(iter);
+            map
+        }
+    }
+
+    impl Extend<(String, JsonValue)> for JsonMap {
+        fn extend<I: IntoIterator<Item = (String, JsonValue)>>(&mut self, iter: I) {
+            for (key, value) in iter {
+                self.insert(key, value);
+            }
+        }
+    }
+
+    impl From<Vec<(String, JsonValue)>> for JsonMap {
+        fn from(entries: Vec<(String, JsonValue)>) -> Self {
+            entries.into_iter().collect()
+        }
+    }
+
+    impl IntoIterator for JsonMap {
+        type Item = (String, JsonValue);
+        type IntoIter = std::vec::IntoIter<(String, JsonValue)>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.entries.into_iter()
+        }
+    }
+
+    impl<'a> IntoIterator for &'a JsonMap {
+        type Item = &'a (String, JsonValue);
+        type IntoIter = std::slice::Iter<'a, (String, JsonValue)>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.entries.iter()
+        }
+    }
+
+    impl<'a> IntoIterator for &'a mut JsonMap {
+        type Item = (&'a String, &'a mut JsonValue);
+        type IntoIter = IterMut<'a>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter_mut()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::{DuplicateKeys, JsonParser};
+
+        fn member(i: usize) -> (String, JsonValue) {
+            (format!("k{}", i), JsonValue::Number(i as f64))
+        }
+
+        fn keys(map: &JsonMap) -> Vec<&str> {
+            map.keys().map(String::as_str).collect()
+        }
+
+        /// Every key is found at its position, through the index or not.
+        fn assert_consistent(map: &JsonMap) {
+            for (i, (key, value)) in map.iter().enumerate() {
+                assert_eq!(map.get_index_of(key), Some(i), "{}", key);
+                assert_eq!(map.get(key), Some(value));
+            }
+            if map.len() > INDEX_THRESHOLD {
+                assert_eq!(map.index.len(), map.len());
+            } else {
+                assert!(map.index.is_empty());
+            }
+        }
+
+        #[test]
+        fn insertion_order_is_kept_on_both_sides_of_the_threshold() {
+            for size in [1, INDEX_THRESHOLD, INDEX_THRESHOLD + 1, 50] {
+                let mut members: JsonMap = (0..size).rev().map(member).collect();
+                assert_eq!(members.len(), size);
+                assert_eq!(members.get_index(0).map(|(k, _)| k.clone()), Some(format!("k{}", size - 1)));
+                assert_consistent(&members);
+                assert!(!members.contains_key("missing"));
+
+                // Replacing a value keeps the key where it was.
+                assert_eq!(members.insert("k0".to_string(), JsonValue::Null), Some(JsonValue::Number(0.0)));
+                assert_eq!(members.len(), size);
+                assert_eq!(members.get_index_of("k0"), Some(size - 1));
+                assert_eq!(members.get("k0"), Some(&JsonValue::Null));
+                assert_consistent(&members);
+            }
+        }
+
+        #[test]
+        fn removal_crosses_the_threshold() {
+            let mut members: JsonMap = (0..INDEX_THRESHOLD + 3).map(member).collect();
+            assert_eq!(members.remove("k1"), Some(JsonValue::Number(1.0)));
+            assert_eq!(members.remove("k1"), None);
+            assert_consistent(&members);
+            while members.len() > 2 {
+                let middle = members.get_index(members.len() / 2).map(|(k, _)| k.clone()).unwrap();
+                assert!(members.remove(&middle).is_some());
+                assert!(!members.contains_key(&middle));
+                assert_consistent(&members);
+            }
+            assert_eq!(keys(&members), ["k0", &format!("k{}", INDEX_THRESHOLD + 2)]);
+            members.clear();
+            assert!(members.is_empty());
+            assert_consistent(&members);
+        }
+
+        #[test]
+        fn rename_keeps_the_position() {
+            for size in [3, 20] {
+                let mut members: JsonMap = (0..size).map(member).collect();
+                assert!(members.rename("k1", "one"));
+                assert_eq!(members.get_index_of("one"), Some(1));
+                assert!(!members.contains_key("k1"));
+                assert!(!members.rename("k1", "two"), "missing key");
+                assert!(!members.rename("one", "k2"), "existing key");
+                assert_consistent(&members);
+            }
+        }
+
+        #[test]
+        fn retain_and_sort_rebuild_the_index() {
+            let mut members: JsonMap = (0..30).rev().map(member).collect();
+            members.sort_keys();
+            assert_eq!(keys(&members)[..4], ["k0", "k1", "k10", "k11"]);
+            assert_consistent(&members);
+            members.retain(|_, value| value.as_f64().is_some_and(|n| n < 5.0));
+            assert_eq!(keys(&members), ["k0", "k1", "k2", "k3", "k4"]);
+            assert_consistent(&members);
+        }
+
+        #[test]
+        fn iteration_and_mutation() {
+            let mut members: JsonMap = (0..10).map(member).collect();
+            for (_, value) in members.iter_mut() {
+                *value = JsonValue::Boolean(true);
+            }
+            for value in members.values_mut().take(2) {
+                *value = JsonValue::Null;
+            }
+            if let Some((key, value)) = members.get_index_mut(9) {
+                assert_eq!(key, "k9");
+                *value = JsonValue::Number(9.0);
+            }
+            *members.get_mut("k8").unwrap() = JsonValue::Number(8.0);
+            let values: Vec<String> = members.values().map(|v| v.to_json_string()).collect();
+            assert_eq!(values, ["null", "null", "true", "true", "true", "true", "true", "true", "8", "9"]);
+            let owned: Vec<(String, JsonValue)> = members.clone().into_iter().collect();
+            assert_eq!(owned.as_slice(), members.as_slice());
+            assert_eq!(format!("{:?}", JsonMap::from(vec![member(1)])), r#"{"k1": Number(1.0)}"#);
+        }
+
+        #[test]
+        fn later_duplicates_win_when_collecting() {
+            let members = JsonMap::from(vec![member(1), member(2), ("k1".to_string(), JsonValue::Null)]);
+            assert_eq!(keys(&members), ["k1", "k2"]);
+            assert_eq!(members.get("k1"), Some(&JsonValue::Null));
+            let mut extended = members.clone();
+            extended.extend(vec![member(3), member(2)]);
+            assert_eq!(keys(&extended), ["k1", "k2", "k3"]);
+            assert_ne!(members, extended);
+        }
+
+        #[test]
+        fn parser_handles_duplicate_keys() {
+            let input = r#"{"a": 1, "b": 2, "a": 3}"#;
+            let parse = |policy| JsonParser::new(input).with_duplicate_keys(policy).parse().map(|v| v.to_json_string());
+            assert_eq!(parse(DuplicateKeys::Last), Ok(r#"{"a":3,"b":2}"#.to_string()));
+            assert_eq!(parse(DuplicateKeys::First), Ok(r#"{"a":1,"b":2}"#.to_string()));
+            assert_eq!(parse(DuplicateKeys::Error), Err("Duplicate key 'a' at position 17".to_string()));
+        }
+    }
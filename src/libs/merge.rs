@@ -0,0 +1,404 @@
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{to_pointer, PathSegment};
+
+    impl JsonValue {
+        /// Applies an RFC 7396 JSON Merge Patch.
+        ///
+        /// Objects are merged recursively, a `null` member removes the key and
+        /// every other value, arrays included, replaces the target.
+        pub fn merge_patch(&mut self, patch: &JsonValue) {
+            let JsonValue::Object(patch_obj) = patch else {
+                *self = patch.clone();
+                return;
+            };
+            if !matches!(self, JsonValue::Object(_)) {
+                *self = JsonValue::Object(JsonMap::new());
+            }
+            let JsonValue::Object(target) = self else {
+                return;
+            };
+            for (key, value) in patch_obj {
+                if value.is_null() {
+                    target.remove(key);
+                    continue;
+                }
+                match target.get_mut(key) {
+                    Some(existing) => existing.merge_patch(value),
+                    None => {
+                        let mut new_value = JsonValue::Null;
+                        new_value.merge_patch(value);
+                        target.insert(key.clone(), new_value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// How a deep merge combines two arrays found at the same path.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum ArrayMerge {
+        /// The later array replaces the earlier one.
+        Replace,
+        /// The later elements are appended.
+        Concat,
+        /// The later elements are appended unless an equal element exists.
+        Union,
+        /// Object elements with the same value for this key are merged,
+        /// the others are appended.
+        ByKey(String),
+    }
+
+    impl FromStr for ArrayMerge {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "replace" => Ok(ArrayMerge::Replace),
+                "concat" => Ok(ArrayMerge::Concat),
+                "union" => Ok(ArrayMerge::Union),
+                _ => match s.strip_prefix("key:") {
+                    Some(key) if !key.is_empty() => Ok(ArrayMerge::ByKey(key.to_string())),
+                    _ => Err(format!(
+                        "Unknown array strategy '{}' (expected replace, concat, union or key:<field>)",
+                        s
+                    )),
+                },
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct MergeOptions {
+        pub arrays: ArrayMerge,
+        /// Treats `null` as "remove this key", as in RFC 7396.
+        pub null_deletes: bool,
+    }
+
+    impl Default for MergeOptions {
+        fn default() -> Self {
+            Self { arrays: ArrayMerge::Replace, null_deletes: false }
+        }
+    }
+
+    /// A value set by one source and overwritten with a different value by
+    /// a later one.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct MergeConflict {
+        pub path: Vec<PathSegment>,
+        pub previous_source: Option<usize>,
+        pub previous: JsonValue,
+        pub source: usize,
+        pub value: JsonValue,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct MergeResult {
+        pub value: JsonValue,
+        /// Source index of every leaf value, keyed by JSON Pointer.
+        pub origins: BTreeMap<String, usize>,
+        pub conflicts: Vec<MergeConflict>,
+    }
+
+    impl MergeResult {
+        /// The leaves of the merged document in document order, with the
+        /// index of the source each one came from.
+        pub fn leaf_origins(&self) -> Vec<(Vec<PathSegment>, &JsonValue, Option<usize>)> {
+            let mut leaves = Vec::new();
+            let mut path = Vec::new();
+            collect_leaves(&self.value, &mut path, &mut |path, value| {
+                leaves.push((path.to_vec(), value, self.origins.get(&to_pointer(path)).copied()));
+            });
+            leaves
+        }
+    }
+
+    /// Calls `f` for every scalar, empty object and empty array below `value`.
+    fn collect_leaves<'a>(
+        value: &'a JsonValue,
+        path: &mut Vec<PathSegment>,
+        f: &mut dyn FnMut(&[PathSegment], &'a JsonValue),
+    ) {
+        match value {
+            JsonValue::Object(obj) if !obj.is_empty() => {
+                for (key, child) in obj {
+                    path.push(PathSegment::Key(key.clone()));
+                    collect_leaves(child, path, f);
+                    path.pop();
+                }
+            }
+            JsonValue::Array(arr) if !arr.is_empty() => {
+                for (i, child) in arr.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    collect_leaves(child, path, f);
+                    path.pop();
+                }
+            }
+            _ => f(path, value),
+        }
+    }
+
+    struct Merger<'o> {
+        options: &'o MergeOptions,
+        origins: BTreeMap<String, usize>,
+        conflicts: Vec<MergeConflict>,
+    }
+
+    impl Merger<'_> {
+        fn forget(&mut self, path: &[PathSegment]) {
+            let pointer = to_pointer(path);
+            let prefix = format!("{}/", pointer);
+            let stale: Vec<String> = self
+                .origins
+                .range(prefix.clone()..)
+                .take_while(|(k, _)| k.starts_with(&prefix))
+                .map(|(k, _)| k.clone())
+                .collect();
+            self.origins.remove(&pointer);
+            for key in stale {
+                self.origins.remove(&key);
+            }
+        }
+
+        fn record(&mut self, path: &mut Vec<PathSegment>, value: &JsonValue, source: usize) {
+            let origins = &mut self.origins;
+            collect_leaves(value, path, &mut |path, _| {
+                origins.insert(to_pointer(path), source);
+            });
+        }
+
+        fn replace(&mut self, target: &mut JsonValue, value: &JsonValue, source: usize, path: &mut Vec<PathSegment>) {
+            if *target != *value {
+                let pointer = to_pointer(path);
+                let prefix = format!("{}/", pointer);
+                let previous_source = self.origins.get(&pointer).copied().or_else(|| {
+                    self.origins
+                        .range(prefix.clone()..)
+                        .next()
+                        .filter(|(k, _)| k.starts_with(&prefix))
+                        .map(|(_, s)| *s)
+                });
+                self.conflicts.push(MergeConflict {
+                    path: path.clone(),
+                    previous_source,
+                    previous: target.clone(),
+                    source,
+                    value: value.clone(),
+                });
+            }
+            self.forget(path);
+            *target = value.clone();
+            self.record(path, value, source);
+        }
+
+        fn append(&mut self, target: &mut Vec<JsonValue>, value: &JsonValue, source: usize, path: &mut Vec<PathSegment>) {
+            path.push(PathSegment::Index(target.len()));
+            target.push(value.clone());
+            self.record(path, value, source);
+            path.pop();
+        }
+
+        fn merge(&mut self, target: &mut JsonValue, value: &JsonValue, source: usize, path: &mut Vec<PathSegment>) {
+            match (target, value) {
+                (JsonValue::Object(target), JsonValue::Object(obj)) => {
+                    for (key, child) in obj {
+                        path.push(PathSegment::Key(key.clone()));
+                        match (target.get_mut(key), child) {
+                            (Some(_), JsonValue::Null) if self.options.null_deletes => {
+                                target.remove(key);
+                                self.forget(path);
+                            }
+                            (None, JsonValue::Null) if self.options.null_deletes => {}
+                            (Some(existing), _) => self.merge(existing, child, source, path),
+                            (None, _) => {
+                                target.insert(key.clone(), child.clone());
+                                self.record(path, child, source);
+                            }
+                        }
+                        path.pop();
+                    }
+                }
+                (JsonValue::Array(target), JsonValue::Array(arr)) => match &self.options.arrays {
+                    ArrayMerge::Replace => {
+                        let mut current = JsonValue::Array(std::mem::take(target));
+                        self.replace(&mut current, value, source, path);
+                        if let JsonValue::Array(merged) = current {
+                            *target = merged;
+                        }
+                    }
+                    ArrayMerge::Concat => {
+                        for element in arr {
+                            self.append(target, element, source, path);
+                        }
+                    }
+                    ArrayMerge::Union => {
+                        for element in arr {
+                            if !target.contains(element) {
+                                self.append(target, element, source, path);
+                            }
+                        }
+                    }
+                    ArrayMerge::ByKey(key) => {
+                        for element in arr {
+                            let id = element.get_field(key);
+                            let matched = id.and_then(|id| {
+                                target
+                                    .iter()
+                                    .position(|existing| existing.get_field(key) == Some(id))
+                            });
+                            match matched {
+                                Some(i) => {
+                                    path.push(PathSegment::Index(i));
+                                    self.merge(&mut target[i], element, source, path);
+                                    path.pop();
+                                }
+                                None => self.append(target, element, source, path),
+                            }
+                        }
+                    }
+                },
+                (target, value) => self.replace(target, value, source, path),
+            }
+        }
+    }
+
+    /// Merges the sources in order, later ones taking precedence, and keeps
+    /// track of where every final value came from.
+    pub fn deep_merge(sources: &[JsonValue], options: &MergeOptions) -> MergeResult {
+        let mut merger = Merger {
+            options,
+            origins: BTreeMap::new(),
+            conflicts: Vec::new(),
+        };
+        let mut value = JsonValue::Null;
+        let mut path = Vec::new();
+        for (source, document) in sources.iter().enumerate() {
+            if source == 0 {
+                value = document.clone();
+                merger.record(&mut path, document, 0);
+            } else {
+                merger.merge(&mut value, document, source, &mut path);
+            }
+        }
+        MergeResult {
+            value,
+            origins: merger.origins,
+            conflicts: merger.conflicts,
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+        use crate::libs::path::format_path;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        fn merged(sources: &[&str], options: &MergeOptions) -> MergeResult {
+            let sources: Vec<JsonValue> = sources.iter().map(|source| parse(source)).collect();
+            deep_merge(&sources, options)
+        }
+
+        fn with_arrays(arrays: ArrayMerge) -> MergeOptions {
+            MergeOptions { arrays, ..MergeOptions::default() }
+        }
+
+        #[test]
+        fn merge_patch_cases_from_the_rfc() {
+            let cases = [
+                (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+                (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+                (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+                (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+                (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+                (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+                (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+                (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+                (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+                (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+                (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+                (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+                (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+                (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+                (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+            ];
+            for (target, patch, expected) in cases {
+                let mut value = parse(target);
+                value.merge_patch(&parse(patch));
+                assert_eq!(value.to_json_string(), expected, "{} + {}", target, patch);
+            }
+        }
+
+        #[test]
+        fn later_sources_win() {
+            let result = merged(
+                &[r#"{"db": {"host": "localhost", "port": 5432}, "debug": true}"#, r#"{"db": {"host": "prod"}}"#],
+                &MergeOptions::default(),
+            );
+            assert_eq!(result.value.to_json_string(), r#"{"db":{"host":"prod","port":5432},"debug":true}"#);
+            assert_eq!(result.origins.get("/db/host"), Some(&1));
+            assert_eq!(result.origins.get("/db/port"), Some(&0));
+            let leaves: Vec<(String, Option<usize>)> =
+                result.leaf_origins().into_iter().map(|(path, _, source)| (format_path(&path), source)).collect();
+            assert_eq!(
+                leaves,
+                [("db.host".to_string(), Some(1)), ("db.port".to_string(), Some(0)), ("debug".to_string(), Some(0))]
+            );
+        }
+
+        #[test]
+        fn overwritten_values_are_conflicts() {
+            let result = merged(&[r#"{"a": 1, "b": 2}"#, r#"{"a": 1, "b": 3}"#, r#"{"b": {"c": 4}}"#], &MergeOptions::default());
+            let conflicts: Vec<(String, Option<usize>, usize)> = result
+                .conflicts
+                .iter()
+                .map(|conflict| (format_path(&conflict.path), conflict.previous_source, conflict.source))
+                .collect();
+            assert_eq!(conflicts, [("b".to_string(), Some(0), 1), ("b".to_string(), Some(1), 2)]);
+            assert_eq!(result.origins.get("/b/c"), Some(&2));
+            assert_eq!(result.origins.get("/b"), None);
+        }
+
+        #[test]
+        fn array_strategies() {
+            let sources = [r#"{"tags": ["a", "b"]}"#, r#"{"tags": ["b", "c"]}"#];
+            let tags = |arrays| merged(&sources, &with_arrays(arrays)).value.get("tags").unwrap().to_json_string();
+            assert_eq!(tags(ArrayMerge::Replace), r#"["b","c"]"#);
+            assert_eq!(tags(ArrayMerge::Concat), r#"["a","b","b","c"]"#);
+            assert_eq!(tags(ArrayMerge::Union), r#"["a","b","c"]"#);
+
+            let result = merged(
+                &[r#"[{"id": 1, "a": 1}, {"id": 2}]"#, r#"[{"id": 1, "b": 2}, {"id": 3}]"#],
+                &with_arrays(ArrayMerge::ByKey("id".to_string())),
+            );
+            assert_eq!(result.value.to_json_string(), r#"[{"id":1,"a":1,"b":2},{"id":2},{"id":3}]"#);
+            assert_eq!(result.origins.get("/0/b"), Some(&1));
+            assert_eq!(result.origins.get("/2/id"), Some(&1));
+        }
+
+        #[test]
+        fn null_deletes_when_asked() {
+            let sources = [r#"{"a": 1, "b": {"c": 2}}"#, r#"{"b": null, "d": null}"#];
+            let kept = merged(&sources, &MergeOptions::default());
+            assert_eq!(kept.value.to_json_string(), r#"{"a":1,"b":null,"d":null}"#);
+            let options = MergeOptions { null_deletes: true, ..MergeOptions::default() };
+            let deleted = merged(&sources, &options);
+            assert_eq!(deleted.value.to_json_string(), r#"{"a":1}"#);
+            assert_eq!(deleted.origins.get("/b/c"), None);
+        }
+
+        #[test]
+        fn array_strategies_parse() {
+            assert_eq!("union".parse(), Ok(ArrayMerge::Union));
+            assert_eq!("key:name".parse(), Ok(ArrayMerge::ByKey("name".to_string())));
+            assert!("key:".parse::<ArrayMerge>().is_err());
+            assert!("merge".parse::<ArrayMerge>().is_err());
+        }
+    }
@@ -1 +1,24 @@
-pub mod json_parser;
\ No newline at end of file
+pub mod json_parser;
+pub mod path;
+pub mod cst;
+pub mod layout;
+pub mod serializer;
+pub mod diff;
+pub mod patch;
+pub mod merge;
+pub mod regex;
+pub mod formats;
+pub mod schema;
+pub mod infer;
+pub mod codegen;
+pub mod convert;
+pub mod value;
+pub mod map;
+pub mod borrowed;
+pub mod document;
+pub mod parallel;
+pub mod input;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
\ No newline at end of file
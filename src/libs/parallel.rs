@@ -0,0 +1,413 @@
+    use std::thread;
+
+    use crate::libs::borrowed;
+    use crate::libs::json_parser::{find_string_special, JsonParser, JsonValue, ParseMode};
+
+    /// How an input made of many independent records is laid out.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Layout {
+        /// One value per line (JSON Lines / NDJSON); records are numbered
+        /// by line from 1.
+        Lines,
+        /// A top-level array; records are its elements, numbered from 0.
+        Array,
+    }
+
+    /// A string found by [`search_records`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct SearchMatch {
+        /// Line number or element index, depending on the layout.
+        pub record: usize,
+        /// Key path inside the record, as in `get_all_occurrences`.
+        pub path: String,
+        /// Set when the string is an array element.
+        pub index: Option<usize>,
+        pub value: String,
+    }
+
+    /// The number of worker threads to use when none is requested.
+    pub fn default_jobs() -> usize {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    }
+
+    /// Tells whether `input` can be split into records, or `None` for a
+    /// single document that must be parsed as a whole.
+    ///
+    /// Input is line-delimited when its first line holds a complete value
+    /// and more content follows.
+    pub fn detect_layout(input: &str, mode: ParseMode) -> Option<Layout> {
+        let trimmed = input.trim_start();
+        let first_line = trimmed.split('\n').next().unwrap_or_default().trim_end();
+        let rest = trimmed[first_line.len()..].trim();
+        if !first_line.is_empty() && !rest.is_empty() && parse_line(first_line, mode).is_ok() {
+            return Some(Layout::Lines);
+        }
+        trimmed.starts_with('[').then_some(Layout::Array)
+    }
+
+    /// Parses every record on `jobs` threads, in input order.
+    pub fn parse_records(input: &str, layout: Layout, mode: ParseMode, jobs: usize) -> Result<Vec<JsonValue>, String> {
+        let records = map_records(input, layout, mode, jobs, |value| value.into_owned())?;
+        Ok(records.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Searches every record for strings equal to `target`, ignoring case,
+    /// on `jobs` threads. Matches are returned in input order.
+    pub fn search_records(
+        input: &str,
+        layout: Layout,
+        target: &str,
+        mode: ParseMode,
+        jobs: usize,
+    ) -> Result<Vec<SearchMatch>, String> {
+        let lowercase = target.to_lowercase();
+        let records = map_records(input, layout, mode, jobs, |value| {
+            // A matching string element of a top-level array is found at its
+            // index, as when the whole array is searched.
+            let element = match &value {
+                borrowed::JsonValue::String(s) if layout == Layout::Array && s.to_lowercase() == lowercase => {
+                    Some(s.to_string())
+                }
+                _ => None,
+            };
+            let found = value
+                .find_strings(target)
+                .into_iter()
+                .map(|(path, index, s)| (path, index, s.to_string()))
+                .collect::<Vec<_>>();
+            (element, found)
+        })?;
+        Ok(records
+            .into_iter()
+            .flat_map(|(record, (element, found))| {
+                let element = element.map(|value| SearchMatch { record, path: String::new(), index: Some(record), value });
+                element.into_iter().chain(
+                    found.into_iter().map(move |(path, index, value)| SearchMatch { record, path, index, value }),
+                )
+            })
+            .collect())
+    }
+
+    /// Parses each record and applies `f` to it, splitting the input into
+    /// one contiguous chunk per thread. Results keep their input order and
+    /// come with the record number.
+    pub fn map_records<T, F>(input: &str, layout: Layout, mode: ParseMode, jobs: usize, f: F) -> Result<Vec<(usize, T)>, String>
+    where
+        T: Send,
+        F: Fn(borrowed::JsonValue) -> T + Sync,
+    {
+        let jobs = jobs.max(1);
+        let (chunks, first_record, name) = match layout {
+            Layout::Lines => (line_chunks(input, jobs), 1, "Line"),
+            Layout::Array => (array_chunks(input, mode, jobs)?, 0, "Element"),
+        };
+        let last = chunks.len() - 1;
+        let run = |(i, &(start, end)): (usize, &(usize, usize))| match layout {
+            Layout::Lines => map_lines(&input[start..end], mode, &f),
+            Layout::Array => map_elements(input, start, end, i == 0, i == last, mode, &f),
+        };
+        let results: Vec<ChunkResult<T>> = if chunks.len() == 1 {
+            vec![run((0, &chunks[0]))]
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = chunks.iter().enumerate().map(|chunk| scope.spawn(move || run(chunk))).collect();
+                handles.into_iter().map(|handle| handle.join().expect("worker thread panicked")).collect()
+            })
+        };
+
+        let mut records = Vec::new();
+        let mut offset = first_record;
+        for result in results {
+            match result {
+                Ok((count, items)) => {
+                    records.extend(items.into_iter().map(|(i, item)| (offset + i, item)));
+                    offset += count;
+                }
+                Err((i, e)) => return Err(format!("{} {}: {}", name, offset + i, e)),
+            }
+        }
+        Ok(records)
+    }
+
+    /// The number of records in a chunk and the results, numbered from the
+    /// start of the chunk; or the failing record and its error.
+    type ChunkResult<T> = Result<(usize, Vec<(usize, T)>), (usize, String)>;
+
+    fn parse_line(line: &str, mode: ParseMode) -> Result<borrowed::JsonValue<'_>, String> {
+        let mut parser = JsonParser::with_mode(line, mode);
+        let value = parser.parse_borrowed()?;
+        parser.finish()?;
+        Ok(value)
+    }
+
+    /// Splits the input after a line break roughly every `len / jobs` bytes.
+    fn line_chunks(input: &str, jobs: usize) -> Vec<(usize, usize)> {
+        let step = input.len() / jobs + 1;
+        let mut chunks = Vec::with_capacity(jobs);
+        let mut start = 0;
+        while start < input.len() {
+            let from = (start + step).min(input.len());
+            let end = match input.as_bytes()[from..].iter().position(|&b| b == b'\n') {
+                Some(i) => from + i + 1,
+                None => input.len(),
+            };
+            chunks.push((start, end));
+            start = end;
+        }
+        if chunks.is_empty() {
+            chunks.push((0, 0));
+        }
+        chunks
+    }
+
+    fn map_lines<T>(chunk: &str, mode: ParseMode, f: &impl Fn(borrowed::JsonValue) -> T) -> ChunkResult<T> {
+        let mut items = Vec::new();
+        let mut count = 0;
+        for (i, line) in chunk.split_inclusive('\n').enumerate() {
+            count += 1;
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = parse_line(line, mode).map_err(|e| (i, e))?;
+            items.push((i, f(value)));
+        }
+        Ok((count, items))
+    }
+
+    /// Splits the elements of a top-level array into chunks of roughly
+    /// `len / jobs` bytes. Chunks start at an element and end just after
+    /// the comma that follows their last element; the last one runs to the
+    /// end of the input.
+    ///
+    /// The scan only tracks nesting, strings and comments, leaving syntax
+    /// errors for the workers to report.
+    fn array_chunks(input: &str, mode: ParseMode, jobs: usize) -> Result<Vec<(usize, usize)>, String> {
+        let bytes = input.as_bytes();
+        let start = input.find('[').ok_or("Invalid JSON format")? + 1;
+        let step = (bytes.len() - start) / jobs + 1;
+        let mut chunks = Vec::with_capacity(jobs);
+        let mut chunk_start = start;
+        let mut depth = 0usize;
+        let mut i = start;
+        while i < bytes.len() && chunks.len() + 1 < jobs {
+            match bytes[i] {
+                quote @ b'"' => i = skip_string(bytes, i, quote),
+                quote @ b'\'' if mode.is_json5() => i = skip_string(bytes, i, quote),
+                b'/' if mode.allows_comments() && bytes.get(i + 1) == Some(&b'/') => {
+                    i = input[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                }
+                b'/' if mode.allows_comments() && bytes.get(i + 1) == Some(&b'*') => {
+                    i = input[i + 2..].find("*/").map_or(bytes.len(), |end| i + end + 4);
+                }
+                b'[' | b'{' => {
+                    depth += 1;
+                    i += 1;
+                }
+                b']' | b'}' if depth == 0 => break,
+                b']' | b'}' => {
+                    depth -= 1;
+                    i += 1;
+                }
+                b',' if depth == 0 => {
+                    i += 1;
+                    if i - chunk_start >= step {
+                        chunks.push((chunk_start, i));
+                        chunk_start = i;
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+        chunks.push((chunk_start, bytes.len()));
+        Ok(chunks)
+    }
+
+    /// The index just past the string literal opening at `start`.
+    fn skip_string(bytes: &[u8], start: usize, quote: u8) -> usize {
+        let mut i = start + 1;
+        while let Some(offset) = find_string_special(&bytes[i..], quote) {
+            i += offset + 1;
+            match bytes[i - 1] {
+                b'\\' => i += 1,
+                b if b == quote => return i,
+                _ => {}
+            }
+        }
+        bytes.len()
+    }
+
+    fn map_elements<T>(
+        input: &str,
+        start: usize,
+        end: usize,
+        first: bool,
+        last: bool,
+        mode: ParseMode,
+        f: &impl Fn(borrowed::JsonValue) -> T,
+    ) -> ChunkResult<T> {
+        let mut parser = JsonParser::with_mode(input, mode);
+        parser.position = start;
+        let mut items = Vec::new();
+        loop {
+            let i = items.len();
+            parser.skip_trivia().map_err(|e| (i, e))?;
+            if last && parser.peek_byte() == Some(b']') {
+                // Either an empty array or a trailing comma.
+                if !(mode.allows_trailing_commas() || first && i == 0) {
+                    return Err((i, parser.extension_error("Trailing commas", ParseMode::Jsonc)));
+                }
+                parser.position += 1;
+                parser.finish().map_err(|e| (i, e))?;
+                break;
+            }
+            let value = parser.parse_borrowed().map_err(|e| (i, e))?;
+            items.push((i, f(value)));
+            parser.skip_trivia().map_err(|e| (i, e))?;
+            match parser.peek_byte() {
+                Some(b',') => {
+                    parser.position += 1;
+                    if parser.position >= end {
+                        break;
+                    }
+                }
+                Some(b']') if last => {
+                    parser.position += 1;
+                    parser.finish().map_err(|e| (i, e))?;
+                    break;
+                }
+                _ => return Err((i, "Expected ',' or ']'".to_string())),
+            }
+        }
+        Ok((items.len(), items))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Records with strings, nesting and comments that contain the
+        /// characters the chunk scanner looks for.
+        fn records(count: usize) -> Vec<String> {
+            (0..count)
+                .map(|i| match i % 4 {
+                    0 => format!(r#"{{"id": {}, "name": "Target", "tags": ["x", "target"]}}"#, i),
+                    1 => format!(r#"["a,]b", {{"nested": [{{"v": "[{{,"}}]}}, {}]"#, i),
+                    2 => format!(r#""target \"{}\" ]""#, i),
+                    _ => format!(r#"{{"deep": {{"list": [[], {{}}, "TARGET"]}}, "n": {}.5}}"#, i),
+                })
+                .collect()
+        }
+
+        fn sequential(records: &[String], target: &str, first_record: usize) -> Vec<SearchMatch> {
+            let mut matches = Vec::new();
+            for (i, record) in records.iter().enumerate() {
+                let value = JsonParser::new(record).parse_borrowed().unwrap();
+                for (path, index, s) in value.find_strings(target) {
+                    matches.push(SearchMatch { record: first_record + i, path, index, value: s.to_string() });
+                }
+            }
+            matches
+        }
+
+        #[test]
+        fn lines_give_the_same_results_for_any_job_count() {
+            let records = records(37);
+            let input = records.join("\n") + "\n";
+            let expected: Vec<JsonValue> = records.iter().map(|r| JsonParser::new(r).parse().unwrap()).collect();
+            let matches = sequential(&records, "target", 1);
+            assert_eq!(matches.len(), 29);
+            for jobs in [1, 2, 3, 8, 64] {
+                assert_eq!(parse_records(&input, Layout::Lines, ParseMode::Strict, jobs), Ok(expected.clone()), "{}", jobs);
+                assert_eq!(search_records(&input, Layout::Lines, "target", ParseMode::Strict, jobs), Ok(matches.clone()), "{}", jobs);
+            }
+        }
+
+        #[test]
+        fn array_elements_give_the_same_results_for_any_job_count() {
+            let records = records(41);
+            let input = format!("[\n{}\n]\n", records.join(",\n"));
+            let JsonValue::Array(expected) = JsonParser::new(&input).parse().unwrap() else {
+                panic!("not an array");
+            };
+            let matches = sequential(&records, "TARGET", 0);
+            for jobs in [1, 2, 3, 8, 64] {
+                assert_eq!(parse_records(&input, Layout::Array, ParseMode::Strict, jobs), Ok(expected.clone()), "{}", jobs);
+                assert_eq!(search_records(&input, Layout::Array, "TARGET", ParseMode::Strict, jobs), Ok(matches.clone()), "{}", jobs);
+            }
+        }
+
+        #[test]
+        fn array_matches_are_those_of_the_whole_document() {
+            let mut records = records(23);
+            records.insert(5, r#""Target""#.to_string());
+            let input = format!("[{}]", records.join(", "));
+            let document = JsonParser::new(&input).parse_borrowed().unwrap();
+            let expected: Vec<_> = document
+                .find_strings("target")
+                .into_iter()
+                .map(|(path, index, s)| (path, index, s.to_string()))
+                .collect();
+            assert!(expected.contains(&(String::new(), Some(5), "Target".to_string())));
+            for jobs in [1, 2, 8] {
+                let found: Vec<_> = search_records(&input, Layout::Array, "target", ParseMode::Strict, jobs)
+                    .unwrap()
+                    .into_iter()
+                    .map(|found| (found.path, found.index, found.value))
+                    .collect();
+                assert_eq!(found, expected, "{}", jobs);
+            }
+        }
+
+        #[test]
+        fn extended_syntax_is_split_correctly() {
+            let input = "[/* a, b */ {a: 'x,]', b: [1,]}, // c, d\n 'target', \"]\", 2,]";
+            let expected = match JsonParser::with_mode(input, ParseMode::Json5).parse() {
+                Ok(JsonValue::Array(elements)) => elements,
+                other => panic!("{:?}", other),
+            };
+            for jobs in 1..8 {
+                assert_eq!(parse_records(input, Layout::Array, ParseMode::Json5, jobs), Ok(expected.clone()), "{}", jobs);
+            }
+            assert!(parse_records(input, Layout::Array, ParseMode::Strict, 4).is_err());
+        }
+
+        #[test]
+        fn errors_name_the_same_record_for_any_job_count() {
+            let mut lines = records(20);
+            lines[13] = "{\"broken\": }".to_string();
+            let input = lines.join("\n");
+            let array = format!("[{}]", lines.join(", "));
+            for jobs in [1, 2, 5, 20] {
+                let err = parse_records(&input, Layout::Lines, ParseMode::Strict, jobs).unwrap_err();
+                assert!(err.starts_with("Line 14: "), "{}: {}", jobs, err);
+                let err = parse_records(&array, Layout::Array, ParseMode::Strict, jobs).unwrap_err();
+                assert!(err.starts_with("Element 13: "), "{}: {}", jobs, err);
+            }
+            for jobs in [1, 3] {
+                let err = parse_records("[1, 2,]", Layout::Array, ParseMode::Strict, jobs).unwrap_err();
+                assert!(err.contains("Trailing commas"), "{}", err);
+                assert!(parse_records("[1, 2] x", Layout::Array, ParseMode::Strict, jobs).is_err());
+            }
+        }
+
+        #[test]
+        fn empty_inputs_and_blank_lines() {
+            for jobs in [1, 4] {
+                assert_eq!(parse_records("[]", Layout::Array, ParseMode::Strict, jobs), Ok(Vec::new()));
+                assert_eq!(parse_records(" [ ] ", Layout::Array, ParseMode::Strict, jobs), Ok(Vec::new()));
+                assert_eq!(parse_records("", Layout::Lines, ParseMode::Strict, jobs), Ok(Vec::new()));
+                let found = search_records("\n{\"a\": \"x\"}\r\n\n[\"x\"]\n", Layout::Lines, "x", ParseMode::Strict, jobs).unwrap();
+                assert_eq!(found.iter().map(|m| m.record).collect::<Vec<_>>(), [2, 4]);
+            }
+        }
+
+        #[test]
+        fn layouts_are_detected() {
+            assert_eq!(detect_layout("{\"a\": 1}\n{\"a\": 2}\n", ParseMode::Strict), Some(Layout::Lines));
+            assert_eq!(detect_layout("  [1, 2]\n[3]", ParseMode::Strict), Some(Layout::Lines));
+            assert_eq!(detect_layout("[\n1,\n2\n]", ParseMode::Strict), Some(Layout::Array));
+            assert_eq!(detect_layout("{\n\"a\": 1\n}", ParseMode::Strict), None);
+            assert_eq!(detect_layout("{\"a\": 1}\n", ParseMode::Strict), None);
+        }
+    }
@@ -0,0 +1,376 @@
+    use crate::libs::diff::{equivalent, DiffOptions};
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::path::{parse_pointer, to_pointer, PathSegment};
+
+    /// Resolves an array reference token: an index without leading zeros,
+    /// or `-` (one past the end) when `allow_end` is set.
+    fn array_index(token: &str, len: usize, allow_end: bool) -> Result<usize, String> {
+        if token == "-" && allow_end {
+            return Ok(len);
+        }
+        let valid = !token.is_empty()
+            && token.chars().all(|c| c.is_ascii_digit())
+            && (token == "0" || !token.starts_with('0'));
+        let index = if valid { token.parse::<usize>().ok() } else { None };
+        match index {
+            Some(index) if index < len || (allow_end && index == len) => Ok(index),
+            _ => Err(format!("Invalid array index '{}' (length {})", token, len)),
+        }
+    }
+
+    impl JsonValue {
+        /// Returns the value referenced by an RFC 6901 JSON Pointer.
+        pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+            let mut current = self;
+            for token in parse_pointer(pointer).ok()? {
+                current = match current {
+                    JsonValue::Object(obj) => obj.get(&token)?,
+                    JsonValue::Array(arr) => arr.get(array_index(&token, arr.len(), false).ok()?)?,
+                    _ => return None,
+                };
+            }
+            Some(current)
+        }
+
+        pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+            let mut current = self;
+            for token in parse_pointer(pointer).ok()? {
+                current = match current {
+                    JsonValue::Object(obj) => obj.get_mut(&token)?,
+                    JsonValue::Array(arr) => {
+                        let index = array_index(&token, arr.len(), false).ok()?;
+                        arr.get_mut(index)?
+                    }
+                    _ => return None,
+                };
+            }
+            Some(current)
+        }
+
+        /// Splits a pointer into the container it points into and the last
+        /// reference token.
+        fn pointer_parent_mut(&mut self, pointer: &str) -> Result<(&mut JsonValue, String), String> {
+            let mut tokens = parse_pointer(pointer)?;
+            let last = tokens.pop().ok_or("The document root has no parent")?;
+            let parent_pointer = to_pointer(
+                &tokens.into_iter().map(PathSegment::Key).collect::<Vec<_>>(),
+            );
+            let parent = self
+                .pointer_mut(&parent_pointer)
+                .ok_or_else(|| format!("Path '{}' does not exist", parent_pointer))?;
+            Ok((parent, last))
+        }
+
+        fn patch_add(&mut self, pointer: &str, value: JsonValue) -> Result<(), String> {
+            if pointer.is_empty() {
+                *self = value;
+                return Ok(());
+            }
+            let (parent, token) = self.pointer_parent_mut(pointer)?;
+            match parent {
+                JsonValue::Object(obj) => {
+                    obj.insert(token, value);
+                    Ok(())
+                }
+                JsonValue::Array(arr) => {
+                    let index = array_index(&token, arr.len(), true)?;
+                    arr.insert(index, value);
+                    Ok(())
+                }
+                _ => Err(format!("Parent of '{}' is not a container", pointer)),
+            }
+        }
+
+        fn patch_remove(&mut self, pointer: &str) -> Result<JsonValue, String> {
+            if pointer.is_empty() {
+                return Ok(std::mem::replace(self, JsonValue::Null));
+            }
+            let (parent, token) = self.pointer_parent_mut(pointer)?;
+            match parent {
+                JsonValue::Object(obj) => obj
+                    .remove(&token)
+                    .ok_or_else(|| format!("Path '{}' does not exist", pointer)),
+                JsonValue::Array(arr) => {
+                    let index = array_index(&token, arr.len(), false)?;
+                    Ok(arr.remove(index))
+                }
+                _ => Err(format!("Path '{}' does not exist", pointer)),
+            }
+        }
+
+        fn apply_operation(&mut self, operation: &JsonValue) -> Result<(), String> {
+            let field = |name: &str| operation.get_field(name);
+            let string_field = |name: &str| match field(name) {
+                Some(JsonValue::String(s)) => Ok(s.as_str()),
+                Some(_) => Err(format!("'{}' must be a string", name)),
+                None => Err(format!("missing '{}'", name)),
+            };
+            let value_field = || field("value").cloned().ok_or("missing 'value'");
+
+            let path = string_field("path")?;
+            match string_field("op")? {
+                "add" => self.patch_add(path, value_field()?),
+                "remove" => self.patch_remove(path).map(|_| ()),
+                "replace" => {
+                    let target = self
+                        .pointer_mut(path)
+                        .ok_or_else(|| format!("Path '{}' does not exist", path))?;
+                    *target = value_field()?;
+                    Ok(())
+                }
+                "move" => {
+                    let from = string_field("from")?;
+                    if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                        return Err(format!("Cannot move '{}' into one of its children", from));
+                    }
+                    let value = self.patch_remove(from)?;
+                    self.patch_add(path, value)
+                }
+                "copy" => {
+                    let from = string_field("from")?;
+                    let value = self
+                        .pointer(from)
+                        .cloned()
+                        .ok_or_else(|| format!("Path '{}' does not exist", from))?;
+                    self.patch_add(path, value)
+                }
+                "test" => {
+                    let expected = value_field()?;
+                    match self.pointer(path) {
+                        Some(actual) if equivalent(actual, &expected, &DiffOptions::default()) => Ok(()),
+                        Some(actual) => Err(format!(
+                            "value at '{}' is {}, expected {}",
+                            path,
+                            actual.to_json_string(),
+                            expected.to_json_string()
+                        )),
+                        None => Err(format!("Path '{}' does not exist", path)),
+                    }
+                }
+                other => Err(format!("unknown operation '{}'", other)),
+            }
+        }
+
+        /// The value of member `name` when `self` is an object.
+        pub(crate) fn get_field(&self, name: &str) -> Option<&JsonValue> {
+            match self {
+                JsonValue::Object(obj) => obj.get(name),
+                _ => None,
+            }
+        }
+
+        /// Applies an RFC 6902 JSON Patch document.
+        ///
+        /// The patch is all or nothing: when an operation fails, `self` is
+        /// left unchanged and the error names the index of that operation.
+        pub fn apply_patch(&mut self, patch: &JsonValue) -> Result<(), String> {
+            let JsonValue::Array(operations) = patch else {
+                return Err("A JSON Patch must be an array of operations".to_string());
+            };
+            let mut patched = self.clone();
+            for (i, operation) in operations.iter().enumerate() {
+                let op = match operation.get_field("op") {
+                    Some(JsonValue::String(op)) => op.as_str(),
+                    _ => "?",
+                };
+                patched
+                    .apply_operation(operation)
+                    .map_err(|err| format!("Patch operation {} ({}) failed: {}", i, op, err))?;
+            }
+            *self = patched;
+            Ok(())
+        }
+    }
+
+    fn operation(op: &str, path: &[PathSegment], value: Option<&JsonValue>) -> JsonValue {
+        let mut fields = vec![
+            ("op".to_string(), JsonValue::String(op.to_string())),
+            ("path".to_string(), JsonValue::String(to_pointer(path))),
+        ];
+        if let Some(value) = value {
+            fields.push(("value".to_string(), value.clone()));
+        }
+        JsonValue::Object(fields.into())
+    }
+
+    /// Generates a JSON Patch that turns `old` into `new`.
+    ///
+    /// Unchanged subtrees produce no operations, and arrays are aligned on
+    /// their longest common subsequence so that an insertion or removal in
+    /// the middle of an array is a single operation.
+    pub fn diff_to_patch(old: &JsonValue, new: &JsonValue) -> JsonValue {
+        let mut operations = Vec::new();
+        let mut path = Vec::new();
+        patch_values(old, new, &mut path, &mut operations);
+        JsonValue::Array(operations)
+    }
+
+    fn patch_values(old: &JsonValue, new: &JsonValue, path: &mut Vec<PathSegment>, out: &mut Vec<JsonValue>) {
+        match (old, new) {
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                for (key, old_value) in a {
+                    path.push(PathSegment::Key(key.clone()));
+                    match b.get(key) {
+                        Some(new_value) => patch_values(old_value, new_value, path, out),
+                        None => out.push(operation("remove", path, None)),
+                    }
+                    path.pop();
+                }
+                for (key, new_value) in b {
+                    if !a.contains_key(key) {
+                        path.push(PathSegment::Key(key.clone()));
+                        out.push(operation("add", path, Some(new_value)));
+                        path.pop();
+                    }
+                }
+            }
+            (JsonValue::Array(a), JsonValue::Array(b)) => patch_arrays(a, b, path, out),
+            _ => {
+                if old != new {
+                    out.push(operation("replace", path, Some(new)));
+                }
+            }
+        }
+    }
+
+    fn patch_arrays(a: &[JsonValue], b: &[JsonValue], path: &mut Vec<PathSegment>, out: &mut Vec<JsonValue>) {
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        // `k` is the index in the array as it is after the operations so far.
+        let (mut i, mut j, mut k) = (0, 0, 0);
+        while i < a.len() || j < b.len() {
+            path.push(PathSegment::Index(k));
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                i += 1;
+                j += 1;
+                k += 1;
+            } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                if j < b.len() && lcs[i + 1][j + 1] == lcs[i][j] {
+                    patch_values(&a[i], &b[j], path, out);
+                    j += 1;
+                    k += 1;
+                } else {
+                    out.push(operation("remove", path, None));
+                }
+                i += 1;
+            } else {
+                out.push(operation("add", path, Some(&b[j])));
+                j += 1;
+                k += 1;
+            }
+            path.pop();
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        fn patched(document: &str, patch: &str) -> Result<String, String> {
+            let mut value = parse(document);
+            value.apply_patch(&parse(patch))?;
+            Ok(value.to_json_string())
+        }
+
+        #[test]
+        fn operations_from_the_rfc() {
+            let cases = [
+                (r#"{"foo": "bar"}"#, r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#, r#"{"foo":"bar","baz":"qux"}"#),
+                (r#"{"foo": ["bar", "baz"]}"#, r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#, r#"{"foo":["bar","qux","baz"]}"#),
+                (r#"{"foo": [1]}"#, r#"[{"op": "add", "path": "/foo/-", "value": 2}]"#, r#"{"foo":[1,2]}"#),
+                (r#"{"baz": "qux", "foo": "bar"}"#, r#"[{"op": "remove", "path": "/baz"}]"#, r#"{"foo":"bar"}"#),
+                (r#"{"foo": ["bar", "qux", "baz"]}"#, r#"[{"op": "remove", "path": "/foo/1"}]"#, r#"{"foo":["bar","baz"]}"#),
+                (r#"{"baz": "qux"}"#, r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#, r#"{"baz":"boo"}"#),
+                (
+                    r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                    r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                    r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#,
+                ),
+                (r#"{"foo": ["all", "grass", "cows", "eat"]}"#, r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#, r#"{"foo":["all","cows","eat","grass"]}"#),
+                (r#"{"a": {"b": 1}}"#, r#"[{"op": "copy", "from": "/a", "path": "/c"}]"#, r#"{"a":{"b":1},"c":{"b":1}}"#),
+                (r#"{"a/b": 1, "m~n": 2}"#, r#"[{"op": "remove", "path": "/a~1b"}, {"op": "test", "path": "/m~0n", "value": 2}]"#, r#"{"m~n":2}"#),
+                (r#"[1]"#, r#"[{"op": "replace", "path": "", "value": {"x": true}}]"#, r#"{"x":true}"#),
+            ];
+            for (document, patch, expected) in cases {
+                assert_eq!(patched(document, patch).as_deref(), Ok(expected), "{}", patch);
+            }
+        }
+
+        #[test]
+        fn invalid_operations_fail() {
+            let document = r#"{"foo": ["bar"], "n": 1}"#;
+            for patch in [
+                r#"[{"op": "test", "path": "/n", "value": 2}]"#,
+                r#"[{"op": "remove", "path": "/missing"}]"#,
+                r#"[{"op": "add", "path": "/foo/2", "value": 0}]"#,
+                r#"[{"op": "add", "path": "/foo/01", "value": 0}]"#,
+                r#"[{"op": "replace", "path": "/nope", "value": 0}]"#,
+                r#"[{"op": "move", "from": "/foo", "path": "/foo/0"}]"#,
+                r#"[{"op": "add", "path": "/x"}]"#,
+                r#"[{"op": "frobnicate", "path": "/n"}]"#,
+                r#"{"op": "add", "path": "/x", "value": 1}"#,
+            ] {
+                assert!(patched(document, patch).is_err(), "{}", patch);
+            }
+        }
+
+        #[test]
+        fn a_failing_patch_changes_nothing() {
+            let mut value = parse(r#"{"a": 1, "list": [1, 2]}"#);
+            let before = value.clone();
+            let patch = parse(
+                r#"[
+                    {"op": "add", "path": "/b", "value": 2},
+                    {"op": "remove", "path": "/list/0"},
+                    {"op": "test", "path": "/a", "value": 5}
+                ]"#,
+            );
+            let err = value.apply_patch(&patch).unwrap_err();
+            assert!(err.starts_with("Patch operation 2 (test) failed"), "{}", err);
+            assert_eq!(value, before);
+        }
+
+        #[test]
+        fn generated_patches_turn_old_into_new() {
+            let pairs = [
+                (r#"{"a": 1, "b": [1, 2, 3], "c": {"d": true}}"#, r#"{"a": 2, "b": [1, 9, 2, 3], "e": null}"#),
+                (r#"[1, 2, 3, 4]"#, r#"[2, 4, 5]"#),
+                (r#"{"a/b": {"m~n": 1}}"#, r#"{"a/b": {"m~n": 2}}"#),
+                (r#"{"same": [1]}"#, r#"{"same": [1]}"#),
+                (r#"1"#, r#""text""#),
+            ];
+            for (old, new) in pairs {
+                let (mut value, new) = (parse(old), parse(new));
+                let patch = diff_to_patch(&value, &new);
+                value.apply_patch(&patch).unwrap();
+                assert_eq!(value, new, "{}", patch.to_json_string());
+            }
+            let patch = diff_to_patch(&parse(r#"[1, 2, 3]"#), &parse(r#"[1, 9, 2, 3]"#));
+            assert_eq!(patch.to_json_string(), r#"[{"op":"add","path":"/1","value":9}]"#);
+        }
+
+        #[test]
+        fn pointers_resolve() {
+            let value = parse(r#"{"a": [{"b": 1}], "": 2}"#);
+            assert_eq!(value.pointer("/a/0/b"), Some(&JsonValue::Number(1.0)));
+            assert_eq!(value.pointer("/"), Some(&JsonValue::Number(2.0)));
+            assert_eq!(value.pointer(""), Some(&value));
+            assert_eq!(value.pointer("/a/01"), None);
+            assert_eq!(value.pointer("/a/-"), None);
+        }
+    }
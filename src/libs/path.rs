@@ -0,0 +1,265 @@
+    use std::fmt;
+
+    use crate::libs::json_parser::JsonValue;
+
+    /// One step of a path into a JSON document: an object key or an array index.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum PathSegment {
+        Key(String),
+        Index(usize),
+    }
+
+    impl fmt::Display for PathSegment {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                PathSegment::Key(key) => write!(f, "{}", key),
+                PathSegment::Index(index) => write!(f, "[{}]", index),
+            }
+        }
+    }
+
+    /// Parses a path such as `users[0].name` or `details["first.name"]`.
+    ///
+    /// Keys are separated by dots, array indexes are written in brackets and
+    /// keys that contain dots or brackets can be quoted inside brackets, where
+    /// `\"` and `\\` stand for a quote and a backslash. The empty string is
+    /// the path of the document root.
+    pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+        let mut segments = Vec::new();
+        let chars: Vec<char> = path.chars().collect();
+        let mut i = 0;
+        let mut expect_key = !path.starts_with('[');
+
+        while i < chars.len() {
+            match chars[i] {
+                '[' if chars.get(i + 1) == Some(&'"') => {
+                    let mut key = String::new();
+                    let mut j = i + 2;
+                    loop {
+                        match chars.get(j) {
+                            Some('\\') if matches!(chars.get(j + 1), Some('"' | '\\')) => {
+                                key.push(chars[j + 1]);
+                                j += 2;
+                            }
+                            Some('"') => break,
+                            Some(&c) => {
+                                key.push(c);
+                                j += 1;
+                            }
+                            None => return Err(format!("Unclosed '\"' in path '{}'", path)),
+                        }
+                    }
+                    if chars.get(j + 1) != Some(&']') {
+                        return Err(format!("Expected ']' after the quoted key in path '{}'", path));
+                    }
+                    segments.push(PathSegment::Key(key));
+                    i = j + 2;
+                    expect_key = false;
+                }
+                '[' => {
+                    let close = chars[i..]
+                        .iter()
+                        .position(|&c| c == ']')
+                        .map(|p| p + i)
+                        .ok_or_else(|| format!("Unclosed '[' in path '{}'", path))?;
+                    let inner: String = chars[i + 1..close].iter().collect();
+                    if inner.trim() == WILDCARD {
+                        segments.push(PathSegment::Key(WILDCARD.to_string()));
+                    } else {
+                        let index = inner
+                            .trim()
+                            .parse::<usize>()
+                            .map_err(|_| format!("Invalid index '{}' in path '{}'", inner, path))?;
+                        segments.push(PathSegment::Index(index));
+                    }
+                    i = close + 1;
+                    expect_key = false;
+                }
+                '.' if expect_key => return Err(format!("Empty key in path '{}'", path)),
+                '.' => {
+                    i += 1;
+                    expect_key = true;
+                }
+                _ if expect_key => {
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+                    segments.push(PathSegment::Key(chars[start..i].iter().collect()));
+                    expect_key = false;
+                }
+                c => return Err(format!("Unexpected '{}' in path '{}'", c, path)),
+            }
+        }
+        if expect_key && !path.is_empty() {
+            return Err(format!("Empty key in path '{}'", path));
+        }
+        Ok(segments)
+    }
+
+    /// The key that [`query`] treats as a wildcard. `[*]` is read as this
+    /// key too.
+    pub const WILDCARD: &str = "*";
+
+    /// Every value that `segments` match, with its own path. The `*` key
+    /// matches every member of an object and every element of an array, as
+    /// in `users[*].name` or `details.*`.
+    pub fn query<'v>(value: &'v JsonValue, segments: &[PathSegment]) -> Vec<(Vec<PathSegment>, &'v JsonValue)> {
+        let mut found = Vec::new();
+        collect_matches(value, segments, &mut Vec::new(), &mut found);
+        found
+    }
+
+    fn collect_matches<'v>(
+        value: &'v JsonValue,
+        segments: &[PathSegment],
+        path: &mut Vec<PathSegment>,
+        found: &mut Vec<(Vec<PathSegment>, &'v JsonValue)>,
+    ) {
+        let Some((segment, rest)) = segments.split_first() else {
+            found.push((path.clone(), value));
+            return;
+        };
+        let mut visit = |step: PathSegment, child: &'v JsonValue| {
+            path.push(step);
+            collect_matches(child, rest, path, found);
+            path.pop();
+        };
+        match (value, segment) {
+            (JsonValue::Object(obj), PathSegment::Key(key)) if key == WILDCARD => {
+                for (k, v) in obj {
+                    visit(PathSegment::Key(k.clone()), v);
+                }
+            }
+            (JsonValue::Array(arr), PathSegment::Key(key)) if key == WILDCARD => {
+                for (i, v) in arr.iter().enumerate() {
+                    visit(PathSegment::Index(i), v);
+                }
+            }
+            (JsonValue::Object(obj), PathSegment::Key(key)) => {
+                if let Some(v) = obj.get(key) {
+                    visit(segment.clone(), v);
+                }
+            }
+            (JsonValue::Array(arr), PathSegment::Index(index)) => {
+                if let Some(v) = arr.get(*index) {
+                    visit(segment.clone(), v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Formats segments back into the syntax accepted by [`parse_path`].
+    pub fn format_path(segments: &[PathSegment]) -> String {
+        let mut path = String::new();
+        for segment in segments {
+            match segment {
+                PathSegment::Key(key) => {
+                    if key.is_empty() || key.contains(['.', '[', ']', '"']) {
+                        let escaped = key.replace('\\', "\\\\").replace('"', "\\\"");
+                        path.push_str(&format!("[\"{}\"]", escaped));
+                    } else {
+                        if !path.is_empty() {
+                            path.push('.');
+                        }
+                        path.push_str(key);
+                    }
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        path
+    }
+
+
+    /// Splits an RFC 6901 JSON Pointer such as `/users/0/name` into its
+    /// unescaped reference tokens. The empty pointer refers to the whole
+    /// document.
+    pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+        if pointer.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rest = pointer
+            .strip_prefix('/')
+            .ok_or_else(|| format!("JSON Pointer '{}' must start with '/'", pointer))?;
+        rest.split('/')
+            .map(|token| {
+                if token.replace("~0", "").replace("~1", "").contains('~') {
+                    return Err(format!("Invalid escape in JSON Pointer '{}'", pointer));
+                }
+                Ok(token.replace("~1", "/").replace("~0", "~"))
+            })
+            .collect()
+    }
+
+    /// Formats segments as an RFC 6901 JSON Pointer.
+    pub fn to_pointer(segments: &[PathSegment]) -> String {
+        segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+                PathSegment::Index(index) => format!("/{}", index),
+            })
+            .collect()
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn key(k: &str) -> PathSegment {
+            PathSegment::Key(k.to_string())
+        }
+
+        #[test]
+        fn paths_mix_keys_and_indexes() {
+            assert_eq!(parse_path(""), Ok(vec![]));
+            assert_eq!(
+                parse_path("users[0].name"),
+                Ok(vec![key("users"), PathSegment::Index(0), key("name")])
+            );
+            assert_eq!(parse_path("[2][ 1 ]"), Ok(vec![PathSegment::Index(2), PathSegment::Index(1)]));
+            assert_eq!(parse_path("a.*"), Ok(vec![key("a"), key(WILDCARD)]));
+            assert_eq!(parse_path("a[*]"), Ok(vec![key("a"), key(WILDCARD)]));
+        }
+
+        #[test]
+        fn quoted_keys_hold_any_character() {
+            assert_eq!(parse_path(r#"["first.name"]"#), Ok(vec![key("first.name")]));
+            assert_eq!(parse_path(r#"a["x]y"].b"#), Ok(vec![key("a"), key("x]y"), key("b")]));
+            assert_eq!(parse_path(r#"["say \"hi\""]"#), Ok(vec![key("say \"hi\"")]));
+            assert_eq!(parse_path(r#"["back\\slash"]"#), Ok(vec![key("back\\slash")]));
+            assert_eq!(parse_path(r#"[""]"#), Ok(vec![key("")]));
+        }
+
+        #[test]
+        fn malformed_paths_are_errors() {
+            for path in ["a..b", ".a", "a.", "a[", "a[x]", r#"["a"#, r#"["a"x]"#, "a[0]b"] {
+                assert!(parse_path(path).is_err(), "{}", path);
+            }
+        }
+
+        #[test]
+        fn formatted_paths_parse_back() {
+            let paths = [
+                vec![key("users"), PathSegment::Index(3), key("name")],
+                vec![key("a.b"), key("c[0]"), key("d]e")],
+                vec![key("quote\"d"), key("back\\slash"), key("")],
+                vec![PathSegment::Index(0), key("x")],
+            ];
+            for segments in paths {
+                let path = format_path(&segments);
+                assert_eq!(parse_path(&path), Ok(segments), "{}", path);
+            }
+            assert_eq!(format_path(&[key("a"), PathSegment::Index(1), key("b.c")]), r#"a[1]["b.c"]"#);
+        }
+
+        #[test]
+        fn pointers_round_trip() {
+            let segments = [key("a/b"), key("m~n"), PathSegment::Index(0)];
+            assert_eq!(to_pointer(&segments), "/a~1b/m~0n/0");
+            assert_eq!(parse_pointer("/a~1b/m~0n/0"), Ok(vec!["a/b".to_string(), "m~n".to_string(), "0".to_string()]));
+        }
+    }
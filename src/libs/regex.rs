@@ -0,0 +1,598 @@
+    /// A small regular expression engine for the ECMA-262 patterns used by
+    /// JSON Schema (`pattern`, `patternProperties`).
+    ///
+    /// Supported: literals and escapes, `.`, character classes with ranges,
+    /// `\d \w \s` and their negations, `^ $ \b \B`, groups (capturing,
+    /// `(?:...)`, `(?=...)`, `(?!...)`), alternation and greedy or lazy
+    /// quantifiers `* + ? {n} {n,} {n,m}`. Matching is unanchored, as the
+    /// specification requires.
+    ///
+    /// Patterns are compiled to a small program that runs every thread in
+    /// lockstep (a Pike VM), so matching never recurses on the text and, for
+    /// patterns without lookaheads, takes time linear in it: `^(a+)+$` or a
+    /// 100 000 character input are no problem. Only a yes or no answer is needed, so lazy quantifiers match
+    /// the same texts as greedy ones.
+    #[derive(Clone, Debug)]
+    pub struct Regex {
+        program: Vec<Inst>,
+    }
+
+    /// Upper bound on the size of a compiled program, reached by nesting
+    /// counted repetitions such as `((a{1000}){1000})`.
+    const MAX_PROGRAM: usize = 100_000;
+
+    #[derive(Clone, Debug)]
+    enum ClassItem {
+        Char(char),
+        Range(char, char),
+        Digit(bool),
+        Word(bool),
+        Space(bool),
+    }
+
+    #[derive(Clone, Debug)]
+    enum Node {
+        Empty,
+        Char(char),
+        Any,
+        Class(Vec<ClassItem>, bool),
+        Start,
+        End,
+        WordBoundary(bool),
+        Look(Box<Node>, bool),
+        Concat(Vec<Node>),
+        Alternation(Vec<Node>),
+        Repeat(Box<Node>, usize, Option<usize>),
+    }
+
+    fn is_word(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
+    fn is_space(c: char) -> bool {
+        c.is_whitespace() || c == '\u{feff}'
+    }
+
+    impl ClassItem {
+        fn matches(&self, c: char) -> bool {
+            match *self {
+                ClassItem::Char(x) => c == x,
+                ClassItem::Range(a, b) => a <= c && c <= b,
+                ClassItem::Digit(positive) => c.is_ascii_digit() == positive,
+                ClassItem::Word(positive) => is_word(c) == positive,
+                ClassItem::Space(positive) => is_space(c) == positive,
+            }
+        }
+    }
+
+    struct Parser<'p> {
+        chars: Vec<char>,
+        position: usize,
+        pattern: &'p str,
+    }
+
+    impl Parser<'_> {
+        fn error(&self, message: &str) -> String {
+            format!("Invalid regular expression '{}': {}", self.pattern, message)
+        }
+
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.position).copied()
+        }
+
+        fn eat(&mut self, c: char) -> bool {
+            if self.peek() == Some(c) {
+                self.position += 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        fn alternation(&mut self) -> Result<Node, String> {
+            let mut branches = vec![self.concat()?];
+            while self.eat('|') {
+                branches.push(self.concat()?);
+            }
+            Ok(if branches.len() == 1 {
+                branches.pop().unwrap_or(Node::Empty)
+            } else {
+                Node::Alternation(branches)
+            })
+        }
+
+        fn concat(&mut self) -> Result<Node, String> {
+            let mut nodes = Vec::new();
+            while let Some(c) = self.peek() {
+                if c == '|' || c == ')' {
+                    break;
+                }
+                let atom = self.atom()?;
+                nodes.push(self.quantifier(atom)?);
+            }
+            Ok(Node::Concat(nodes))
+        }
+
+        fn number(&mut self) -> Option<usize> {
+            let start = self.position;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+            self.chars[start..self.position].iter().collect::<String>().parse().ok()
+        }
+
+        fn quantifier(&mut self, atom: Node) -> Result<Node, String> {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    let start = self.position;
+                    self.position += 1;
+                    let Some(min) = self.number() else {
+                        // Not a quantifier: `{` is a literal character.
+                        self.position = start;
+                        return Ok(atom);
+                    };
+                    let max = if self.eat(',') { self.number() } else { Some(min) };
+                    if !self.eat('}') {
+                        self.position = start;
+                        return Ok(atom);
+                    }
+                    self.position -= 1;
+                    if max.is_some_and(|max| max < min) {
+                        return Err(self.error("numbers out of order in quantifier"));
+                    }
+                    (min, max)
+                }
+                _ => return Ok(atom),
+            };
+            self.position += 1;
+            if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+                return Err(self.error("nothing to repeat"));
+            }
+            // Lazy and greedy quantifiers accept the same texts.
+            self.eat('?');
+            Ok(Node::Repeat(Box::new(atom), min, max))
+        }
+
+        fn atom(&mut self) -> Result<Node, String> {
+            let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+            self.position += 1;
+            match c {
+                '.' => Ok(Node::Any),
+                '^' => Ok(Node::Start),
+                '$' => Ok(Node::End),
+                '(' => {
+                    let look = if self.eat('?') {
+                        if self.eat(':') {
+                            None
+                        } else if self.eat('=') {
+                            Some(true)
+                        } else if self.eat('!') {
+                            Some(false)
+                        } else {
+                            return Err(self.error("unsupported group syntax"));
+                        }
+                    } else {
+                        None
+                    };
+                    let inner = self.alternation()?;
+                    if !self.eat(')') {
+                        return Err(self.error("missing ')'"));
+                    }
+                    Ok(match look {
+                        Some(positive) => Node::Look(Box::new(inner), positive),
+                        None => inner,
+                    })
+                }
+                ')' => Err(self.error("unmatched ')'")),
+                '[' => self.class(),
+                '*' | '+' | '?' => Err(self.error("nothing to repeat")),
+                '\\' => self.escape(false).map(|item| match item {
+                    Escape::Item(ClassItem::Char(c)) => Node::Char(c),
+                    Escape::Item(item) => Node::Class(vec![item], false),
+                    Escape::Boundary(positive) => Node::WordBoundary(positive),
+                }),
+                c => Ok(Node::Char(c)),
+            }
+        }
+
+        fn class(&mut self) -> Result<Node, String> {
+            let negated = self.eat('^');
+            let mut items = Vec::new();
+            loop {
+                let c = self.peek().ok_or_else(|| self.error("missing ']'"))?;
+                self.position += 1;
+                if c == ']' {
+                    break;
+                }
+                let item = if c == '\\' {
+                    match self.escape(true)? {
+                        Escape::Item(item) => item,
+                        Escape::Boundary(_) => ClassItem::Char('\u{8}'),
+                    }
+                } else {
+                    ClassItem::Char(c)
+                };
+                if let ClassItem::Char(start) = item {
+                    if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|&c| c != ']') {
+                        self.position += 1;
+                        let end = match self.peek() {
+                            Some('\\') => {
+                                self.position += 1;
+                                match self.escape(true)? {
+                                    Escape::Item(ClassItem::Char(end)) => end,
+                                    _ => return Err(self.error("invalid character class range")),
+                                }
+                            }
+                            Some(end) => {
+                                self.position += 1;
+                                end
+                            }
+                            None => return Err(self.error("missing ']'")),
+                        };
+                        if end < start {
+                            return Err(self.error("range out of order in character class"));
+                        }
+                        items.push(ClassItem::Range(start, end));
+                        continue;
+                    }
+                }
+                items.push(item);
+            }
+            Ok(Node::Class(items, negated))
+        }
+
+        fn hex(&mut self, count: usize) -> Result<char, String> {
+            let digits: String = self.chars.iter().skip(self.position).take(count).collect();
+            if digits.len() != count || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(self.error("invalid hexadecimal escape"));
+            }
+            self.position += count;
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| self.error("invalid hexadecimal escape"))
+        }
+
+        fn escape(&mut self, in_class: bool) -> Result<Escape, String> {
+            let c = self.peek().ok_or_else(|| self.error("trailing backslash"))?;
+            self.position += 1;
+            let item = match c {
+                'd' => ClassItem::Digit(true),
+                'D' => ClassItem::Digit(false),
+                'w' => ClassItem::Word(true),
+                'W' => ClassItem::Word(false),
+                's' => ClassItem::Space(true),
+                'S' => ClassItem::Space(false),
+                'b' if !in_class => return Ok(Escape::Boundary(true)),
+                'B' if !in_class => return Ok(Escape::Boundary(false)),
+                'b' => ClassItem::Char('\u{8}'),
+                'n' => ClassItem::Char('\n'),
+                'r' => ClassItem::Char('\r'),
+                't' => ClassItem::Char('\t'),
+                'f' => ClassItem::Char('\u{c}'),
+                'v' => ClassItem::Char('\u{b}'),
+                '0' => ClassItem::Char('\0'),
+                'x' => ClassItem::Char(self.hex(2)?),
+                'u' => ClassItem::Char(self.hex(4)?),
+                'p' | 'P' => return Err(self.error("unicode property escapes are not supported")),
+                c if c.is_ascii_digit() => return Err(self.error("backreferences are not supported")),
+                c => ClassItem::Char(c),
+            };
+            Ok(Escape::Item(item))
+        }
+    }
+
+    enum Escape {
+        Item(ClassItem),
+        Boundary(bool),
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Assertion {
+        Start,
+        End,
+        WordBoundary(bool),
+    }
+
+    #[derive(Clone, Debug)]
+    enum Inst {
+        Char(char),
+        Any,
+        Class(Vec<ClassItem>, bool),
+        Assert(Assertion),
+        Look(Vec<Inst>, bool),
+        Split(usize, usize),
+        Jump(usize),
+        Match,
+    }
+
+    struct Compiler<'p> {
+        program: Vec<Inst>,
+        pattern: &'p str,
+    }
+
+    impl Compiler<'_> {
+        fn push(&mut self, inst: Inst) -> Result<usize, String> {
+            if self.program.len() >= MAX_PROGRAM {
+                return Err(format!("Invalid regular expression '{}': pattern is too large", self.pattern));
+            }
+            self.program.push(inst);
+            Ok(self.program.len() - 1)
+        }
+
+        fn patch(&mut self, at: usize, target: usize) {
+            match &mut self.program[at] {
+                Inst::Split(_, second) => *second = target,
+                Inst::Jump(to) => *to = target,
+                _ => {}
+            }
+        }
+
+        fn node(&mut self, node: &Node) -> Result<(), String> {
+            match node {
+                Node::Empty => {}
+                Node::Char(c) => {
+                    self.push(Inst::Char(*c))?;
+                }
+                Node::Any => {
+                    self.push(Inst::Any)?;
+                }
+                Node::Class(items, negated) => {
+                    self.push(Inst::Class(items.clone(), *negated))?;
+                }
+                Node::Start => {
+                    self.push(Inst::Assert(Assertion::Start))?;
+                }
+                Node::End => {
+                    self.push(Inst::Assert(Assertion::End))?;
+                }
+                Node::WordBoundary(positive) => {
+                    self.push(Inst::Assert(Assertion::WordBoundary(*positive)))?;
+                }
+                Node::Look(inner, positive) => {
+                    let program = compile(inner, self.pattern)?;
+                    self.push(Inst::Look(program, *positive))?;
+                }
+                Node::Concat(nodes) => {
+                    for node in nodes {
+                        self.node(node)?;
+                    }
+                }
+                Node::Alternation(branches) => {
+                    let mut jumps = Vec::new();
+                    for (index, branch) in branches.iter().enumerate() {
+                        if index + 1 == branches.len() {
+                            self.node(branch)?;
+                            break;
+                        }
+                        let split = self.push(Inst::Split(self.program.len() + 1, 0))?;
+                        self.node(branch)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        let next = self.program.len();
+                        self.patch(split, next);
+                    }
+                    let end = self.program.len();
+                    for jump in jumps {
+                        self.patch(jump, end);
+                    }
+                }
+                Node::Repeat(inner, min, max) => {
+                    for _ in 0..*min {
+                        self.node(inner)?;
+                    }
+                    match max {
+                        None => {
+                            let split = self.push(Inst::Split(self.program.len() + 1, 0))?;
+                            self.node(inner)?;
+                            self.push(Inst::Jump(split))?;
+                            let end = self.program.len();
+                            self.patch(split, end);
+                        }
+                        Some(max) => {
+                            let mut splits = Vec::new();
+                            for _ in *min..*max {
+                                splits.push(self.push(Inst::Split(self.program.len() + 1, 0))?);
+                                self.node(inner)?;
+                            }
+                            let end = self.program.len();
+                            for split in splits {
+                                self.patch(split, end);
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn compile(node: &Node, pattern: &str) -> Result<Vec<Inst>, String> {
+        let mut compiler = Compiler {
+            program: Vec::new(),
+            pattern,
+        };
+        compiler.node(node)?;
+        compiler.push(Inst::Match)?;
+        Ok(compiler.program)
+    }
+
+    impl Regex {
+        pub fn new(pattern: &str) -> Result<Regex, String> {
+            let mut parser = Parser {
+                chars: pattern.chars().collect(),
+                position: 0,
+                pattern,
+            };
+            let node = parser.alternation()?;
+            if parser.position < parser.chars.len() {
+                return Err(parser.error("unmatched ')'"));
+            }
+            Ok(Regex {
+                program: compile(&node, pattern)?,
+            })
+        }
+
+        /// Whether the pattern matches anywhere in `text`.
+        pub fn is_match(&self, text: &str) -> bool {
+            let chars: Vec<char> = text.chars().collect();
+            run(&self.program, &chars, 0, true)
+        }
+    }
+
+    fn word_at(text: &[char], position: usize) -> bool {
+        text.get(position).is_some_and(|&c| is_word(c))
+    }
+
+    fn holds(assertion: Assertion, text: &[char], position: usize) -> bool {
+        match assertion {
+            Assertion::Start => position == 0,
+            Assertion::End => position == text.len(),
+            Assertion::WordBoundary(positive) => {
+                let before = position > 0 && word_at(text, position - 1);
+                (before != word_at(text, position)) == positive
+            }
+        }
+    }
+
+    /// Runs `program` over `text` from `start`. A search also starts a new
+    /// thread at every later position; otherwise the match must begin at
+    /// `start`. Either way it may end anywhere.
+    fn run(program: &[Inst], text: &[char], start: usize, search: bool) -> bool {
+        // `seen[pc] == step` marks instructions already reached at this step,
+        // which also stops repetitions of empty groups from looping.
+        let mut seen = vec![usize::MAX; program.len()];
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        let mut stack = Vec::new();
+        for position in start..=text.len() {
+            if search || position == start {
+                stack.push(0);
+            }
+            stack.extend(next.drain(..).rev());
+            current.clear();
+            while let Some(pc) = stack.pop() {
+                if seen[pc] == position {
+                    continue;
+                }
+                seen[pc] = position;
+                match &program[pc] {
+                    Inst::Match => return true,
+                    Inst::Jump(to) => stack.push(*to),
+                    Inst::Split(first, second) => {
+                        stack.push(*second);
+                        stack.push(*first);
+                    }
+                    Inst::Assert(assertion) => {
+                        if holds(*assertion, text, position) {
+                            stack.push(pc + 1);
+                        }
+                    }
+                    Inst::Look(inner, positive) => {
+                        if run(inner, text, position, false) == *positive {
+                            stack.push(pc + 1);
+                        }
+                    }
+                    Inst::Char(_) | Inst::Any | Inst::Class(..) => current.push(pc),
+                }
+            }
+            let Some(&c) = text.get(position) else {
+                break;
+            };
+            for &pc in &current {
+                let accepted = match &program[pc] {
+                    Inst::Char(x) => c == *x,
+                    Inst::Any => !matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}'),
+                    Inst::Class(items, negated) => items.iter().any(|item| item.matches(c)) != *negated,
+                    _ => false,
+                };
+                if accepted {
+                    next.push(pc + 1);
+                }
+            }
+            if next.is_empty() && !search {
+                break;
+            }
+        }
+        false
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn matches(pattern: &str, text: &str) -> bool {
+            Regex::new(pattern).unwrap().is_match(text)
+        }
+
+        #[test]
+        fn literals_classes_and_anchors() {
+            assert!(matches("b", "abc"));
+            assert!(!matches("^b", "abc"));
+            assert!(matches("^a.c$", "abc"));
+            assert!(!matches("^a.c$", "a\nc"));
+            assert!(matches("^[a-c]+\\d$", "cab7"));
+            assert!(!matches("^[^a-c]", "cab"));
+            assert!(matches("^\\w+\\s\\W$", "word !"));
+            assert!(matches("^\\x41\\u00e9$", "Aé"));
+            assert!(matches("a{", "a{"));
+        }
+
+        #[test]
+        fn alternation_and_quantifiers() {
+            assert!(matches("^(cat|dog)s?$", "dogs"));
+            assert!(!matches("^(cat|dog)s?$", "cow"));
+            assert!(matches("^a{2,3}$", "aaa"));
+            assert!(!matches("^a{2,3}$", "aaaa"));
+            assert!(!matches("^a{2}$", "a"));
+            assert!(matches("^a{2,}$", "aaaaa"));
+            assert!(matches("^a+?b*?$", "aabb"));
+            assert!(matches("^(a*)*b$", "aaab"));
+            assert!(matches("^(|a)+$", ""));
+        }
+
+        #[test]
+        fn boundaries_and_lookaheads() {
+            assert!(matches("\\bcat\\b", "a cat here"));
+            assert!(!matches("\\bcat\\b", "concatenate"));
+            assert!(matches("\\Bcat\\B", "concatenate"));
+            assert!(matches("^(?=.*\\d)(?!.*x).{3}$", "a1b"));
+            assert!(!matches("^(?=.*\\d)(?!.*x).{3}$", "a1x"));
+            assert!(!matches("^(?=.*\\d)", "abc"));
+        }
+
+        #[test]
+        fn invalid_patterns_are_errors() {
+            for pattern in ["(a", "a)", "[a", "*a", "a{3,1}", "[z-a]", "\\1", "\\p{L}", "(?<n>a)", "^*"] {
+                assert!(Regex::new(pattern).is_err(), "{}", pattern);
+            }
+            let error = Regex::new("((a{1000}){1000})").unwrap_err();
+            assert!(error.contains("pattern is too large"), "{}", error);
+        }
+
+        #[test]
+        fn long_inputs_do_not_overflow_the_stack() {
+            let text = "x".repeat(100_000);
+            assert!(matches("^.*$", &text));
+            assert!(matches("^[a-z]*$", &text));
+            assert!(!matches("^[a-w]*$", &text));
+            assert!(matches("(x|y)+$", &text));
+        }
+
+        #[test]
+        fn nested_quantifiers_are_linear() {
+            let text = format!("{}b", "a".repeat(10_000));
+            assert!(!matches("^(a+)+$", &text));
+            assert!(!matches("^(a|aa)*$", &text));
+            assert!(matches("^(a+)+b$", &text));
+        }
+
+        #[test]
+        fn unanchored_search_is_linear() {
+            let text = "a".repeat(100_000);
+            assert!(!matches("a*b", &text));
+            assert!(matches("aaa$", &text));
+        }
+    }
@@ -0,0 +1,1060 @@
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+
+    use crate::libs::diff::{equivalent, DiffOptions};
+    use crate::libs::formats::check_format;
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::map::JsonMap;
+    use crate::libs::path::{to_pointer, PathSegment};
+    use crate::libs::regex::Regex;
+
+    /// Nesting limit for `$ref`, which protects against schemas that refer
+    /// to themselves without consuming any of the instance.
+    const MAX_DEPTH: usize = 256;
+
+    /// One violation found by [`JsonSchema::validate`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct ValidationError {
+        /// JSON Pointer to the offending value in the instance.
+        pub instance_path: String,
+        /// JSON Pointer to the keyword that failed in the schema.
+        pub schema_path: String,
+        pub message: String,
+    }
+
+    impl fmt::Display for ValidationError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let instance = if self.instance_path.is_empty() { "(root)" } else { &self.instance_path };
+            write!(f, "{}: {} (schema: #{})", instance, self.message, self.schema_path)
+        }
+    }
+
+    impl ValidationError {
+        pub fn to_json(&self) -> JsonValue {
+            JsonValue::Object(JsonMap::from(vec![
+                ("instancePath".to_string(), JsonValue::String(self.instance_path.clone())),
+                ("schemaPath".to_string(), JsonValue::String(self.schema_path.clone())),
+                ("message".to_string(), JsonValue::String(self.message.clone())),
+            ]))
+        }
+    }
+
+    /// A JSON Schema (draft 2020-12) ready to validate instances.
+    pub struct JsonSchema {
+        root: JsonValue,
+        /// Schemas reachable by absolute `$id` URI or `URI#anchor`, with
+        /// their schema path. The root is also reachable by its own URI,
+        /// empty when it has no `$id`.
+        resources: HashMap<String, String>,
+        /// Base URI around each schema that has an `$id`, by schema path.
+        /// A `$ref` landing on one resolves its `$id` against this again.
+        scopes: HashMap<String, String>,
+        /// Compiled `pattern` and `patternProperties` regular expressions.
+        regexes: HashMap<String, Result<Regex, String>>,
+    }
+
+    /// The properties and items a schema has evaluated successfully, used by
+    /// `unevaluatedProperties` and `unevaluatedItems`.
+    #[derive(Default)]
+    struct Evaluated {
+        properties: HashSet<String>,
+        /// Length of the prefix evaluated by `prefixItems`.
+        items: usize,
+        /// Indexes matched by `contains`, wherever they are.
+        indexes: HashSet<usize>,
+        all_items: bool,
+    }
+
+    impl Evaluated {
+        fn absorb(&mut self, other: Evaluated) {
+            self.properties.extend(other.properties);
+            self.items = self.items.max(other.items);
+            self.indexes.extend(other.indexes);
+            self.all_items |= other.all_items;
+        }
+    }
+
+    /// The keywords of one schema object.
+    struct Keywords<'s>(&'s JsonMap);
+
+    impl<'s> Keywords<'s> {
+        fn get(&self, name: &str) -> Option<&'s JsonValue> {
+            self.0.get(name)
+        }
+    }
+
+    struct Context<'a> {
+        instance_path: Vec<PathSegment>,
+        schema_path: String,
+        /// Base URI that relative `$ref` and `$id` values resolve against.
+        base: String,
+        errors: &'a mut Vec<ValidationError>,
+    }
+
+    fn has_type(value: &JsonValue, expected: &str) -> bool {
+        let actual = value.type_name();
+        actual == expected || (expected == "number" && actual == "integer")
+    }
+
+    fn as_number(value: Option<&JsonValue>) -> Option<f64> {
+        match value {
+            Some(JsonValue::Number(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_count(value: Option<&JsonValue>) -> Option<usize> {
+        as_number(value).filter(|n| *n >= 0.0).map(|n| n as usize)
+    }
+
+    fn escape_token(token: &str) -> String {
+        token.replace('~', "~0").replace('/', "~1")
+    }
+
+    fn same(a: &JsonValue, b: &JsonValue) -> bool {
+        equivalent(a, b, &DiffOptions::default())
+    }
+
+    fn has_scheme(uri: &str) -> bool {
+        match uri.split_once(':') {
+            Some((scheme, _)) => {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            }
+            None => false,
+        }
+    }
+
+    /// Removes `.` and `..` segments from a URI path (RFC 3986, 5.2.4).
+    fn remove_dot_segments(path: &str) -> String {
+        let mut output: Vec<&str> = Vec::new();
+        let mut segments = path.split('/').peekable();
+        while let Some(segment) = segments.next() {
+            let last = segments.peek().is_none();
+            match segment {
+                "." => {}
+                ".." => {
+                    if output.len() > 1 || output.first().is_some_and(|s| !s.is_empty()) {
+                        output.pop();
+                    }
+                }
+                segment => output.push(segment),
+            }
+            if last && matches!(segment, "." | "..") {
+                output.push("");
+            }
+        }
+        output.join("/")
+    }
+
+    /// Resolves `reference` against `base` (RFC 3986, 5.2). Without a base,
+    /// relative references stay relative.
+    fn resolve_uri(base: &str, reference: &str) -> String {
+        let base = base.split('#').next().unwrap_or("");
+        if reference.is_empty() {
+            return base.to_string();
+        }
+        if reference.starts_with('#') {
+            return format!("{}{}", base, reference);
+        }
+        if has_scheme(reference) {
+            return reference.to_string();
+        }
+        let (scheme, rest) = match base.find(':') {
+            Some(end) if has_scheme(base) => base.split_at(end + 1),
+            _ => ("", base),
+        };
+        if reference.starts_with("//") {
+            return format!("{}{}", scheme, reference);
+        }
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(after) => rest.split_at(after.find(['/', '?']).unwrap_or(after.len()) + 2),
+            None => ("", rest),
+        };
+        let path = path.split('?').next().unwrap_or("");
+        let merged = if reference.starts_with('/') {
+            reference.to_string()
+        } else if reference.starts_with('?') {
+            format!("{}{}", path, reference)
+        } else {
+            match path.rfind('/') {
+                Some(end) => format!("{}{}", &path[..=end], reference),
+                None if !authority.is_empty() => format!("/{}", reference),
+                None => reference.to_string(),
+            }
+        };
+        let (merged_path, suffix) = match merged.find(['?', '#']) {
+            Some(end) => merged.split_at(end),
+            None => (merged.as_str(), ""),
+        };
+        format!("{}{}{}{}", scheme, authority, remove_dot_segments(merged_path), suffix)
+    }
+
+    /// Decodes `%XX` escapes in a URI fragment.
+    fn percent_decode(fragment: &str) -> Option<String> {
+        let bytes = fragment.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = fragment.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(decoded).ok()
+    }
+
+    impl JsonSchema {
+        pub fn new(schema: JsonValue) -> Result<Self, String> {
+            if !matches!(schema, JsonValue::Object(_) | JsonValue::Boolean(_)) {
+                return Err("A schema must be an object or a boolean".to_string());
+            }
+            let mut compiled = JsonSchema {
+                root: schema,
+                resources: HashMap::new(),
+                scopes: HashMap::new(),
+                regexes: HashMap::new(),
+            };
+            let mut resources = HashMap::new();
+            let mut scopes = HashMap::new();
+            let mut patterns = Vec::new();
+            index_schema(&compiled.root, String::new(), "", &mut resources, &mut scopes, &mut patterns);
+            compiled.resources = resources;
+            compiled.scopes = scopes;
+            for pattern in patterns {
+                let regex = Regex::new(&pattern);
+                compiled.regexes.insert(pattern, regex);
+            }
+            Ok(compiled)
+        }
+
+        /// Validates `instance` and returns every violation found.
+        pub fn validate(&self, instance: &JsonValue) -> Vec<ValidationError> {
+            let mut errors = Vec::new();
+            let mut context = Context {
+                instance_path: Vec::new(),
+                schema_path: String::new(),
+                base: String::new(),
+                errors: &mut errors,
+            };
+            self.validate_node(&self.root, instance, &mut context, 0);
+            errors
+        }
+
+        pub fn is_valid(&self, instance: &JsonValue) -> bool {
+            self.validate(instance).is_empty()
+        }
+
+        fn error(&self, context: &mut Context, keyword: &str, message: String) {
+            context.errors.push(ValidationError {
+                instance_path: to_pointer(&context.instance_path),
+                schema_path: if keyword.is_empty() {
+                    context.schema_path.clone()
+                } else {
+                    format!("{}/{}", context.schema_path, keyword)
+                },
+                message,
+            });
+        }
+
+        /// Validates against a subschema reached through `keyword`. Errors go
+        /// to `errors` and the result says whether it passed.
+        #[allow(clippy::too_many_arguments)]
+        fn validate_sub(
+            &self,
+            schema: &JsonValue,
+            instance: &JsonValue,
+            keyword: &str,
+            segment: Option<PathSegment>,
+            context: &Context,
+            errors: &mut Vec<ValidationError>,
+            depth: usize,
+        ) -> (bool, Evaluated) {
+            let before = errors.len();
+            let mut instance_path = context.instance_path.clone();
+            instance_path.extend(segment);
+            let mut sub = Context {
+                instance_path,
+                schema_path: format!("{}/{}", context.schema_path, keyword),
+                base: context.base.clone(),
+                errors,
+            };
+            let evaluated = self.validate_node(schema, instance, &mut sub, depth + 1);
+            (sub.errors.len() == before, evaluated)
+        }
+
+        fn regex(&self, pattern: &str) -> Result<&Regex, String> {
+            match self.regexes.get(pattern) {
+                Some(Ok(regex)) => Ok(regex),
+                Some(Err(err)) => Err(err.clone()),
+                None => Err(format!("Pattern '{}' was not compiled", pattern)),
+            }
+        }
+
+        /// Finds the schema `reference` points to from `base`, with its
+        /// schema path and the base URI to validate it with.
+        fn resolve(&self, base: &str, reference: &str) -> Option<(&JsonValue, String, String)> {
+            let uri = resolve_uri(base, reference);
+            let (resource, fragment) = uri.split_once('#').unwrap_or((&uri, ""));
+            let fragment = percent_decode(fragment)?;
+            let resource_path = self.resources.get(resource)?;
+            let path = if fragment.is_empty() || fragment.starts_with('/') {
+                format!("{}{}", resource_path, fragment)
+            } else {
+                self.resources.get(&format!("{}#{}", resource, fragment))?.clone()
+            };
+            let base = self.scopes.get(&path).cloned().unwrap_or_else(|| resource.to_string());
+            Some((self.root.pointer(&path)?, path, base))
+        }
+
+        fn validate_node(&self, schema: &JsonValue, instance: &JsonValue, context: &mut Context, depth: usize) -> Evaluated {
+            let mut evaluated = Evaluated::default();
+            let obj = match schema {
+                JsonValue::Boolean(true) => return evaluated,
+                JsonValue::Boolean(false) => {
+                    self.error(context, "", "no value is allowed here".to_string());
+                    return evaluated;
+                }
+                JsonValue::Object(obj) => obj,
+                _ => return evaluated,
+            };
+            if depth > MAX_DEPTH {
+                self.error(context, "$ref", "schema nesting is too deep".to_string());
+                return evaluated;
+            }
+            let keywords = Keywords(obj);
+            if let Some(JsonValue::String(id)) = keywords.get("$id") {
+                context.base = resolve_uri(&context.base, id.trim_end_matches('#'));
+            }
+
+            if let Some(JsonValue::String(reference)) = keywords.get("$ref") {
+                match self.resolve(&context.base, reference) {
+                    Some((target, _, target_base)) => {
+                        let mut errors = Vec::new();
+                        let base = std::mem::replace(&mut context.base, target_base);
+                        let (_, sub) = self.validate_sub(target, instance, "$ref", None, context, &mut errors, depth);
+                        context.base = base;
+                        context.errors.extend(errors);
+                        evaluated.absorb(sub);
+                    }
+                    None => self.error(context, "$ref", format!("cannot resolve reference '{}'", reference)),
+                }
+            }
+
+            self.validate_generic(&keywords, instance, context);
+            match instance {
+                JsonValue::Number(n) => self.validate_number(&keywords, *n, context),
+                JsonValue::String(s) => self.validate_string(&keywords, s, context),
+                JsonValue::Array(arr) => self.validate_array(&keywords, arr, context, &mut evaluated, depth),
+                JsonValue::Object(members) => self.validate_object(&keywords, members, context, &mut evaluated, depth),
+                _ => {}
+            }
+            self.validate_applicators(&keywords, instance, context, &mut evaluated, depth);
+            self.validate_unevaluated(&keywords, instance, context, &mut evaluated, depth);
+            evaluated
+        }
+
+        fn validate_generic(&self, keywords: &Keywords, instance: &JsonValue, context: &mut Context) {
+            match keywords.get("type") {
+                Some(JsonValue::String(expected)) if !has_type(instance, expected) => self.error(
+                    context,
+                    "type",
+                    format!("expected {}, found {}", expected, instance.type_name()),
+                ),
+                Some(JsonValue::Array(types)) => {
+                    let matched = types.iter().any(|t| matches!(t, JsonValue::String(t) if has_type(instance, t)));
+                    if !matched {
+                        let names: Vec<String> = types.iter().map(|t| t.to_json_string()).collect();
+                        self.error(
+                            context,
+                            "type",
+                            format!("expected one of {}, found {}", names.join(", "), instance.type_name()),
+                        );
+                    }
+                }
+                _ => {}
+            }
+            if let Some(JsonValue::Array(values)) = keywords.get("enum") {
+                if !values.iter().any(|v| same(v, instance)) {
+                    self.error(context, "enum", format!("{} is not one of the allowed values", instance.to_json_string()));
+                }
+            }
+            if let Some(expected) = keywords.get("const") {
+                if !same(expected, instance) {
+                    self.error(context, "const", format!("expected {}", expected.to_json_string()));
+                }
+            }
+        }
+
+        fn validate_number(&self, keywords: &Keywords, n: f64, context: &mut Context) {
+            if let Some(m) = as_number(keywords.get("multipleOf")) {
+                let quotient = n / m;
+                if m > 0.0 && (quotient - quotient.round()).abs() > 1e-9 {
+                    self.error(context, "multipleOf", format!("{} is not a multiple of {}", n, m));
+                }
+            }
+            type Check = (&'static str, fn(f64, f64) -> bool, &'static str);
+            let checks: [Check; 4] = [
+                ("minimum", |n, m| n >= m, ">="),
+                ("maximum", |n, m| n <= m, "<="),
+                ("exclusiveMinimum", |n, m| n > m, ">"),
+                ("exclusiveMaximum", |n, m| n < m, "<"),
+            ];
+            for (name, check, operator) in checks {
+                if let Some(limit) = as_number(keywords.get(name)) {
+                    if !check(n, limit) {
+                        self.error(context, name, format!("{} must be {} {}", n, operator, limit));
+                    }
+                }
+            }
+        }
+
+        fn validate_string(&self, keywords: &Keywords, s: &str, context: &mut Context) {
+            let length = s.chars().count();
+            if let Some(min) = as_count(keywords.get("minLength")) {
+                if length < min {
+                    self.error(context, "minLength", format!("string is shorter than {} characters", min));
+                }
+            }
+            if let Some(max) = as_count(keywords.get("maxLength")) {
+                if length > max {
+                    self.error(context, "maxLength", format!("string is longer than {} characters", max));
+                }
+            }
+            if let Some(JsonValue::String(pattern)) = keywords.get("pattern") {
+                match self.regex(pattern) {
+                    Ok(regex) if !regex.is_match(s) => {
+                        self.error(context, "pattern", format!("does not match pattern '{}'", pattern))
+                    }
+                    Ok(_) => {}
+                    Err(err) => self.error(context, "pattern", err),
+                }
+            }
+            if let Some(JsonValue::String(format)) = keywords.get("format") {
+                if check_format(format, s) == Some(false) {
+                    self.error(context, "format", format!("is not a valid {}", format));
+                }
+            }
+        }
+
+        fn validate_array(
+            &self,
+            keywords: &Keywords,
+            arr: &[JsonValue],
+            context: &mut Context,
+            evaluated: &mut Evaluated,
+            depth: usize,
+        ) {
+            if let Some(min) = as_count(keywords.get("minItems")) {
+                if arr.len() < min {
+                    self.error(context, "minItems", format!("array has fewer than {} items", min));
+                }
+            }
+            if let Some(max) = as_count(keywords.get("maxItems")) {
+                if arr.len() > max {
+                    self.error(context, "maxItems", format!("array has more than {} items", max));
+                }
+            }
+            if let Some(JsonValue::Boolean(true)) = keywords.get("uniqueItems") {
+                let duplicate = (0..arr.len()).find_map(|i| {
+                    (i + 1..arr.len()).find(|&j| same(&arr[i], &arr[j])).map(|j| (i, j))
+                });
+                if let Some((i, j)) = duplicate {
+                    self.error(context, "uniqueItems", format!("items {} and {} are equal", i, j));
+                }
+            }
+
+            let mut errors = Vec::new();
+            let mut prefix = 0;
+            if let Some(JsonValue::Array(schemas)) = keywords.get("prefixItems") {
+                for (i, (schema, item)) in schemas.iter().zip(arr).enumerate() {
+                    let path = format!("prefixItems/{}", i);
+                    self.validate_sub(schema, item, &path, Some(PathSegment::Index(i)), context, &mut errors, depth);
+                }
+                prefix = schemas.len().min(arr.len());
+                evaluated.items = evaluated.items.max(prefix);
+            }
+            if let Some(schema) = keywords.get("items") {
+                for (i, item) in arr.iter().enumerate().skip(prefix) {
+                    self.validate_sub(schema, item, "items", Some(PathSegment::Index(i)), context, &mut errors, depth);
+                }
+                evaluated.all_items = true;
+            }
+            context.errors.extend(errors);
+
+            if let Some(schema) = keywords.get("contains") {
+                let matched: Vec<usize> = (0..arr.len())
+                    .filter(|&i| {
+                        let mut ignored = Vec::new();
+                        self.validate_sub(schema, &arr[i], "contains", Some(PathSegment::Index(i)), context, &mut ignored, depth)
+                            .0
+                    })
+                    .collect();
+                let matches = matched.len();
+                let min = as_count(keywords.get("minContains")).unwrap_or(1);
+                if matches < min {
+                    self.error(
+                        context,
+                        "contains",
+                        format!("array contains {} matching items, at least {} required", matches, min),
+                    );
+                }
+                if let Some(max) = as_count(keywords.get("maxContains")) {
+                    if matches > max {
+                        self.error(
+                            context,
+                            "maxContains",
+                            format!("array contains {} matching items, at most {} allowed", matches, max),
+                        );
+                    }
+                }
+                evaluated.indexes.extend(matched);
+            }
+        }
+
+        fn validate_object(
+            &self,
+            keywords: &Keywords,
+            members: &JsonMap,
+            context: &mut Context,
+            evaluated: &mut Evaluated,
+            depth: usize,
+        ) {
+            let has = |name: &str| members.contains_key(name);
+            if let Some(min) = as_count(keywords.get("minProperties")) {
+                if members.len() < min {
+                    self.error(context, "minProperties", format!("object has fewer than {} properties", min));
+                }
+            }
+            if let Some(max) = as_count(keywords.get("maxProperties")) {
+                if members.len() > max {
+                    self.error(context, "maxProperties", format!("object has more than {} properties", max));
+                }
+            }
+            if let Some(JsonValue::Array(required)) = keywords.get("required") {
+                for name in required {
+                    if let JsonValue::String(name) = name {
+                        if !has(name) {
+                            self.error(context, "required", format!("missing required property '{}'", name));
+                        }
+                    }
+                }
+            }
+            if let Some(JsonValue::Object(dependencies)) = keywords.get("dependentRequired") {
+                for (trigger, required) in dependencies {
+                    if !has(trigger) {
+                        continue;
+                    }
+                    if let JsonValue::Array(required) = required {
+                        for name in required {
+                            if let JsonValue::String(name) = name {
+                                if !has(name) {
+                                    self.error(
+                                        context,
+                                        &format!("dependentRequired/{}", escape_token(trigger)),
+                                        format!("property '{}' requires property '{}'", trigger, name),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut errors = Vec::new();
+            let properties = match keywords.get("properties") {
+                Some(JsonValue::Object(properties)) => Some(properties),
+                _ => None,
+            };
+            let patterns = match keywords.get("patternProperties") {
+                Some(JsonValue::Object(patterns)) => patterns.as_slice(),
+                _ => &[],
+            };
+            for (name, value) in members {
+                let segment = || Some(PathSegment::Key(name.clone()));
+                let mut matched = false;
+                if let Some(schema) = properties.and_then(|properties| properties.get(name)) {
+                    let path = format!("properties/{}", escape_token(name));
+                    self.validate_sub(schema, value, &path, segment(), context, &mut errors, depth);
+                    matched = true;
+                }
+                for (pattern, schema) in patterns {
+                    match self.regex(pattern) {
+                        Ok(regex) if regex.is_match(name) => {
+                            let path = format!("patternProperties/{}", escape_token(pattern));
+                            self.validate_sub(schema, value, &path, segment(), context, &mut errors, depth);
+                            matched = true;
+                        }
+                        Ok(_) => {}
+                        Err(err) => self.error(context, "patternProperties", err),
+                    }
+                }
+                if matched {
+                    evaluated.properties.insert(name.clone());
+                } else if let Some(schema) = keywords.get("additionalProperties") {
+                    self.validate_sub(schema, value, "additionalProperties", segment(), context, &mut errors, depth);
+                    evaluated.properties.insert(name.clone());
+                }
+                if let Some(schema) = keywords.get("propertyNames") {
+                    let key = JsonValue::String(name.clone());
+                    self.validate_sub(schema, &key, "propertyNames", segment(), context, &mut errors, depth);
+                }
+            }
+            if let Some(JsonValue::Object(dependencies)) = keywords.get("dependentSchemas") {
+                for (trigger, schema) in dependencies {
+                    if has(trigger) {
+                        let path = format!("dependentSchemas/{}", escape_token(trigger));
+                        let (_, sub) = self.validate_sub(
+                            schema,
+                            &JsonValue::Object(members.clone()),
+                            &path,
+                            None,
+                            context,
+                            &mut errors,
+                            depth,
+                        );
+                        evaluated.absorb(sub);
+                    }
+                }
+            }
+            context.errors.extend(errors);
+        }
+
+        fn validate_applicators(
+            &self,
+            keywords: &Keywords,
+            instance: &JsonValue,
+            context: &mut Context,
+            evaluated: &mut Evaluated,
+            depth: usize,
+        ) {
+            if let Some(JsonValue::Array(schemas)) = keywords.get("allOf") {
+                let mut errors = Vec::new();
+                for (i, schema) in schemas.iter().enumerate() {
+                    let path = format!("allOf/{}", i);
+                    let (_, sub) = self.validate_sub(schema, instance, &path, None, context, &mut errors, depth);
+                    evaluated.absorb(sub);
+                }
+                context.errors.extend(errors);
+            }
+
+            for name in ["anyOf", "oneOf"] {
+                let Some(JsonValue::Array(schemas)) = keywords.get(name) else {
+                    continue;
+                };
+                let mut branch_errors = Vec::new();
+                let mut passed = Vec::new();
+                for (i, schema) in schemas.iter().enumerate() {
+                    let path = format!("{}/{}", name, i);
+                    let (valid, sub) = self.validate_sub(schema, instance, &path, None, context, &mut branch_errors, depth);
+                    if valid {
+                        passed.push(i);
+                        evaluated.absorb(sub);
+                    }
+                }
+                if passed.is_empty() {
+                    self.error(context, name, format!("value does not match any schema in {}", name));
+                    context.errors.extend(branch_errors);
+                } else if name == "oneOf" && passed.len() > 1 {
+                    self.error(
+                        context,
+                        name,
+                        format!("value matches more than one schema in oneOf (indexes {:?})", passed),
+                    );
+                }
+            }
+
+            if let Some(schema) = keywords.get("not") {
+                let mut ignored = Vec::new();
+                if self.validate_sub(schema, instance, "not", None, context, &mut ignored, depth).0 {
+                    self.error(context, "not", "value must not match the schema in 'not'".to_string());
+                }
+            }
+
+            if let Some(condition) = keywords.get("if") {
+                let mut ignored = Vec::new();
+                let (holds, sub) = self.validate_sub(condition, instance, "if", None, context, &mut ignored, depth);
+                let branch = if holds {
+                    evaluated.absorb(sub);
+                    "then"
+                } else {
+                    "else"
+                };
+                if let Some(schema) = keywords.get(branch) {
+                    let mut errors = Vec::new();
+                    let (_, sub) = self.validate_sub(schema, instance, branch, None, context, &mut errors, depth);
+                    evaluated.absorb(sub);
+                    context.errors.extend(errors);
+                }
+            }
+        }
+
+        fn validate_unevaluated(
+            &self,
+            keywords: &Keywords,
+            instance: &JsonValue,
+            context: &mut Context,
+            evaluated: &mut Evaluated,
+            depth: usize,
+        ) {
+            let mut errors = Vec::new();
+            match instance {
+                JsonValue::Object(members) => {
+                    if let Some(schema) = keywords.get("unevaluatedProperties") {
+                        for (name, value) in members {
+                            if !evaluated.properties.contains(name) {
+                                self.validate_sub(
+                                    schema,
+                                    value,
+                                    "unevaluatedProperties",
+                                    Some(PathSegment::Key(name.clone())),
+                                    context,
+                                    &mut errors,
+                                    depth,
+                                );
+                            }
+                        }
+                        evaluated.properties.extend(members.iter().map(|(k, _)| k.clone()));
+                    }
+                }
+                JsonValue::Array(arr) => {
+                    if let Some(schema) = keywords.get("unevaluatedItems") {
+                        if !evaluated.all_items {
+                            for (i, item) in arr.iter().enumerate().skip(evaluated.items) {
+                                if evaluated.indexes.contains(&i) {
+                                    continue;
+                                }
+                                self.validate_sub(
+                                    schema,
+                                    item,
+                                    "unevaluatedItems",
+                                    Some(PathSegment::Index(i)),
+                                    context,
+                                    &mut errors,
+                                    depth,
+                                );
+                            }
+                        }
+                        evaluated.all_items = true;
+                    }
+                }
+                _ => {}
+            }
+            context.errors.extend(errors);
+        }
+    }
+
+    /// Records `$id` and `$anchor` locations, resolved against the base URI
+    /// in scope, and collects every pattern.
+    fn index_schema(
+        schema: &JsonValue,
+        path: String,
+        base: &str,
+        resources: &mut HashMap<String, String>,
+        scopes: &mut HashMap<String, String>,
+        patterns: &mut Vec<String>,
+    ) {
+        match schema {
+            JsonValue::Object(obj) => {
+                let base = match obj.get("$id") {
+                    Some(JsonValue::String(id)) => {
+                        scopes.insert(path.clone(), base.to_string());
+                        resolve_uri(base, id.trim_end_matches('#'))
+                    }
+                    _ => base.to_string(),
+                };
+                if path.is_empty() || obj.contains_key("$id") {
+                    resources.insert(base.clone(), path.clone());
+                }
+                for (key, value) in obj {
+                    match (key.as_str(), value) {
+                        ("$anchor", JsonValue::String(anchor)) => {
+                            resources.insert(format!("{}#{}", base, anchor), path.clone());
+                        }
+                        ("pattern", JsonValue::String(pattern)) => patterns.push(pattern.clone()),
+                        ("patternProperties", JsonValue::Object(properties)) => {
+                            patterns.extend(properties.iter().map(|(k, _)| k.clone()));
+                        }
+                        _ => {}
+                    }
+                    // `enum`, `const` and `examples` hold data, not schemas.
+                    if !matches!(key.as_str(), "enum" | "const" | "examples" | "default") {
+                        index_schema(value, format!("{}/{}", path, escape_token(key)), &base, resources, scopes, patterns);
+                    }
+                }
+            }
+            JsonValue::Array(arr) => {
+                for (i, value) in arr.iter().enumerate() {
+                    index_schema(value, format!("{}/{}", path, i), base, resources, scopes, patterns);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        fn schema(input: &str) -> JsonSchema {
+            JsonSchema::new(parse(input)).unwrap()
+        }
+
+        /// The schema paths of the errors `instance` produces.
+        fn failures(schema_source: &str, instance: &str) -> Vec<String> {
+            schema(schema_source)
+                .validate(&parse(instance))
+                .into_iter()
+                .map(|error| error.schema_path)
+                .collect()
+        }
+
+        fn valid(schema_source: &str, instance: &str) -> bool {
+            schema(schema_source).is_valid(&parse(instance))
+        }
+
+        #[test]
+        fn type_enum_and_const() {
+            assert!(valid(r#"{"type": "number"}"#, "3"));
+            assert!(valid(r#"{"type": "integer"}"#, "3.0"));
+            assert!(!valid(r#"{"type": "integer"}"#, "3.5"));
+            assert!(valid(r#"{"type": ["string", "null"]}"#, "null"));
+            assert_eq!(failures(r#"{"type": ["string", "null"]}"#, "1"), ["/type"]);
+            assert!(valid(r#"{"enum": [1, "a", {"b": [2]}]}"#, r#"{"b": [2.0]}"#));
+            assert_eq!(failures(r#"{"enum": [1, "a"]}"#, "2"), ["/enum"]);
+            assert_eq!(failures(r#"{"const": {"a": 1}}"#, r#"{"a": 2}"#), ["/const"]);
+            assert!(valid("true", "[]"));
+            assert_eq!(failures("false", "[]"), [""]);
+            assert!(JsonSchema::new(parse("1")).is_err());
+        }
+
+        #[test]
+        fn numbers() {
+            let limits = r#"{"minimum": 1, "exclusiveMaximum": 10, "multipleOf": 0.5}"#;
+            assert!(valid(limits, "1"));
+            assert!(valid(limits, "9.5"));
+            assert_eq!(failures(limits, "10"), ["/exclusiveMaximum"]);
+            assert_eq!(failures(limits, "0.25"), ["/multipleOf", "/minimum"]);
+            assert_eq!(failures(r#"{"maximum": 3, "exclusiveMinimum": 3}"#, "3"), ["/exclusiveMinimum"]);
+            // Keywords for other types are ignored.
+            assert!(valid(limits, r#""text""#));
+        }
+
+        #[test]
+        fn strings() {
+            let rules = r#"{"minLength": 2, "maxLength": 3, "pattern": "^[a-zé]+$"}"#;
+            assert!(valid(rules, r#""éé""#));
+            assert_eq!(failures(rules, r#""a""#), ["/minLength"]);
+            assert_eq!(failures(rules, r#""abcd""#), ["/maxLength"]);
+            assert_eq!(failures(rules, r#""A1""#), ["/pattern"]);
+            assert_eq!(failures(r#"{"pattern": "("}"#, r#""x""#), ["/pattern"]);
+            assert!(valid(r#"{"format": "date"}"#, r#""2024-02-29""#));
+            assert_eq!(failures(r#"{"format": "date"}"#, r#""2023-02-29""#), ["/format"]);
+            assert!(valid(r#"{"format": "unknown-format"}"#, r#""x""#));
+        }
+
+        #[test]
+        fn long_strings_match_patterns() {
+            let text = format!("\"{}\"", "a".repeat(60_000));
+            assert!(valid(r#"{"pattern": "^[a-z]*$"}"#, &text));
+            assert!(!valid(r#"{"pattern": "^(a+)+$"}"#, &format!("\"{}!\"", "a".repeat(60_000))));
+        }
+
+        #[test]
+        fn arrays() {
+            let tuple = r#"{"prefixItems": [{"type": "string"}], "items": {"type": "number"}, "minItems": 1, "maxItems": 3}"#;
+            assert!(valid(tuple, r#"["a", 1, 2]"#));
+            assert_eq!(failures(tuple, r#"[1, "b"]"#), ["/prefixItems/0/type", "/items/type"]);
+            assert_eq!(failures(tuple, "[]"), ["/minItems"]);
+            assert_eq!(failures(tuple, r#"["a", 1, 2, 3]"#), ["/maxItems"]);
+            assert_eq!(failures(r#"{"uniqueItems": true}"#, "[1, {\"a\": 1}, {\"a\": 1.0}]"), ["/uniqueItems"]);
+            assert!(valid(r#"{"uniqueItems": true}"#, "[1, \"1\", [1]]"));
+
+            let contains = r#"{"contains": {"type": "string"}, "minContains": 2, "maxContains": 3}"#;
+            assert!(valid(contains, r#"["a", 1, "b"]"#));
+            assert_eq!(failures(contains, r#"["a", 1]"#), ["/contains"]);
+            assert_eq!(failures(contains, r#"["a", "b", "c", "d"]"#), ["/maxContains"]);
+            assert!(valid(r#"{"contains": true, "minContains": 0}"#, "[]"));
+        }
+
+        #[test]
+        fn objects() {
+            let rules = r#"{
+                "properties": {"name": {"type": "string"}},
+                "patternProperties": {"^x-": {"type": "number"}},
+                "additionalProperties": false,
+                "required": ["name"],
+                "minProperties": 1,
+                "maxProperties": 3
+            }"#;
+            assert!(valid(rules, r#"{"name": "a", "x-size": 1}"#));
+            assert_eq!(failures(rules, r#"{"name": 1, "x-size": "big"}"#), ["/properties/name/type", "/patternProperties/^x-/type"]);
+            assert_eq!(failures(rules, r#"{"name": "a", "other": 1}"#), ["/additionalProperties"]);
+            assert_eq!(failures(rules, "{}"), ["/minProperties", "/required"]);
+            assert_eq!(failures(rules, r#"{"name": "a", "x-1": 1, "x-2": 2, "x-3": 3}"#), ["/maxProperties"]);
+
+            let dependent = r#"{
+                "dependentRequired": {"card": ["billing"]},
+                "dependentSchemas": {"a/b": {"required": ["c"]}},
+                "propertyNames": {"maxLength": 7}
+            }"#;
+            assert!(valid(dependent, r#"{"card": 1, "billing": 2}"#));
+            assert_eq!(failures(dependent, r#"{"card": 1}"#), ["/dependentRequired/card"]);
+            assert_eq!(failures(dependent, r#"{"a/b": 1}"#), ["/dependentSchemas/a~1b/required"]);
+            assert_eq!(failures(dependent, r#"{"too long": 1}"#), ["/propertyNames/maxLength"]);
+        }
+
+        #[test]
+        fn combinators_and_conditionals() {
+            let all = r#"{"allOf": [{"type": "number"}, {"minimum": 2}]}"#;
+            assert!(valid(all, "3"));
+            assert_eq!(failures(all, "1"), ["/allOf/1/minimum"]);
+
+            let any = r#"{"anyOf": [{"type": "string"}, {"minimum": 2}]}"#;
+            assert!(valid(any, "3"));
+            assert_eq!(failures(any, "1"), ["/anyOf", "/anyOf/0/type", "/anyOf/1/minimum"]);
+
+            let one = r#"{"oneOf": [{"type": "number"}, {"minimum": 2}]}"#;
+            assert!(valid(one, "1"));
+            assert_eq!(failures(one, "3"), ["/oneOf"]);
+
+            assert_eq!(failures(r#"{"not": {"type": "null"}}"#, "null"), ["/not"]);
+
+            let conditional = r#"{"if": {"minimum": 10}, "then": {"multipleOf": 10}, "else": {"maximum": 5}}"#;
+            assert!(valid(conditional, "20"));
+            assert!(valid(conditional, "4"));
+            assert_eq!(failures(conditional, "15"), ["/then/multipleOf"]);
+            assert_eq!(failures(conditional, "7"), ["/else/maximum"]);
+        }
+
+        #[test]
+        fn unevaluated_properties_see_through_applicators() {
+            let rules = r#"{
+                "properties": {"a": true},
+                "allOf": [{"properties": {"b": true}}],
+                "if": {"required": ["c"], "properties": {"c": true}},
+                "unevaluatedProperties": false
+            }"#;
+            assert!(valid(rules, r#"{"a": 1, "b": 2, "c": 3}"#));
+            assert_eq!(failures(rules, r#"{"a": 1, "d": 4}"#), ["/unevaluatedProperties"]);
+            let failing_branch = r#"{"anyOf": [{"properties": {"a": {"type": "string"}}}, true], "unevaluatedProperties": false}"#;
+            assert_eq!(failures(failing_branch, r#"{"a": 1}"#), ["/unevaluatedProperties"]);
+        }
+
+        #[test]
+        fn unevaluated_items_count_prefix_and_contains() {
+            let prefix = r#"{"prefixItems": [true], "unevaluatedItems": false}"#;
+            assert!(valid(prefix, "[1]"));
+            assert_eq!(failures(prefix, "[1, 2]"), ["/unevaluatedItems"]);
+
+            let contains = r#"{"contains": {"type": "string"}, "unevaluatedItems": false}"#;
+            assert!(valid(contains, r#"["a", "b"]"#));
+            let errors = schema(contains).validate(&parse(r#"["a", 1]"#));
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].instance_path, "/1");
+
+            let nested = r#"{"allOf": [{"contains": {"const": 1}}], "prefixItems": [true], "unevaluatedItems": false}"#;
+            assert!(valid(nested, "[0, 1, 1]"));
+            assert_eq!(failures(nested, "[0, 1, 2]"), ["/unevaluatedItems"]);
+        }
+
+        #[test]
+        fn references_within_the_document() {
+            let rules = r##"{
+                "$defs": {
+                    "positive": {"type": "number", "exclusiveMinimum": 0},
+                    "a/b%c": {"type": "string"},
+                    "named": {"$anchor": "name", "minLength": 1}
+                },
+                "properties": {
+                    "count": {"$ref": "#/$defs/positive"},
+                    "escaped": {"$ref": "#/$defs/a~1b%25c"},
+                    "label": {"$ref": "#name"},
+                    "tree": {"$ref": "#/properties/tree/$defs/node", "$defs": {"node": {"items": {"$ref": "#/properties/tree/$defs/node"}}}}
+                }
+            }"##;
+            assert!(valid(rules, r#"{"count": 1, "escaped": "x", "label": "y", "tree": [[], [[]]]}"#));
+            assert_eq!(failures(rules, r#"{"count": 0}"#), ["/properties/count/$ref/exclusiveMinimum"]);
+            assert_eq!(failures(rules, r#"{"escaped": 1}"#), ["/properties/escaped/$ref/type"]);
+            assert_eq!(failures(rules, r#"{"label": ""}"#), ["/properties/label/$ref/minLength"]);
+            assert_eq!(failures(r##"{"$ref": "#/nowhere"}"##, "1"), ["/$ref"]);
+        }
+
+        #[test]
+        fn references_resolve_against_the_base_uri() {
+            let rules = r##"{
+                "$id": "https://example.com/schemas/root.json",
+                "properties": {
+                    "item": {"$ref": "item.json"},
+                    "absolute": {"$ref": "https://example.com/schemas/item.json#/properties/size"},
+                    "anchored": {"$ref": "other/tag.json#tag"}
+                },
+                "$defs": {
+                    "item": {
+                        "$id": "item.json",
+                        "properties": {"size": {"$ref": "#/$defs/size"}},
+                        "$defs": {"size": {"type": "integer"}}
+                    },
+                    "tag": {"$id": "other/tag.json", "$anchor": "tag", "$ref": "../root.json#/$defs/word"},
+                    "word": {"type": "string"}
+                }
+            }"##;
+            assert!(valid(rules, r#"{"item": {"size": 1}, "absolute": 2, "anchored": "x"}"#));
+            assert_eq!(
+                failures(rules, r#"{"item": {"size": 1.5}}"#),
+                ["/properties/item/$ref/properties/size/$ref/type"]
+            );
+            assert_eq!(failures(rules, r#"{"absolute": "2"}"#), ["/properties/absolute/$ref/$ref/type"]);
+            assert_eq!(failures(rules, r#"{"anchored": 1}"#), ["/properties/anchored/$ref/$ref/type"]);
+        }
+
+        #[test]
+        fn uris_are_resolved_like_rfc_3986() {
+            let base = "http://a/b/c/d;p?q";
+            for (reference, expected) in [
+                ("g", "http://a/b/c/g"),
+                ("./g", "http://a/b/c/g"),
+                ("g/", "http://a/b/c/g/"),
+                ("/g", "http://a/g"),
+                ("//g", "http://g"),
+                ("?y", "http://a/b/c/d;p?y"),
+                ("g?y#s", "http://a/b/c/g?y#s"),
+                ("#s", "http://a/b/c/d;p?q#s"),
+                ("", "http://a/b/c/d;p?q"),
+                (".", "http://a/b/c/"),
+                ("..", "http://a/b/"),
+                ("../g", "http://a/b/g"),
+                ("../../../g", "http://a/g"),
+                ("urn:x", "urn:x"),
+            ] {
+                assert_eq!(resolve_uri(base, reference), expected, "{}", reference);
+            }
+            assert_eq!(resolve_uri("", "item.json"), "item.json");
+            assert_eq!(resolve_uri("dir/a.json", "b.json#x"), "dir/b.json#x");
+        }
+
+        #[test]
+        fn recursive_references_stop() {
+            let errors = schema(r##"{"$ref": "#"}"##).validate(&parse("1"));
+            assert!(errors.iter().any(|error| error.message == "schema nesting is too deep"));
+        }
+
+        #[test]
+        fn errors_point_into_the_instance() {
+            let errors = schema(r#"{"properties": {"a/b": {"items": {"type": "string"}}}}"#)
+                .validate(&parse(r#"{"a/b": ["x", 2]}"#));
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].instance_path, "/a~1b/1");
+            assert_eq!(errors[0].schema_path, "/properties/a~1b/items/type");
+            assert_eq!(errors[0].to_string(), "/a~1b/1: expected string, found integer (schema: #/properties/a~1b/items/type)");
+        }
+    }
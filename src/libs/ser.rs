@@ -0,0 +1,540 @@
+    use serde::ser::{self, Impossible, Serialize};
+
+    use crate::libs::de::Error;
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::serializer::{write_newline, write_number, write_string, SerializeOptions};
+
+    /// A serde `Serializer` producing the same text as [`serialize`], laid
+    /// out according to [`SerializeOptions`].
+    ///
+    /// [`serialize`]: crate::libs::serializer::serialize
+    pub struct Serializer {
+        out: String,
+        options: SerializeOptions,
+        level: usize,
+    }
+
+    impl Serializer {
+        pub fn new(options: SerializeOptions) -> Self {
+            Self { out: String::new(), options, level: 0 }
+        }
+
+        pub fn into_inner(self) -> String {
+            self.out
+        }
+
+        fn begin(&mut self, open: char) {
+            self.out.push(open);
+            self.level += 1;
+        }
+
+        fn separator(&mut self, first: bool) {
+            if !first {
+                self.out.push(',');
+            }
+            write_newline(&self.options, self.level, &mut self.out);
+        }
+
+        fn end(&mut self, close: char, empty: bool) {
+            self.level -= 1;
+            if !empty {
+                write_newline(&self.options, self.level, &mut self.out);
+            }
+            self.out.push(close);
+        }
+
+        fn key(&mut self, key: &str) {
+            write_string(key, &mut self.out);
+            self.out.push_str(if self.options.indent.is_some() { ": " } else { ":" });
+        }
+    }
+
+    /// Serializes `value` as compact JSON text.
+    pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+        to_string_with_options(value, SerializeOptions::compact())
+    }
+
+    /// Serializes `value` as indented JSON text.
+    pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+        to_string_with_options(value, SerializeOptions::default())
+    }
+
+    pub fn to_string_with_options<T: Serialize + ?Sized>(value: &T, options: SerializeOptions) -> Result<String, Error> {
+        let mut serializer = Serializer::new(options);
+        value.serialize(&mut serializer)?;
+        Ok(serializer.into_inner())
+    }
+
+    /// An array or object being written. With `sort_keys`, object members are
+    /// rendered separately and written once all of them are known.
+    pub struct Compound<'a> {
+        ser: &'a mut Serializer,
+        first: bool,
+        close: char,
+        sorted: Option<Vec<(String, String)>>,
+        pending_key: Option<String>,
+        /// Also closes the object wrapping an enum variant's content.
+        variant: bool,
+    }
+
+    impl<'a> Compound<'a> {
+        fn new(ser: &'a mut Serializer, open: char, close: char) -> Self {
+            ser.begin(open);
+            let sorted = (open == '{' && ser.options.sort_keys).then(Vec::new);
+            Self { ser, first: true, close, sorted, pending_key: None, variant: false }
+        }
+
+        /// Opens `{"variant": ` before the variant's array or object.
+        fn variant(ser: &'a mut Serializer, variant: &str, open: char, close: char) -> Self {
+            ser.begin('{');
+            ser.separator(true);
+            ser.key(variant);
+            Self { variant: true, ..Self::new(ser, open, close) }
+        }
+
+        fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.ser.separator(self.first);
+            self.first = false;
+            value.serialize(&mut *self.ser)
+        }
+
+        fn member<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+            if let Some(sorted) = &mut self.sorted {
+                let mut member = Serializer::new(self.ser.options.clone());
+                member.level = self.ser.level;
+                value.serialize(&mut member)?;
+                sorted.push((key, member.out));
+                return Ok(());
+            }
+            self.ser.separator(self.first);
+            self.first = false;
+            self.ser.key(&key);
+            value.serialize(&mut *self.ser)
+        }
+
+        fn finish(mut self) -> Result<(), Error> {
+            if let Some(mut sorted) = self.sorted.take() {
+                sorted.sort_by(|a, b| a.0.cmp(&b.0));
+                for (key, value) in sorted {
+                    self.ser.separator(self.first);
+                    self.first = false;
+                    self.ser.key(&key);
+                    self.ser.out.push_str(&value);
+                }
+            }
+            self.ser.end(self.close, self.first);
+            if self.variant {
+                self.ser.end('}', false);
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a> ser::Serializer for &'a mut Serializer {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Compound<'a>;
+        type SerializeTuple = Compound<'a>;
+        type SerializeTupleStruct = Compound<'a>;
+        type SerializeTupleVariant = Compound<'a>;
+        type SerializeMap = Compound<'a>;
+        type SerializeStruct = Compound<'a>;
+        type SerializeStructVariant = Compound<'a>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), Error> {
+            self.out.push_str(if v { "true" } else { "false" });
+            Ok(())
+        }
+
+        fn serialize_i8(self, v: i8) -> Result<(), Error> {
+            self.serialize_i64(v.into())
+        }
+
+        fn serialize_i16(self, v: i16) -> Result<(), Error> {
+            self.serialize_i64(v.into())
+        }
+
+        fn serialize_i32(self, v: i32) -> Result<(), Error> {
+            self.serialize_i64(v.into())
+        }
+
+        fn serialize_i64(self, v: i64) -> Result<(), Error> {
+            self.out.push_str(&v.to_string());
+            Ok(())
+        }
+
+        fn serialize_u8(self, v: u8) -> Result<(), Error> {
+            self.serialize_u64(v.into())
+        }
+
+        fn serialize_u16(self, v: u16) -> Result<(), Error> {
+            self.serialize_u64(v.into())
+        }
+
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.serialize_u64(v.into())
+        }
+
+        fn serialize_u64(self, v: u64) -> Result<(), Error> {
+            self.out.push_str(&v.to_string());
+            Ok(())
+        }
+
+        fn serialize_f32(self, v: f32) -> Result<(), Error> {
+            // Formatting as f32 keeps the shortest text, e.g. 0.1 rather
+            // than 0.10000000149011612.
+            if v.is_finite() {
+                self.out.push_str(&v.to_string());
+                Ok(())
+            } else {
+                self.serialize_f64(v.into())
+            }
+        }
+
+        fn serialize_f64(self, v: f64) -> Result<(), Error> {
+            write_number(v, &self.options, &mut self.out);
+            Ok(())
+        }
+
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            write_string(v.encode_utf8(&mut [0; 4]), &mut self.out);
+            Ok(())
+        }
+
+        fn serialize_str(self, v: &str) -> Result<(), Error> {
+            write_string(v, &mut self.out);
+            Ok(())
+        }
+
+        fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+            ser::Serializer::collect_seq(self, v)
+        }
+
+        fn serialize_none(self) -> Result<(), Error> {
+            self.serialize_unit()
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<(), Error> {
+            self.out.push_str("null");
+            Ok(())
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+            self.serialize_unit()
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), Error> {
+            self.serialize_str(variant)
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            let mut object = Compound::new(self, '{', '}');
+            object.member(variant.to_string(), value)?;
+            object.finish()
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+            Ok(Compound::new(self, '[', ']'))
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Compound<'a>, Error> {
+            Ok(Compound::new(self, '[', ']'))
+        }
+
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>, Error> {
+            Ok(Compound::new(self, '[', ']'))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Compound<'a>, Error> {
+            Ok(Compound::variant(self, variant, '[', ']'))
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+            Ok(Compound::new(self, '{', '}'))
+        }
+
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>, Error> {
+            Ok(Compound::new(self, '{', '}'))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Compound<'a>, Error> {
+            Ok(Compound::variant(self, variant, '{', '}'))
+        }
+    }
+
+    impl ser::SerializeSeq for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeTuple for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeTupleStruct for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeTupleVariant for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeMap for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+            self.pending_key = Some(key.serialize(MapKeySerializer)?);
+            Ok(())
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            let key = self.pending_key.take().ok_or_else(|| Error::new("Map value written before its key"))?;
+            self.member(key, value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeStruct for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.member(key.to_string(), value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    impl ser::SerializeStructVariant for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.member(key.to_string(), value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.finish()
+        }
+    }
+
+    /// Turns object keys into strings; numbers, booleans and unit variants
+    /// are accepted and written as their text.
+    struct MapKeySerializer;
+
+    fn key_error() -> Error {
+        Error::new("Object keys must be strings, numbers or booleans")
+    }
+
+    macro_rules! serialize_key_to_string {
+        ($($method:ident: $t:ty),*) => {$(
+            fn $method(self, v: $t) -> Result<String, Error> {
+                Ok(v.to_string())
+            }
+        )*};
+    }
+
+    impl ser::Serializer for MapKeySerializer {
+        type Ok = String;
+        type Error = Error;
+        type SerializeSeq = Impossible<String, Error>;
+        type SerializeTuple = Impossible<String, Error>;
+        type SerializeTupleStruct = Impossible<String, Error>;
+        type SerializeTupleVariant = Impossible<String, Error>;
+        type SerializeMap = Impossible<String, Error>;
+        type SerializeStruct = Impossible<String, Error>;
+        type SerializeStructVariant = Impossible<String, Error>;
+
+        serialize_key_to_string! {
+            serialize_bool: bool,
+            serialize_i8: i8,
+            serialize_i16: i16,
+            serialize_i32: i32,
+            serialize_i64: i64,
+            serialize_u8: u8,
+            serialize_u16: u16,
+            serialize_u32: u32,
+            serialize_u64: u64,
+            serialize_f32: f32,
+            serialize_f64: f64,
+            serialize_char: char,
+            serialize_str: &str
+        }
+
+        fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_none(self) -> Result<String, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<String, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<String, Error> {
+            Ok(variant.to_string())
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<String, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            Err(key_error())
+        }
+    }
+
+    impl Serialize for JsonValue {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::{SerializeMap, SerializeSeq};
+
+            match self {
+                JsonValue::Object(obj) => {
+                    let mut map = serializer.serialize_map(Some(obj.len()))?;
+                    for (key, value) in obj {
+                        map.serialize_entry(key, value)?;
+                    }
+                    map.end()
+                }
+                JsonValue::Array(arr) => {
+                    let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                    for value in arr {
+                        seq.serialize_element(value)?;
+                    }
+                    seq.end()
+                }
+                JsonValue::String(s) => serializer.serialize_str(s),
+                // Whole numbers are handed over as integers so that other
+                // formats do not turn them into floats.
+                JsonValue::Number(n) if n.fract() == 0.0 && n.abs() < (1u64 << 53) as f64 => {
+                    serializer.serialize_i64(*n as i64)
+                }
+                JsonValue::Number(n) => serializer.serialize_f64(*n),
+                JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+                JsonValue::Null => serializer.serialize_unit(),
+            }
+        }
+    }
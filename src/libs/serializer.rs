@@ -0,0 +1,247 @@
+    use crate::libs::json_parser::{escape_string, JsonValue};
+
+    /// Layout of the plain JSON text produced by [`serialize`].
+    ///
+    /// Unlike the `Display` impl of `JsonValue`, the serializer never emits
+    /// ANSI color codes, so its output can be written to files and pipes.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct SerializeOptions {
+        /// `None` writes everything on one line without any whitespace.
+        pub indent: Option<String>,
+        pub sort_keys: bool,
+        /// With indentation, an array that fits on its line within this many
+        /// columns is written on that line, as `[1, 2, 3]`. Only
+        /// [`serialize`] wraps arrays this way.
+        pub wrap_width: Option<usize>,
+        /// Write numbers in exponent notation when that is shorter, as
+        /// `1e21` instead of `1000000000000000000000`.
+        pub shortest_numbers: bool,
+    }
+
+    impl SerializeOptions {
+        pub fn compact() -> Self {
+            Self { indent: None, sort_keys: false, wrap_width: None, shortest_numbers: false }
+        }
+
+        /// The smallest text for a value: compact, with the shortest numbers.
+        pub fn minified() -> Self {
+            Self { shortest_numbers: true, ..Self::compact() }
+        }
+
+        pub fn pretty(indent: usize) -> Self {
+            Self { indent: Some(" ".repeat(indent)), sort_keys: false, wrap_width: None, shortest_numbers: false }
+        }
+    }
+
+    impl Default for SerializeOptions {
+        fn default() -> Self {
+            Self::pretty(4)
+        }
+    }
+
+    pub fn serialize(value: &JsonValue, options: &SerializeOptions) -> String {
+        let mut out = String::new();
+        write_value(value, options, 0, &mut out);
+        out
+    }
+
+    /// Formats a number. Values JSON cannot represent (`NaN`, infinities)
+    /// are written as `null`.
+    pub fn format_number(n: f64) -> String {
+        if n.is_finite() {
+            format!("{}", n)
+        } else {
+            "null".to_string()
+        }
+    }
+
+    /// Formats a number in as few characters as round-trip to the same
+    /// value: decimal or exponent notation, whichever is shorter.
+    pub fn format_number_shortest(n: f64) -> String {
+        let decimal = format_number(n);
+        if !n.is_finite() {
+            return decimal;
+        }
+        let exponent = format!("{:e}", n);
+        if exponent.len() < decimal.len() {
+            exponent
+        } else {
+            decimal
+        }
+    }
+
+    pub(crate) fn write_number(n: f64, options: &SerializeOptions, out: &mut String) {
+        if options.shortest_numbers {
+            out.push_str(&format_number_shortest(n));
+        } else {
+            out.push_str(&format_number(n));
+        }
+    }
+
+    /// Writes a number read from `lexeme` as [`write_number`] writes `n`,
+    /// unless that would be a different number: digits beyond the precision
+    /// of an `f64`, and values it cannot hold, keep the lexeme.
+    pub(crate) fn write_number_lexeme(lexeme: &str, n: f64, options: &SerializeOptions, out: &mut String) {
+        let mut formatted = String::new();
+        write_number(n, options, &mut formatted);
+        match (decimal_parts(lexeme), decimal_parts(&formatted)) {
+            (Some(source), Some(written)) if source == written => out.push_str(&formatted),
+            _ => out.push_str(lexeme),
+        }
+    }
+
+    /// The sign, significant digits and exponent of a decimal or hexadecimal
+    /// number, so that the same number compares equal however it is written.
+    fn decimal_parts(s: &str) -> Option<(bool, String, i64)> {
+        let (negative, unsigned) = match s.as_bytes().first()? {
+            b'-' => (true, &s[1..]),
+            b'+' => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if let Some(hex) = unsigned.strip_prefix("0x").or_else(|| unsigned.strip_prefix("0X")) {
+            let value = u128::from_str_radix(hex, 16).ok()?;
+            return decimal_parts(&value.to_string()).map(|(_, digits, exponent)| (negative, digits, exponent));
+        }
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(i) => (&unsigned[..i], unsigned[i + 1..].parse::<i64>().ok()?),
+            None => (unsigned, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", integer, fraction);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let significant = digits.trim_start_matches('0').trim_end_matches('0');
+        if significant.is_empty() {
+            return Some((negative, String::new(), 0));
+        }
+        let trailing_zeros = digits.len() - digits.trim_end_matches('0').len();
+        let exponent = exponent.checked_sub(fraction.len() as i64)?.checked_add(trailing_zeros as i64)?;
+        Some((negative, significant.to_string(), exponent))
+    }
+
+    pub(crate) fn write_newline(options: &SerializeOptions, level: usize, out: &mut String) {
+        if let Some(indent) = &options.indent {
+            out.push('\n');
+            for _ in 0..level {
+                out.push_str(indent);
+            }
+        }
+    }
+
+    pub(crate) fn write_string(s: &str, out: &mut String) {
+        out.push('"');
+        out.push_str(&escape_string(s));
+        out.push('"');
+    }
+
+    fn write_value(value: &JsonValue, options: &SerializeOptions, level: usize, out: &mut String) {
+        match value {
+            JsonValue::Object(obj) => {
+                if obj.is_empty() {
+                    out.push_str("{}");
+                    return;
+                }
+                let mut entries: Vec<&(String, JsonValue)> = obj.iter().collect();
+                if options.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                out.push('{');
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_newline(options, level + 1, out);
+                    write_string(key, out);
+                    out.push_str(if options.indent.is_some() { ": " } else { ":" });
+                    write_value(value, options, level + 1, out);
+                }
+                write_newline(options, level, out);
+                out.push('}');
+            }
+            JsonValue::Array(arr) => {
+                if arr.is_empty() {
+                    out.push_str("[]");
+                    return;
+                }
+                if let (Some(width), Some(_)) = (options.wrap_width, &options.indent) {
+                    let column = out[out.rfind('\n').map_or(0, |i| i + 1)..].chars().count();
+                    let room = width.saturating_sub(column);
+                    let mut line = String::new();
+                    // A character takes at most 4 bytes, so the line is over
+                    // the width once it exceeds 4 bytes per column left.
+                    write_inline(value, options, room * 4, &mut line);
+                    if line.chars().count() <= room {
+                        out.push_str(&line);
+                        return;
+                    }
+                }
+                out.push('[');
+                for (i, value) in arr.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_newline(options, level + 1, out);
+                    write_value(value, options, level + 1, out);
+                }
+                write_newline(options, level, out);
+                out.push(']');
+            }
+            JsonValue::String(s) => write_string(s, out),
+            JsonValue::Number(n) => write_number(*n, options, out),
+            JsonValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Null => out.push_str("null"),
+        }
+    }
+
+    /// Writes `value` on one line with a space after each separator, giving
+    /// up once the text is longer than `limit` bytes.
+    fn write_inline(value: &JsonValue, options: &SerializeOptions, limit: usize, out: &mut String) {
+        match value {
+            JsonValue::Object(obj) if !obj.is_empty() => {
+                let mut entries: Vec<&(String, JsonValue)> = obj.iter().collect();
+                if options.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                out.push('{');
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if out.len() > limit {
+                        return;
+                    }
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_string(key, out);
+                    out.push_str(": ");
+                    write_inline(value, options, limit, out);
+                }
+                out.push('}');
+            }
+            JsonValue::Array(arr) if !arr.is_empty() => {
+                out.push('[');
+                for (i, value) in arr.iter().enumerate() {
+                    if out.len() > limit {
+                        return;
+                    }
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_inline(value, options, limit, out);
+                }
+                out.push(']');
+            }
+            _ => write_value(value, options, 0, out),
+        }
+    }
+
+    impl JsonValue {
+        /// Compact JSON text without any whitespace or color codes.
+        pub fn to_json_string(&self) -> String {
+            serialize(self, &SerializeOptions::compact())
+        }
+
+        /// Indented JSON text without color codes.
+        pub fn to_pretty_string(&self) -> String {
+            serialize(self, &SerializeOptions::default())
+        }
+    }
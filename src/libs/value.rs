@@ -0,0 +1,423 @@
+    use std::collections::{BTreeMap, HashMap};
+    use std::ops::{Index, IndexMut};
+
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::map::JsonMap;
+
+    static NULL: JsonValue = JsonValue::Null;
+
+    /// Types that can index into a `JsonValue`: `usize` for arrays and
+    /// strings for objects.
+    pub trait ValueIndex {
+        fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue>;
+
+        /// Panics when the value cannot hold the index, as `IndexMut` has no
+        /// way to report the error.
+        fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> &'v mut JsonValue;
+    }
+
+    impl ValueIndex for usize {
+        fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue> {
+            match value {
+                JsonValue::Array(arr) => arr.get(*self),
+                _ => None,
+            }
+        }
+
+        fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> &'v mut JsonValue {
+            match value {
+                JsonValue::Array(arr) => {
+                    let len = arr.len();
+                    arr.get_mut(*self)
+                        .unwrap_or_else(|| panic!("Index {} out of bounds for array of length {}", self, len))
+                }
+                _ => panic!("Cannot index {} with {}", value.type_name(), self),
+            }
+        }
+    }
+
+    impl ValueIndex for str {
+        fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue> {
+            value.get_field(self)
+        }
+
+        /// A missing key is inserted as `null`, and `null` itself becomes an
+        /// empty object, so `value["a"]["b"] = ...` builds nested objects.
+        fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> &'v mut JsonValue {
+            if value.is_null() {
+                *value = JsonValue::Object(JsonMap::new());
+            }
+            match value {
+                JsonValue::Object(obj) => {
+                    if !obj.contains_key(self) {
+                        obj.insert(self.to_string(), JsonValue::Null);
+                    }
+                    obj.get_mut(self).unwrap()
+                }
+                _ => panic!("Cannot index {} with key '{}'", value.type_name(), self),
+            }
+        }
+    }
+
+    impl ValueIndex for String {
+        fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue> {
+            self.as_str().index_into(value)
+        }
+
+        fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> &'v mut JsonValue {
+            self.as_str().index_into_mut(value)
+        }
+    }
+
+    impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+        fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue> {
+            (**self).index_into(value)
+        }
+
+        fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> &'v mut JsonValue {
+            (**self).index_into_mut(value)
+        }
+    }
+
+    /// Missing keys, out-of-range indexes and indexing into scalars all
+    /// yield `null`, so lookups can be chained: `value["a"][0]["b"]`.
+    impl<I: ValueIndex> Index<I> for JsonValue {
+        type Output = JsonValue;
+
+        fn index(&self, index: I) -> &JsonValue {
+            index.index_into(self).unwrap_or(&NULL)
+        }
+    }
+
+    impl<I: ValueIndex> IndexMut<I> for JsonValue {
+        fn index_mut(&mut self, index: I) -> &mut JsonValue {
+            index.index_into_mut(self)
+        }
+    }
+
+    impl JsonValue {
+        pub fn as_str(&self) -> Option<&str> {
+            match self {
+                JsonValue::String(s) => Some(s),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64(&self) -> Option<f64> {
+            match self {
+                JsonValue::Number(n) => Some(*n),
+                _ => None,
+            }
+        }
+
+        /// The number as an `i64`, if it is whole and in range.
+        pub fn as_i64(&self) -> Option<i64> {
+            match self {
+                JsonValue::Number(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => {
+                    Some(*n as i64)
+                }
+                _ => None,
+            }
+        }
+
+        /// The number as a `u64`, if it is whole, positive and in range.
+        pub fn as_u64(&self) -> Option<u64> {
+            match self {
+                JsonValue::Number(n) if n.fract() == 0.0 && *n >= 0.0 && *n < u64::MAX as f64 => Some(*n as u64),
+                _ => None,
+            }
+        }
+
+        pub fn as_bool(&self) -> Option<bool> {
+            match self {
+                JsonValue::Boolean(b) => Some(*b),
+                _ => None,
+            }
+        }
+
+        pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+            match self {
+                JsonValue::Array(arr) => Some(arr),
+                _ => None,
+            }
+        }
+
+        pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+            match self {
+                JsonValue::Array(arr) => Some(arr),
+                _ => None,
+            }
+        }
+
+        pub fn as_object(&self) -> Option<&JsonMap> {
+            match self {
+                JsonValue::Object(obj) => Some(obj),
+                _ => None,
+            }
+        }
+
+        pub fn as_object_mut(&mut self) -> Option<&mut JsonMap> {
+            match self {
+                JsonValue::Object(obj) => Some(obj),
+                _ => None,
+            }
+        }
+
+        pub fn is_null(&self) -> bool {
+            matches!(self, JsonValue::Null)
+        }
+
+        /// Moves the value out, leaving `null` in its place.
+        pub fn take(&mut self) -> JsonValue {
+            std::mem::replace(self, JsonValue::Null)
+        }
+    }
+
+    impl From<bool> for JsonValue {
+        fn from(b: bool) -> Self {
+            JsonValue::Boolean(b)
+        }
+    }
+
+    impl From<String> for JsonValue {
+        fn from(s: String) -> Self {
+            JsonValue::String(s)
+        }
+    }
+
+    impl From<&str> for JsonValue {
+        fn from(s: &str) -> Self {
+            JsonValue::String(s.to_string())
+        }
+    }
+
+    impl From<&String> for JsonValue {
+        fn from(s: &String) -> Self {
+            JsonValue::String(s.clone())
+        }
+    }
+
+    impl From<char> for JsonValue {
+        fn from(c: char) -> Self {
+            JsonValue::String(c.to_string())
+        }
+    }
+
+    impl From<()> for JsonValue {
+        fn from(_: ()) -> Self {
+            JsonValue::Null
+        }
+    }
+
+    macro_rules! from_number {
+        ($($t:ty),*) => {$(
+            impl From<$t> for JsonValue {
+                fn from(n: $t) -> Self {
+                    JsonValue::Number(n as f64)
+                }
+            }
+        )*};
+    }
+
+    from_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+    impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+        fn from(value: Option<T>) -> Self {
+            value.map_or(JsonValue::Null, Into::into)
+        }
+    }
+
+    impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+        fn from(values: Vec<T>) -> Self {
+            JsonValue::Array(values.into_iter().map(Into::into).collect())
+        }
+    }
+
+    impl<T: Clone + Into<JsonValue>> From<&[T]> for JsonValue {
+        fn from(values: &[T]) -> Self {
+            JsonValue::Array(values.iter().cloned().map(Into::into).collect())
+        }
+    }
+
+    impl<K: Into<String>, V: Into<JsonValue>> From<BTreeMap<K, V>> for JsonValue {
+        fn from(map: BTreeMap<K, V>) -> Self {
+            map.into_iter().collect()
+        }
+    }
+
+    /// Members are sorted by key so that the result does not depend on the
+    /// hasher.
+    impl<K: Into<String>, V: Into<JsonValue>, S> From<HashMap<K, V, S>> for JsonValue {
+        fn from(map: HashMap<K, V, S>) -> Self {
+            let mut members: JsonMap = map.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+            members.sort_keys();
+            JsonValue::Object(members)
+        }
+    }
+
+    impl<T: Into<JsonValue>> FromIterator<T> for JsonValue {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            JsonValue::Array(iter.into_iter().map(Into::into).collect())
+        }
+    }
+
+    impl<K: Into<String>, V: Into<JsonValue>> FromIterator<(K, V)> for JsonValue {
+        fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+            JsonValue::Object(iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+        }
+    }
+
+    /// Builds a `JsonValue` from JSON-like syntax, e.g.
+    /// `json!({ "name": name, "tags": ["admin", null], "age": 36 })`.
+    /// Values may be any expression convertible with `JsonValue::from`, and
+    /// keys may be string literals or parenthesized expressions.
+    #[macro_export]
+    macro_rules! json {
+        (@array [$($elements:expr,)*]) => {
+            ::std::vec![$($elements,)*]
+        };
+        (@array [$($elements:expr,)*] null $(, $($rest:tt)*)?) => {
+            $crate::json!(@array [$($elements,)* $crate::json!(null),] $($($rest)*)?)
+        };
+        (@array [$($elements:expr,)*] [$($array:tt)*] $(, $($rest:tt)*)?) => {
+            $crate::json!(@array [$($elements,)* $crate::json!([$($array)*]),] $($($rest)*)?)
+        };
+        (@array [$($elements:expr,)*] {$($object:tt)*} $(, $($rest:tt)*)?) => {
+            $crate::json!(@array [$($elements,)* $crate::json!({$($object)*}),] $($($rest)*)?)
+        };
+        (@array [$($elements:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+            $crate::json!(@array [$($elements,)* $crate::json!($next),] $($($rest)*)?)
+        };
+
+        (@object [$($members:expr,)*]) => {
+            $crate::libs::map::JsonMap::from(::std::vec![$($members,)*])
+        };
+        (@object [$($members:expr,)*] $key:tt : null $(, $($rest:tt)*)?) => {
+            $crate::json!(@object [$($members,)* (::std::string::String::from($key), $crate::json!(null)),] $($($rest)*)?)
+        };
+        (@object [$($members:expr,)*] $key:tt : [$($array:tt)*] $(, $($rest:tt)*)?) => {
+            $crate::json!(@object [$($members,)* (::std::string::String::from($key), $crate::json!([$($array)*])),] $($($rest)*)?)
+        };
+        (@object [$($members:expr,)*] $key:tt : {$($object:tt)*} $(, $($rest:tt)*)?) => {
+            $crate::json!(@object [$($members,)* (::std::string::String::from($key), $crate::json!({$($object)*})),] $($($rest)*)?)
+        };
+        (@object [$($members:expr,)*] $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+            $crate::json!(@object [$($members,)* (::std::string::String::from($key), $crate::json!($value)),] $($($rest)*)?)
+        };
+
+        (null) => {
+            $crate::libs::json_parser::JsonValue::Null
+        };
+        ([ $($tt:tt)* ]) => {
+            $crate::libs::json_parser::JsonValue::Array($crate::json!(@array [] $($tt)*))
+        };
+        ({ $($tt:tt)* }) => {
+            $crate::libs::json_parser::JsonValue::Object($crate::json!(@object [] $($tt)*))
+        };
+        ($other:expr) => {
+            $crate::libs::json_parser::JsonValue::from($other)
+        };
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::libs::json_parser::JsonParser;
+
+        fn parse(input: &str) -> JsonValue {
+            JsonParser::new(input).parse().unwrap()
+        }
+
+        #[test]
+        fn json_builds_every_kind_of_value() {
+            let name = "Ada";
+            let key = String::from("computed");
+            let value = json!({
+                "name": name,
+                "age": 36,
+                "ratio": 0.5,
+                "admin": true,
+                "nothing": null,
+                "tags": ["a", null, [1, 2], {"deep": -1}],
+                "empty": {},
+                (key): 1 + 1,
+            });
+            assert_eq!(
+                value.to_json_string(),
+                r#"{"name":"Ada","age":36,"ratio":0.5,"admin":true,"nothing":null,"tags":["a",null,[1,2],{"deep":-1}],"empty":{},"computed":2}"#
+            );
+            assert_eq!(json!(null), JsonValue::Null);
+            assert_eq!(json!([]), JsonValue::Array(Vec::new()));
+            assert_eq!(json!(Some("x")), JsonValue::String("x".to_string()));
+            assert_eq!(json!(None::<i32>), JsonValue::Null);
+            assert_eq!(json!(vec![1, 2]), parse("[1, 2]"));
+        }
+
+        #[test]
+        fn indexing_reads_through_missing_members() {
+            let value = parse(r#"{"a": [{"b": "x"}, 2], "n": 5}"#);
+            assert_eq!(value["a"][0]["b"].as_str(), Some("x"));
+            assert_eq!(value["a"][1].as_i64(), Some(2));
+            assert_eq!(value[String::from("n")].as_u64(), Some(5));
+            assert!(value["missing"].is_null());
+            assert!(value["a"][5].is_null());
+            assert!(value["n"]["deeper"][0].is_null());
+            assert!(value[0].is_null());
+        }
+
+        #[test]
+        fn indexing_mutably_creates_objects() {
+            let mut value = JsonValue::Null;
+            value["a"]["b"] = 1.into();
+            value["a"]["c"] = json!([false]);
+            value["a"]["c"][0] = true.into();
+            assert_eq!(value.to_json_string(), r#"{"a":{"b":1,"c":[true]}}"#);
+            assert_eq!(value["a"]["b"].take(), JsonValue::Number(1.0));
+            assert!(value["a"]["b"].is_null());
+        }
+
+        #[test]
+        #[should_panic(expected = "Index 3 out of bounds for array of length 1")]
+        fn indexing_past_the_end_panics() {
+            let mut value = json!([1]);
+            value[3] = 2.into();
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot index string with key 'a'")]
+        fn indexing_scalars_with_keys_panics() {
+            let mut value = json!("text");
+            value["a"] = 2.into();
+        }
+
+        #[test]
+        fn accessors_check_the_type() {
+            let value = parse(r#"[-3, 2.5, 1e300, "s", true, [], {}]"#);
+            assert_eq!(value[0].as_i64(), Some(-3));
+            assert_eq!(value[0].as_u64(), None);
+            assert_eq!(value[1].as_i64(), None);
+            assert_eq!(value[1].as_f64(), Some(2.5));
+            assert_eq!(value[2].as_i64(), None);
+            assert_eq!(value[3].as_str(), Some("s"));
+            assert_eq!(value[3].as_bool(), None);
+            assert_eq!(value[4].as_bool(), Some(true));
+            assert!(value[5].as_array().is_some_and(|arr| arr.is_empty()));
+            assert!(value[6].as_object().is_some_and(|obj| obj.is_empty()));
+            assert!(value[6].as_array().is_none());
+        }
+
+        #[test]
+        fn conversions() {
+            assert_eq!(JsonValue::from('x'), JsonValue::String("x".to_string()));
+            assert_eq!(JsonValue::from(()), JsonValue::Null);
+            assert_eq!(JsonValue::from(&[1u8, 2][..]), parse("[1, 2]"));
+            let map = HashMap::from([("b", 2), ("a", 1)]);
+            assert_eq!(JsonValue::from(map).to_json_string(), r#"{"a":1,"b":2}"#);
+            let map = BTreeMap::from([("z", "last"), ("y", "first")]);
+            assert_eq!(JsonValue::from(map).to_json_string(), r#"{"y":"first","z":"last"}"#);
+            let collected: JsonValue = (1..4).collect();
+            assert_eq!(collected, parse("[1, 2, 3]"));
+            let collected: JsonValue = vec![("k", true)].into_iter().collect();
+            assert_eq!(collected.to_json_string(), r#"{"k":true}"#);
+        }
+    }
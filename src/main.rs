@@ -1,50 +1,336 @@
-mod libs;
-mod utils;
-
-use crate::libs::json_parser::{JsonParser,JsonValue};
-use crate::utils::handle_arguments::{get_arguments,Command};
-
-fn main() {
-    match get_arguments() {
-        Ok(command) => match command {
-            Command::Parse(content, key, index) => {
-                let mut parser = JsonParser::new(&content);
-                match parser.parse() {
-                    Ok(json_value) => {
-                        if let Some(key) = key {
-                            json_value.handle_json_logic(Some(&key), index);
-                        } else {
-                            println!("{}", json_value);
+use std::io::{self, IsTerminal};
+use std::process::ExitCode;
+
+use json_parser::libs::borrowed;
+use json_parser::libs::document::Document;
+use json_parser::libs::diff::{diff, diff_to_json, format_diff_text};
+use json_parser::libs::codegen::generate_types;
+use json_parser::libs::infer::infer_schema;
+use json_parser::libs::json_parser::{JsonParser,JsonValue,ParseMode};
+use json_parser::libs::merge::deep_merge;
+use json_parser::libs::parallel::{detect_layout, search_records, Layout};
+use json_parser::libs::path::{format_path, parse_path, query};
+use json_parser::libs::schema::JsonSchema;
+use json_parser::libs::patch::diff_to_patch;
+use json_parser::utils::edit::{run_edit, run_patch};
+use json_parser::utils::exit::{Failure, Status};
+use json_parser::utils::files::Inputs;
+use json_parser::utils::format::{run_format, run_minify};
+use json_parser::utils::handle_arguments::{get_arguments,Command,OutputFormat};
+use json_parser::utils::output::Output;
+
+fn main() -> ExitCode {
+    match get_arguments().and_then(run) {
+        Ok(status) => status.into(),
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            failure.status.into()
+        }
+    }
+}
+
+fn parse_error(e: String) -> Failure {
+    Failure::parse(format!("Error parsing JSON: {}", e))
+}
+
+fn parse_files(files: &[(String, String)], mode: ParseMode) -> Result<Vec<JsonValue>, Failure> {
+    files
+        .iter()
+        .map(|(name, content)| {
+            JsonParser::with_mode(content, mode)
+                .parse()
+                .map_err(|e| Failure::parse(format!("Error parsing JSON in {}: {}", name, e)))
+        })
+        .collect()
+}
+
+/// Runs `each` on every input, passing the file name when the output
+/// should carry it. A single input fails the command on its first error.
+/// With several, a file that cannot be read or parsed, or has no match, is
+/// reported with its name and the others still run; a summary follows on
+/// stderr.
+fn for_each_input(
+    inputs: Inputs,
+    mut each: impl FnMut(Option<&str>, &str) -> Result<Status, Failure>,
+) -> Result<Status, Failure> {
+    if !inputs.with_names {
+        return match inputs.files.into_iter().next() {
+            Some(input) => each(None, &input.content?),
+            None => Ok(Status::NoMatch),
+        };
+    }
+    let scanned = inputs.files.len();
+    let (mut matched, mut failed, mut unreadable) = (0, 0, 0);
+    for input in inputs.files {
+        let read = input.content.is_ok();
+        match input.content.and_then(|content| each(Some(&input.name), &content)) {
+            Ok(Status::Success) => matched += 1,
+            Ok(_) => {}
+            // Errors writing the output end the whole command.
+            Err(failure) if read && !matches!(failure.status, Status::Parse | Status::NoMatch) => return Err(failure),
+            Err(failure) => {
+                eprintln!("{}: {}", input.name, failure.message);
+                match failure.status {
+                    Status::Parse => failed += 1,
+                    Status::Io => unreadable += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+    let mut summary = format!(
+        "{} file{} scanned, {} with matches, {} failed to parse",
+        scanned,
+        if scanned == 1 { "" } else { "s" },
+        matched,
+        failed
+    );
+    if unreadable > 0 {
+        summary.push_str(&format!(", {} could not be read", unreadable));
+    }
+    eprintln!("{}", summary);
+    Ok(if failed > 0 {
+        Status::Parse
+    } else if unreadable > 0 {
+        Status::Io
+    } else if matched > 0 {
+        Status::Success
+    } else {
+        Status::NoMatch
+    })
+}
+
+/// Flushes the results; `--exit-status` only applies when every input
+/// succeeded.
+fn finish(status: Status, output: Output) -> Result<Status, Failure> {
+    let result_status = output.finish()?;
+    Ok(if status == Status::Success { result_status } else { status })
+}
+
+/// Describes every string equal to `search_value`, ignoring case, as
+/// `location : value`, with colors only on a terminal.
+fn search(content: &str, search_value: &str, mode: ParseMode, jobs: usize) -> Result<Vec<String>, Failure> {
+    let color = io::stdout().is_terminal();
+    let show = |value: &JsonValue| if color { value.to_string() } else { value.to_json_string() };
+    let Some(layout) = detect_layout(content, mode) else {
+        let json_value = borrowed::parse(content, mode).map_err(parse_error)?;
+        let hits = json_value
+            .find_strings(search_value)
+            .into_iter()
+            .map(|(path, index, s)| {
+                let value = show(&JsonValue::String(s.to_string()));
+                match index {
+                    Some(index) => format!("{} at index {} : {}", path, index, value),
+                    None => format!("{} : {}", path, value),
+                }
+            })
+            .collect();
+        return Ok(hits);
+    };
+    let matches = search_records(content, layout, search_value, mode, jobs).map_err(parse_error)?;
+    let hits = matches
+        .into_iter()
+        .map(|found| {
+            let location = match (layout, found.path.is_empty()) {
+                (Layout::Lines, true) => format!("line {}", found.record),
+                (Layout::Lines, false) => format!("line {}: {}", found.record, found.path),
+                // Element paths are those of a search of the whole array.
+                (Layout::Array, _) => found.path,
+            };
+            let value = show(&JsonValue::String(found.value));
+            match found.index {
+                Some(index) => format!("{} at index {} : {}", location, index, value),
+                None => format!("{} : {}", location, value),
+            }
+        })
+        .collect();
+    Ok(hits)
+}
+
+fn run(command: Command) -> Result<Status, Failure> {
+    match command {
+        Command::Get(inputs, key, index, options, mode) => {
+            let segments = key.as_deref().map(parse_path).transpose()?;
+            let mut output = Output::new(options);
+            let status = for_each_input(inputs, |file, content| {
+                let json_value = borrowed::parse(content, mode).map_err(parse_error)?;
+                let mut value = &json_value;
+                if let Some(segments) = &segments {
+                    value = json_value.get_path(segments).ok_or_else(|| {
+                        Failure::no_match(format!("Key '{}' not found.", key.as_deref().unwrap_or_default()))
+                    })?;
+                }
+                if let Some(index) = index {
+                    value = index.checked_sub(1).and_then(|i| value.get_index(i)).ok_or_else(|| {
+                        Failure::no_match(format!(
+                            "Element not found in '{}' at index {}.",
+                            key.as_deref().unwrap_or_default(),
+                            index
+                        ))
+                    })?;
+                }
+                output.result(file, None, &value.clone().into_owned())?;
+                Ok(Status::Success)
+            })?;
+            finish(status, output)
+        }
+        Command::Search(inputs, search_value, mode, jobs) => for_each_input(inputs, |file, content| {
+            let hits = search(content, &search_value, mode, jobs)?;
+            // With several files, those without hits stay silent, as in grep.
+            if file.is_none() || !hits.is_empty() {
+                let prefix = file.map(|file| format!("{}:", file)).unwrap_or_default();
+                println!("{}Found {} occurrences of '{}':", prefix, hits.len(), search_value);
+                for hit in &hits {
+                    println!("{} - {}", prefix, hit);
+                }
+            }
+            Ok(if hits.is_empty() { Status::NoMatch } else { Status::Success })
+        }),
+        Command::Query(inputs, path, show_paths, options, mode) => {
+            let segments = parse_path(&path)?;
+            let mut output = Output::new(options);
+            let status = for_each_input(inputs, |file, content| {
+                let json_value = JsonParser::with_mode(content, mode).parse().map_err(parse_error)?;
+                let matches = query(&json_value, &segments);
+                if matches.is_empty() {
+                    return Err(Failure::no_match(format!("No value matches '{}'.", path)));
+                }
+                for (path, value) in &matches {
+                    let label = show_paths.then(|| format_path(path));
+                    output.result(file, label.as_deref(), value)?;
+                }
+                Ok(Status::Success)
+            })?;
+            finish(status, output)
+        }
+        Command::Format(inputs, settings, output, mode) => run_format(inputs, &settings, output, mode),
+        Command::Minify(inputs, write, mode) => run_minify(inputs, write, mode),
+        Command::Stats(content, mode) => {
+            let document = Document::parse(&content, mode).map_err(parse_error)?;
+            let stats = document.stats();
+            println!("Nodes:   {}", stats.nodes);
+            println!("Tape:    {} bytes", stats.tape_bytes);
+            println!("Strings: {} bytes", stats.string_bytes);
+            println!(
+                "Total:   {} bytes ({:.2}x the input)",
+                stats.total_bytes(),
+                stats.total_bytes() as f64 / content.len().max(1) as f64
+            );
+            Ok(Status::Success)
+        }
+        Command::Edit(file, action, output, mode) => {
+            run_edit(&file, &action, output, mode)?;
+            Ok(Status::Success)
+        }
+        Command::Diff(old, new, options, format, mode) => {
+            let old = JsonParser::with_mode(&old, mode).parse().map_err(parse_error)?;
+            let new = JsonParser::with_mode(&new, mode).parse().map_err(parse_error)?;
+            let entries = diff(&old, &new, &options);
+            match format {
+                OutputFormat::Text => print!("{}", format_diff_text(&entries, io::stdout().is_terminal())),
+                OutputFormat::Json => println!("{}", diff_to_json(&entries).to_json_string()),
+            }
+            Ok(Status::Success)
+        }
+        Command::Patch(file, patch, output, mode) => {
+            run_patch(&file, &patch, output, mode)?;
+            Ok(Status::Success)
+        }
+        Command::MakePatch(old, new, mode) => {
+            let old = JsonParser::with_mode(&old, mode).parse().map_err(parse_error)?;
+            let new = JsonParser::with_mode(&new, mode).parse().map_err(parse_error)?;
+            println!("{}", diff_to_patch(&old, &new).to_pretty_string());
+            Ok(Status::Success)
+        }
+        Command::Merge(files, options, show_origins, show_conflicts, mode) => {
+            let documents = parse_files(&files, mode)?;
+            let merged = deep_merge(&documents, &options);
+            let file_name = |source: Option<usize>| {
+                source.map_or("?", |s| files[s].0.as_str())
+            };
+            if show_origins {
+                for (path, value, source) in merged.leaf_origins() {
+                    println!("{} = {}  <- {}", format_path(&path), value.to_json_string(), file_name(source));
+                }
+            } else {
+                println!("{}", merged.value.to_pretty_string());
+            }
+            if show_conflicts {
+                eprintln!("{} conflicts", merged.conflicts.len());
+                for conflict in &merged.conflicts {
+                    eprintln!(
+                        " - {}: {} from {} overridden by {} from {}",
+                        format_path(&conflict.path),
+                        conflict.previous.to_json_string(),
+                        file_name(conflict.previous_source),
+                        conflict.value.to_json_string(),
+                        file_name(Some(conflict.source))
+                    );
+                }
+            }
+            Ok(Status::Success)
+        }
+        Command::Validate(schema, files, format, mode) => {
+            let schema = JsonParser::with_mode(&schema, mode)
+                .parse()
+                .map_err(|e| format!("Error parsing schema: {}", e))
+                .and_then(JsonSchema::new)
+                .map_err(Failure::parse)?;
+            // Unparsable files outrank invalid ones in the exit status.
+            let mut status = Status::Success;
+            let mut report = Vec::new();
+            for (name, content) in &files {
+                match JsonParser::with_mode(content, mode).parse() {
+                    Ok(instance) => {
+                        let errors = schema.validate(&instance);
+                        if !errors.is_empty() && status == Status::Success {
+                            status = Status::NoMatch;
                         }
-                    }
-                    Err(e) => eprintln!("Error parsing JSON: {}", e),
-                }
-            }
-            Command::Search(content, search_value) => {
-                let mut parser = JsonParser::new(&content);
-                match parser.parse() {
-                    Ok(json_value) => {
-                        let target_value = JsonValue::String(search_value.clone());
-                        let (count, keys, indexes) = json_value.get_all_occurrences(&target_value);
-
-                        println!("Found {} occurrences of '{}':", count, search_value);
-                        for (i, key) in keys.iter().enumerate() {
-                            match json_value.get(&key) {
-                                Some(value) => {
-                                    if let Some(index) = indexes[i] {
-                                            println!(" - {} at index {} : {}", key, index, value);
-                                    } else {
-                                        println!(" - {} : {}", key, value);
-                                    }
+                        if format == OutputFormat::Text {
+                            if errors.is_empty() {
+                                println!("{}: valid", name);
+                            } else {
+                                println!("{}: {} errors", name, errors.len());
+                                for error in &errors {
+                                    println!(" - {}", error);
                                 }
-                                None => println!(" - {}: <not found>", key),
                             }
                         }
+                        let errors: Vec<JsonValue> = errors.iter().map(|e| e.to_json()).collect();
+                        report.push(JsonValue::Object(vec![
+                            ("file".to_string(), JsonValue::String(name.clone())),
+                            ("valid".to_string(), JsonValue::Boolean(errors.is_empty())),
+                            ("errors".to_string(), JsonValue::Array(errors)),
+                        ].into()));
+                    }
+                    Err(e) => {
+                        eprintln!("Error parsing JSON in {}: {}", name, e);
+                        status = Status::Parse;
                     }
-                    Err(e) => eprintln!("Error parsing JSON: {}", e),
                 }
             }
-        },
-        Err(e) => eprintln!("{}", e),
+            if format == OutputFormat::Json {
+                println!("{}", JsonValue::Array(report).to_json_string());
+            }
+            Ok(status)
+        }
+        Command::InferSchema(files, mode) => {
+            let samples = parse_files(&files, mode)?;
+            println!("{}", infer_schema(&samples).to_pretty_string());
+            Ok(Status::Success)
+        }
+        Command::Codegen(files, language, name, mode) => {
+            let samples = parse_files(&files, mode)?;
+            print!("{}", generate_types(&samples, &name, language)?);
+            Ok(Status::Success)
+        }
+        Command::Help(help) => {
+            print!("{}", help);
+            Ok(Status::Success)
+        }
+        Command::Version => {
+            println!("json_parser {}", env!("CARGO_PKG_VERSION"));
+            Ok(Status::Success)
+        }
     }
 }
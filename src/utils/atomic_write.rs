@@ -0,0 +1,36 @@
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::process;
+
+    /// Replaces the content of `path` without ever leaving it half written.
+    ///
+    /// The new content goes to a temporary file in the same directory, which
+    /// is then renamed over the original. The original permissions are kept.
+    pub fn write_atomic(path: &str, content: &str) -> Result<(), String> {
+        let target = Path::new(path);
+        let directory = match target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let file_name = target
+            .file_name()
+            .ok_or_else(|| format!("Invalid file path {}", path))?
+            .to_string_lossy();
+        let temp_path = directory.join(format!(".{}.{}.tmp", file_name, process::id()));
+
+        let result = (|| {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            if let Ok(metadata) = fs::metadata(target) {
+                fs::set_permissions(&temp_path, metadata.permissions())?;
+            }
+            fs::rename(&temp_path, target)
+        })();
+
+        result.map_err(|err| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Error writing file {}: {}", path, err)
+        })
+    }
@@ -0,0 +1,218 @@
+    /// An option accepted by a command.
+    pub struct Opt {
+        pub long: &'static str,
+        pub short: Option<char>,
+        /// Name of the value shown in the help, or `None` for a flag.
+        pub value: Option<&'static str>,
+        pub help: &'static str,
+    }
+
+    /// A positional argument of a command.
+    pub struct Arg {
+        pub name: &'static str,
+        pub help: &'static str,
+        pub required: bool,
+        /// Takes every remaining argument; only the last one can.
+        pub multiple: bool,
+    }
+
+    /// Everything a command accepts, from which its help and usage are built.
+    pub struct Spec {
+        /// The subcommand, or `""` for the command without one.
+        pub name: &'static str,
+        pub about: &'static str,
+        pub arguments: &'static [Arg],
+        pub options: &'static [Opt],
+        pub examples: &'static [&'static str],
+    }
+
+    pub const HELP: Opt = Opt { long: "help", short: Some('h'), value: None, help: "Print this help" };
+
+    /// The options and arguments given to a command.
+    pub struct Matches {
+        options: Vec<(&'static str, Option<String>)>,
+        pub positionals: Vec<String>,
+    }
+
+    impl Matches {
+        pub fn flag(&self, long: &str) -> bool {
+            self.options.iter().any(|(name, _)| *name == long)
+        }
+
+        /// The last value given for an option.
+        pub fn value(&self, long: &str) -> Option<&str> {
+            self.options.iter().rev().find(|(name, _)| *name == long).and_then(|(_, value)| value.as_deref())
+        }
+
+        /// Every value given for an option, in order.
+        pub fn values(&self, long: &str) -> Vec<&str> {
+            self.options.iter().filter(|(name, _)| *name == long).filter_map(|(_, value)| value.as_deref()).collect()
+        }
+
+        /// A positional argument by its position among the positionals.
+        pub fn positional(&self, index: usize) -> Option<&str> {
+            self.positionals.get(index).map(String::as_str)
+        }
+    }
+
+    impl Spec {
+        fn command(&self) -> String {
+            if self.name.is_empty() {
+                "json_parser".to_string()
+            } else {
+                format!("json_parser {}", self.name)
+            }
+        }
+
+        pub fn usage(&self) -> String {
+            let mut usage = format!("Usage: {} [OPTIONS]", self.command());
+            for arg in self.arguments {
+                let dots = if arg.multiple { "..." } else { "" };
+                if arg.required {
+                    usage.push_str(&format!(" <{}>{}", arg.name, dots));
+                } else {
+                    usage.push_str(&format!(" [{}]{}", arg.name, dots));
+                }
+            }
+            usage
+        }
+
+        /// Builds an error that ends with the usage line and a pointer to
+        /// the help, as every argument error does.
+        pub fn error(&self, message: &str) -> String {
+            format!("{}\n{}\nSee '{} --help' for more information.", message, self.usage(), self.command())
+        }
+
+        pub fn help(&self) -> String {
+            let mut help = format!("{} - {}\n\n{}\n", self.command(), self.about, self.usage());
+            if !self.arguments.is_empty() {
+                let rows: Vec<(String, &str)> = self
+                    .arguments
+                    .iter()
+                    .map(|arg| {
+                        let name = if arg.required { format!("<{}>", arg.name) } else { format!("[{}]", arg.name) };
+                        (name, arg.help)
+                    })
+                    .collect();
+                help.push_str("\nArguments:\n");
+                help.push_str(&table(&rows));
+            }
+            let rows: Vec<(String, &str)> = self
+                .options
+                .iter()
+                .chain([&HELP])
+                .map(|opt| {
+                    let mut name = match opt.short {
+                        Some(short) => format!("-{}, --{}", short, opt.long),
+                        None => format!("    --{}", opt.long),
+                    };
+                    if let Some(value) = opt.value {
+                        name.push_str(&format!(" <{}>", value));
+                    }
+                    (name, opt.help)
+                })
+                .collect();
+            help.push_str("\nOptions:\n");
+            help.push_str(&table(&rows));
+            if !self.examples.is_empty() {
+                help.push_str("\nExamples:\n");
+                for example in self.examples {
+                    help.push_str(&format!("  {}\n", example));
+                }
+            }
+            help
+        }
+
+        /// Parses the arguments that follow the command name.
+        ///
+        /// Long options take their value as `--name value` or `--name=value`,
+        /// short ones as `-n value` or `-nvalue`, and short flags can be
+        /// grouped (`-in`). Everything after `--` is positional, as are `-`
+        /// and negative numbers. Returns `None` when help was requested.
+        pub fn parse(&self, args: &[String]) -> Result<Option<Matches>, String> {
+            let mut matches = Matches { options: Vec::new(), positionals: Vec::new() };
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                if arg == "--" {
+                    matches.positionals.extend(args.by_ref().cloned());
+                    break;
+                }
+                if let Some(long) = arg.strip_prefix("--") {
+                    let (name, inline) = match long.split_once('=') {
+                        Some((name, value)) => (name, Some(value.to_string())),
+                        None => (long, None),
+                    };
+                    let opt = self
+                        .find(|opt| opt.long == name)
+                        .ok_or_else(|| self.error(&format!("Unknown option '--{}'", name)))?;
+                    let value = match (opt.value, inline) {
+                        (Some(_), Some(value)) => Some(value),
+                        (Some(_), None) => Some(self.take_value(&mut args, &format!("--{}", name))?),
+                        (None, Some(_)) => return Err(self.error(&format!("Option '--{}' does not take a value", name))),
+                        (None, None) => None,
+                    };
+                    matches.options.push((opt.long, value));
+                } else if is_short_options(arg) {
+                    let shorts = &arg[1..];
+                    for (i, short) in shorts.char_indices() {
+                        let opt = self
+                            .find(|opt| opt.short == Some(short))
+                            .ok_or_else(|| self.error(&format!("Unknown option '-{}'", short)))?;
+                        if opt.value.is_none() {
+                            matches.options.push((opt.long, None));
+                            continue;
+                        }
+                        let rest = &shorts[i + short.len_utf8()..];
+                        let value = if rest.is_empty() {
+                            self.take_value(&mut args, &format!("-{}", short))?
+                        } else {
+                            rest.to_string()
+                        };
+                        matches.options.push((opt.long, Some(value)));
+                        break;
+                    }
+                } else {
+                    matches.positionals.push(arg.clone());
+                }
+            }
+            if matches.flag(HELP.long) {
+                return Ok(None);
+            }
+            self.check_positionals(&matches.positionals)?;
+            Ok(Some(matches))
+        }
+
+        fn find(&self, predicate: impl Fn(&Opt) -> bool) -> Option<&Opt> {
+            self.options.iter().chain([&HELP]).find(|opt| predicate(opt))
+        }
+
+        fn take_value<'a>(&self, args: &mut impl Iterator<Item = &'a String>, name: &str) -> Result<String, String> {
+            args.next()
+                .cloned()
+                .ok_or_else(|| self.error(&format!("Option '{}' requires a value", name)))
+        }
+
+        fn check_positionals(&self, positionals: &[String]) -> Result<(), String> {
+            if let Some(missing) = self.arguments.iter().filter(|arg| arg.required).nth(positionals.len()) {
+                return Err(self.error(&format!("Missing argument <{}>", missing.name)));
+            }
+            let multiple = self.arguments.last().is_some_and(|arg| arg.multiple);
+            if positionals.len() > self.arguments.len() && !multiple {
+                let extra = &positionals[self.arguments.len()];
+                return Err(self.error(&format!("Unexpected argument '{}'", extra)));
+            }
+            Ok(())
+        }
+    }
+
+    fn is_short_options(arg: &str) -> bool {
+        let mut chars = arg.chars();
+        chars.next() == Some('-') && chars.next().is_some_and(|c| !c.is_ascii_digit() && c != '.')
+    }
+
+    fn table(rows: &[(String, &str)]) -> String {
+        let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(name, help)| format!("  {:<width$}  {}\n", name, help, width = width))
+            .collect()
+    }
@@ -0,0 +1,56 @@
+    use crate::libs::cst::CstDocument;
+    use crate::libs::json_parser::{JsonParser, JsonValue, ParseMode};
+    use crate::utils::atomic_write::write_atomic;
+    use crate::utils::exit::Failure;
+    use crate::utils::handle_arguments::{read_path, EditAction, EditOutput};
+    use crate::utils::text_diff::unified_diff;
+
+    /// Applies one edit to `file` through the concrete syntax tree, so that
+    /// comments and formatting outside the edited value are left untouched.
+    ///
+    /// An edit that does not fit the document, such as a missing path, is a
+    /// `NoMatch` failure.
+    pub fn run_edit(file: &str, action: &EditAction, output: EditOutput, mode: ParseMode) -> Result<(), Failure> {
+        let content = read_path(file)?;
+        let mut document = CstDocument::parse(&content, mode)
+            .map_err(|err| Failure::parse(format!("Error parsing JSON: {}", err)))?;
+
+        match action {
+            EditAction::Set(path, value) => document.set(path, value),
+            EditAction::Delete(path) => document.remove(path),
+            EditAction::Insert(path, value) => document.insert(path, value),
+            EditAction::Append(path, value) => document.append(path, value),
+            EditAction::Rename(path, new_key) => document.rename(path, new_key),
+        }
+        .map_err(Failure::no_match)?;
+
+        write_output(file, &content, &document.to_string(), output)
+    }
+
+    /// Applies a patch document to `file`: an array is a JSON Patch and an
+    /// object a JSON Merge Patch. The patched document is printed with the
+    /// standard layout.
+    pub fn run_patch(file: &str, patch: &str, output: EditOutput, mode: ParseMode) -> Result<(), Failure> {
+        let content = read_path(file)?;
+        let mut value = JsonParser::with_mode(&content, mode)
+            .parse()
+            .map_err(|err| Failure::parse(format!("Error parsing JSON: {}", err)))?;
+        let patch = JsonParser::with_mode(patch, mode)
+            .parse()
+            .map_err(|err| Failure::parse(format!("Error parsing patch: {}", err)))?;
+        if let JsonValue::Object(_) = patch {
+            value.merge_patch(&patch);
+        } else {
+            value.apply_patch(&patch).map_err(Failure::no_match)?;
+        }
+        write_output(file, &content, &format!("{}\n", value.to_pretty_string()), output)
+    }
+
+    fn write_output(file: &str, content: &str, edited: &str, output: EditOutput) -> Result<(), Failure> {
+        match output {
+            EditOutput::Stdout => print!("{}", edited),
+            EditOutput::InPlace => write_atomic(file, edited).map_err(Failure::io)?,
+            EditOutput::DryRun => print!("{}", unified_diff(content, edited, file, file)),
+        }
+        Ok(())
+    }
@@ -0,0 +1,55 @@
+    use std::process::ExitCode;
+
+    /// How a command ended, as scripts see it in its exit code.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Status {
+        Success = 0,
+        /// Nothing matched: a missing key or path, a search without results,
+        /// a document that fails validation, or a null or false result with
+        /// `--exit-status`.
+        NoMatch = 1,
+        /// The arguments are invalid.
+        Usage = 2,
+        /// The input, or JSON text given as an argument, is not valid.
+        Parse = 3,
+        /// A file or standard input could not be read or written.
+        Io = 4,
+    }
+
+    impl From<Status> for ExitCode {
+        fn from(status: Status) -> Self {
+            ExitCode::from(status as u8)
+        }
+    }
+
+    /// A command that failed: the diagnostic for stderr and the exit status.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Failure {
+        pub status: Status,
+        pub message: String,
+    }
+
+    impl Failure {
+        pub fn new(status: Status, message: impl Into<String>) -> Self {
+            Self { status, message: message.into() }
+        }
+
+        pub fn no_match(message: impl Into<String>) -> Self {
+            Self::new(Status::NoMatch, message)
+        }
+
+        pub fn parse(message: impl Into<String>) -> Self {
+            Self::new(Status::Parse, message)
+        }
+
+        pub fn io(message: impl Into<String>) -> Self {
+            Self::new(Status::Io, message)
+        }
+    }
+
+    /// Errors raised while reading the arguments are usage errors.
+    impl From<String> for Failure {
+        fn from(message: String) -> Self {
+            Self::new(Status::Usage, message)
+        }
+    }
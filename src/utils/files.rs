@@ -0,0 +1,157 @@
+    use std::fs;
+    use std::path::Path;
+
+    use crate::utils::exit::Failure;
+    use crate::utils::handle_arguments::read_path;
+
+    /// Files picked from a directory when no `--include` is given.
+    pub const DEFAULT_INCLUDE: &[&str] = &["*.json", "*.jsonc", "*.json5", "*.jsonl", "*.ndjson"];
+
+    /// A document given to a command, named as in its messages.
+    pub struct Input {
+        pub name: String,
+        pub content: Result<String, Failure>,
+    }
+
+    /// The documents a command reads. With several of them, or a directory,
+    /// every line of output and every error starts with the file name.
+    pub struct Inputs {
+        pub files: Vec<Input>,
+        pub with_names: bool,
+    }
+
+    impl Inputs {
+        pub fn single(name: &str, content: String) -> Self {
+            Self { files: vec![Input { name: name.to_string(), content: Ok(content) }], with_names: false }
+        }
+    }
+
+    /// Which files to take from a directory, by glob patterns on their names.
+    /// Excluded names also prune whole directories.
+    pub struct Filter {
+        pub include: Vec<String>,
+        pub exclude: Vec<String>,
+    }
+
+    impl Filter {
+        fn includes(&self, name: &str) -> bool {
+            if self.include.is_empty() {
+                DEFAULT_INCLUDE.iter().any(|pattern| glob_match(pattern, name))
+            } else {
+                self.include.iter().any(|pattern| glob_match(pattern, name))
+            }
+        }
+
+        fn excludes(&self, name: &str) -> bool {
+            self.exclude.iter().any(|pattern| glob_match(pattern, name))
+        }
+    }
+
+    /// Matches a name against a glob: `*` is any run of characters, `?` any
+    /// one character, and `[...]` one of a set such as `[a-z]` (`[!...]`
+    /// negates it).
+    pub fn glob_match(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        let (mut p, mut n) = (0, 0);
+        // Where to resume after the last `*` when the rest fails to match.
+        let mut backtrack: Option<(usize, usize)> = None;
+        while n < name.len() {
+            let step = match pattern.get(p) {
+                Some('*') => {
+                    backtrack = Some((p, n));
+                    p += 1;
+                    continue;
+                }
+                Some('?') => Some(p + 1),
+                Some('[') => match_class(&pattern, p, name[n]),
+                Some(&c) if c == name[n] => Some(p + 1),
+                _ => None,
+            };
+            match (step, backtrack) {
+                (Some(next), _) => {
+                    p = next;
+                    n += 1;
+                }
+                (None, Some((star, start))) => {
+                    p = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                (None, None) => return false,
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+
+    /// Matches `c` against the class opening at `pattern[start]`, returning
+    /// the position after it. An unclosed `[` is a literal.
+    fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+        let negated = matches!(pattern.get(start + 1), Some('!' | '^'));
+        let first = if negated { start + 2 } else { start + 1 };
+        // A `]` right after the opening bracket is part of the set.
+        let Some(end) = (first + 1..pattern.len()).find(|&i| pattern[i] == ']') else {
+            return (c == '[').then_some(start + 1);
+        };
+        let set = &pattern[first..end];
+        let mut found = false;
+        let mut i = 0;
+        while i < set.len() {
+            if i + 2 < set.len() && set[i + 1] == '-' {
+                found |= set[i] <= c && c <= set[i + 2];
+                i += 3;
+            } else {
+                found |= set[i] == c;
+                i += 1;
+            }
+        }
+        (found != negated).then_some(end + 1)
+    }
+
+    /// Adds the document at `path` to `inputs`, or with `recursive` every
+    /// file under it that the filter takes, in name order. Hidden entries,
+    /// whose names start with `.`, and symbolic links to directories are
+    /// skipped.
+    pub fn collect(path: &str, recursive: bool, filter: &Filter, inputs: &mut Vec<Input>) {
+        if !Path::new(path).is_dir() {
+            inputs.push(Input { name: path.to_string(), content: read_path(path) });
+        } else if !recursive {
+            inputs.push(Input {
+                name: path.to_string(),
+                content: Err(Failure::io(format!("{} is a directory (use --recursive to read the files in it)", path))),
+            });
+        } else {
+            walk(Path::new(path), filter, inputs);
+        }
+    }
+
+    fn walk(directory: &Path, filter: &Filter, inputs: &mut Vec<Input>) {
+        let entries = fs::read_dir(directory).and_then(|entries| entries.collect::<Result<Vec<_>, _>>());
+        let mut entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => {
+                let name = directory.display().to_string();
+                let content = Err(Failure::io(format!("Error reading directory {}: {}", name, err)));
+                inputs.push(Input { name, content });
+                return;
+            }
+        };
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || filter.excludes(&name) {
+                continue;
+            }
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => walk(&path, filter, inputs),
+                Ok(kind) if kind.is_symlink() && path.is_dir() => {}
+                _ if filter.includes(&name) => {
+                    let name = path.display().to_string();
+                    let content = read_path(&name);
+                    inputs.push(Input { name, content });
+                }
+                _ => {}
+            }
+        }
+    }
@@ -0,0 +1,178 @@
+    use std::path::{Path, PathBuf};
+
+    use crate::libs::cst::CstDocument;
+    use crate::libs::json_parser::{JsonParser, JsonValue, ParseMode};
+    use crate::libs::layout::format_document;
+    use crate::libs::serializer::SerializeOptions;
+    use crate::utils::atomic_write::write_atomic;
+    use crate::utils::exit::{Failure, Status};
+    use crate::utils::files::Inputs;
+    use crate::utils::handle_arguments::{read_path, FormatOutput};
+
+    /// The project config file, looked up from the current directory upwards.
+    pub const CONFIG_FILE: &str = ".jsonfmt.json";
+
+    /// How `fmt` lays out documents.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct FormatSettings {
+        pub options: SerializeOptions,
+        /// End the text with a newline.
+        pub final_newline: bool,
+    }
+
+    impl Default for FormatSettings {
+        fn default() -> Self {
+            Self { options: SerializeOptions::default(), final_newline: true }
+        }
+    }
+
+    impl FormatSettings {
+        /// Lays the document out with its comments, keeping each number's
+        /// lexeme where an `f64` would change it, so no content is lost.
+        pub fn format(&self, document: &CstDocument) -> String {
+            let mut text = format_document(document, &self.options, true);
+            if self.final_newline {
+                text.push('\n');
+            }
+            text
+        }
+
+        /// Applies the settings of a config file, an object with the same
+        /// names as the options of `fmt`:
+        ///
+        /// ```json
+        /// { "indent": 2, "sort-keys": true, "final-newline": true, "wrap-width": 80 }
+        /// ```
+        pub fn apply(&mut self, config: &JsonValue) -> Result<(), String> {
+            let JsonValue::Object(members) = config else {
+                return Err("The config must be an object".to_string());
+            };
+            for (name, value) in members.iter() {
+                let invalid = || format!("Invalid value {} for '{}'", value.to_json_string(), name);
+                match (name.as_str(), value) {
+                    ("indent", JsonValue::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => {
+                        self.options.indent = Some(" ".repeat(*n as usize));
+                    }
+                    ("compact", JsonValue::Boolean(compact)) => {
+                        if *compact {
+                            self.options.indent = None;
+                        }
+                    }
+                    ("sort-keys", JsonValue::Boolean(sort_keys)) => self.options.sort_keys = *sort_keys,
+                    ("final-newline", JsonValue::Boolean(final_newline)) => self.final_newline = *final_newline,
+                    ("wrap-width", JsonValue::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => {
+                        self.options.wrap_width = (*n > 0.0).then_some(*n as usize);
+                    }
+                    ("indent" | "compact" | "sort-keys" | "final-newline" | "wrap-width", _) => return Err(invalid()),
+                    _ => return Err(format!("Unknown setting '{}'", name)),
+                }
+            }
+            Ok(())
+        }
+
+        /// Reads a config file over these settings. It may contain comments.
+        pub fn load(&mut self, path: &str) -> Result<(), Failure> {
+            let content = read_path(path)?;
+            let config = JsonParser::with_mode(&content, ParseMode::Jsonc)
+                .parse()
+                .map_err(|err| Failure::parse(format!("Error parsing {}: {}", path, err)))?;
+            self.apply(&config).map_err(|err| Failure::from(format!("Error in {}: {}", path, err)))
+        }
+    }
+
+    /// Finds [`CONFIG_FILE`] in `directory` or the closest of its parents.
+    pub fn find_config(directory: &Path) -> Option<PathBuf> {
+        directory.ancestors().map(|dir| dir.join(CONFIG_FILE)).find(|path| path.is_file())
+    }
+
+    /// Parses every input, keeping its comments and lexemes, and hands it
+    /// to `each` with its name and text.
+    ///
+    /// A single input fails on its first error. With several, a file that
+    /// cannot be read or parsed is reported and the others still run; the
+    /// status is then the most serious one, parse errors first.
+    fn for_each_document(
+        inputs: Inputs,
+        mode: ParseMode,
+        mut each: impl FnMut(&str, &str, &CstDocument) -> Result<Status, Failure>,
+    ) -> Result<Status, Failure> {
+        let several = inputs.files.len() > 1;
+        let mut status = Status::Success;
+        for input in inputs.files {
+            let result = input.content.and_then(|content| {
+                let document = CstDocument::parse(&content, mode)
+                    .map_err(|err| Failure::parse(format!("Error parsing JSON: {}", err)))?;
+                Ok((content, document))
+            });
+            let next = match result {
+                Ok((content, document)) => each(&input.name, &content, &document)?,
+                Err(failure) if !several => return Err(failure),
+                Err(failure) => {
+                    eprintln!("{}: {}", input.name, failure.message);
+                    failure.status
+                }
+            };
+            if rank(next) > rank(status) {
+                status = next;
+            }
+        }
+        Ok(status)
+    }
+
+    /// Orders the statuses of a run over several files, least serious first.
+    fn rank(status: Status) -> u8 {
+        match status {
+            Status::Success => 0,
+            Status::NoMatch => 1,
+            Status::Io => 2,
+            Status::Parse => 3,
+            Status::Usage => 4,
+        }
+    }
+
+    /// Formats every input: to stdout, rewriting the files that change, or
+    /// listing those that are not formatted. With `Check`, an unformatted
+    /// file is a `NoMatch`.
+    pub fn run_format(inputs: Inputs, settings: &FormatSettings, output: FormatOutput, mode: ParseMode) -> Result<Status, Failure> {
+        for_each_document(inputs, mode, |name, content, document| {
+            let formatted = settings.format(document);
+            match output {
+                FormatOutput::Stdout => print!("{}", formatted),
+                FormatOutput::Write if formatted != content => write_atomic(name, &formatted).map_err(Failure::io)?,
+                FormatOutput::Check if formatted != content => {
+                    println!("{}", name);
+                    return Ok(Status::NoMatch);
+                }
+                FormatOutput::Write | FormatOutput::Check => {}
+            }
+            Ok(Status::Success)
+        })
+    }
+
+    /// Writes every input in its smallest encoding, to stdout or back to the
+    /// file, and reports the sizes before and after on stderr.
+    pub fn run_minify(inputs: Inputs, write: bool, mode: ParseMode) -> Result<Status, Failure> {
+        let several = inputs.files.len() > 1;
+        let (mut before, mut after) = (0, 0);
+        let status = for_each_document(inputs, mode, |name, content, document| {
+            let minified = format_document(document, &SerializeOptions::minified(), false);
+            if !write {
+                print!("{}", minified);
+            } else if minified != content {
+                write_atomic(name, &minified).map_err(Failure::io)?;
+            }
+            eprintln!("{}: {}", name, size_change(content.len(), minified.len()));
+            before += content.len();
+            after += minified.len();
+            Ok(Status::Success)
+        })?;
+        if several {
+            eprintln!("Total: {}", size_change(before, after));
+        }
+        Ok(status)
+    }
+
+    fn size_change(before: usize, after: usize) -> String {
+        let change = (after as f64 / before.max(1) as f64 - 1.0) * 100.0;
+        format!("{} -> {} bytes ({:+.1}%)", before, after, change)
+    }
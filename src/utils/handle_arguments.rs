@@ -1,95 +1,753 @@
     use std::env;
-    use std::fs;
-    use std::io::{self, Read};
-    use std::sync::mpsc;
-    use std::thread;
-    use std::time::Duration;
+    use std::io::{self, IsTerminal};
+    use std::path::Path;
+
+    use crate::libs::codegen::Language;
+    use crate::libs::diff::DiffOptions;
+    use crate::libs::input::{read_file, read_text, ReadError};
+    use crate::libs::json_parser::ParseMode;
+    use crate::libs::merge::MergeOptions;
+    use crate::libs::parallel::default_jobs;
+    use crate::libs::path::parse_path;
+    use crate::utils::cli::{Arg, Matches, Opt, Spec};
+    use crate::utils::exit::Failure;
+    use crate::utils::files::{collect, Filter, Input, Inputs};
+    use crate::utils::format::{find_config, FormatSettings};
+    use crate::utils::output::OutputOptions;
 
     pub enum Command{
-        Parse(String, Option<String>, Option<usize>),
-        Search(String, String),
-    }
-
-    fn read_stdin_with_timeout(timeout: Duration) -> Option<String> {
-        let (tx, rx) = mpsc::channel();
-        thread::spawn(move || {
-            let mut buffer = String::new();
-            let stdin = io::stdin();
-            let mut handle = stdin.lock();
-            if handle.read_to_string(&mut buffer).is_ok() {
-                let _ = tx.send(buffer);
-            }
-        });
-    
-        match rx.recv_timeout(timeout) {
-            Ok(input) => Some(input),
-            Err(_) => None,
-        }
-    }
-
-    pub fn get_arguments() -> Result<Command, String> {
-        let stdin_content = read_stdin_with_timeout(Duration::from_millis(100));
-    
-        let args: Vec<String> = env::args().collect();
-    
-        if let Some(input) = stdin_content {
-            if args.len() >= 2 && (args[1] == "-s" || args[1] == "--search") {
-                if args.len() < 3 {
-                    return Err("Search mode requires a value to search for.".to_string());
-                }
-                let search_value = args[2].clone();
-                return Ok(Command::Search(input, search_value));
+        Get(Inputs, Option<String>, Option<usize>, OutputOptions, ParseMode),
+        Search(Inputs, String, ParseMode, usize),
+        Query(Inputs, String, bool, OutputOptions, ParseMode),
+        Format(Inputs, FormatSettings, FormatOutput, ParseMode),
+        Minify(Inputs, bool, ParseMode),
+        Edit(String, EditAction, EditOutput, ParseMode),
+        Diff(String, String, DiffOptions, OutputFormat, ParseMode),
+        Patch(String, String, EditOutput, ParseMode),
+        MakePatch(String, String, ParseMode),
+        Merge(Vec<(String, String)>, MergeOptions, bool, bool, ParseMode),
+        Validate(String, Vec<(String, String)>, OutputFormat, ParseMode),
+        InferSchema(Vec<(String, String)>, ParseMode),
+        Codegen(Vec<(String, String)>, Language, String, ParseMode),
+        Stats(String, ParseMode),
+        Help(String),
+        Version,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum OutputFormat {
+        Text,
+        Json,
+    }
+
+    /// A change to a document, addressed by a path such as `users[0].name`.
+    pub enum EditAction {
+        Set(String, String),
+        Delete(String),
+        Insert(String, String),
+        Append(String, String),
+        Rename(String, String),
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum EditOutput {
+        Stdout,
+        InPlace,
+        DryRun,
+    }
+
+    /// What `fmt` does with the formatted text.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum FormatOutput {
+        Stdout,
+        /// Rewrite the files that are not formatted.
+        Write,
+        /// List the files that are not formatted.
+        Check,
+    }
+
+    const MODE: Opt = Opt {
+        long: "mode",
+        short: Some('m'),
+        value: Some("MODE"),
+        help: "Parse mode: strict, jsonc or json5 [default: strict]",
+    };
+    const JOBS: Opt = Opt {
+        long: "jobs",
+        short: None,
+        value: Some("N"),
+        help: "Threads for line-delimited input and large arrays [default: CPUs]",
+    };
+    const EXIT_STATUS: Opt = Opt {
+        long: "exit-status",
+        short: Some('e'),
+        value: None,
+        help: "Exit with status 1 when the result is null or false",
+    };
+    const RAW_OUTPUT: Opt = Opt {
+        long: "raw-output",
+        short: Some('r'),
+        value: None,
+        help: "Print strings without quotes or escapes",
+    };
+    const JOIN: Opt = Opt {
+        long: "join",
+        short: Some('j'),
+        value: None,
+        help: "Print results without newlines (implies --raw-output)",
+    };
+    const NULL_DATA: Opt = Opt {
+        long: "null-data",
+        short: None,
+        value: None,
+        help: "End each result with NUL, for xargs -0 (implies --raw-output)",
+    };
+    const RECURSIVE: Opt = Opt {
+        long: "recursive",
+        short: Some('R'),
+        value: None,
+        help: "Read the files in directories and their subdirectories",
+    };
+    const INCLUDE: Opt = Opt {
+        long: "include",
+        short: None,
+        value: Some("GLOB"),
+        help: "Only read directory entries named like GLOB [default: *.json and variants]",
+    };
+    const EXCLUDE: Opt = Opt {
+        long: "exclude",
+        short: None,
+        value: Some("GLOB"),
+        help: "Skip files and directories named like GLOB",
+    };
+    const WITH_FILENAME: Opt = Opt {
+        long: "with-filename",
+        short: Some('H'),
+        value: None,
+        help: "Start each output line with the file name, even for one file",
+    };
+    const FORMAT: Opt = Opt { long: "format", short: Some('f'), value: Some("FORMAT"), help: "Output format: text or json" };
+    const IN_PLACE: Opt = Opt { long: "in-place", short: Some('i'), value: None, help: "Write the result back to the file" };
+    const DRY_RUN: Opt = Opt { long: "dry-run", short: Some('n'), value: None, help: "Print a diff of the change instead" };
+
+    const FILE: Arg = Arg { name: "FILE", help: "Input file, or - for standard input", required: false, multiple: false };
+    const INPUTS: Arg = Arg {
+        name: "FILE",
+        help: "Input files or directories, or - for standard input",
+        required: false,
+        multiple: true,
+    };
+    const FILES: Arg = Arg { name: "FILE", help: "Input files", required: true, multiple: true };
+    const TARGET: Arg = Arg { name: "FILE", help: "File to edit", required: true, multiple: false };
+    const PATH: Arg = Arg { name: "PATH", help: "Path such as details.city or users[0].name", required: true, multiple: false };
+    const OLD: Arg = Arg { name: "OLD", help: "Original document", required: true, multiple: false };
+    const NEW: Arg = Arg { name: "NEW", help: "Changed document", required: true, multiple: false };
+    const VALUE: Arg = Arg { name: "VALUE", help: "New value, as JSON text", required: true, multiple: false };
+
+    const MAIN: Spec = Spec {
+        name: "",
+        about: "Parse, search and edit JSON documents",
+        arguments: &[
+            FILE,
+            Arg { name: "KEY", help: "Path of the value to print", required: false, multiple: false },
+            Arg { name: "INDEX", help: "Element of that value to print, from 1", required: false, multiple: false },
+        ],
+        options: &[
+            Opt { long: "search", short: Some('s'), value: Some("VALUE"), help: "Same as the search command" },
+            Opt { long: "stats", short: None, value: None, help: "Same as the stats command" },
+            RAW_OUTPUT,
+            JOIN,
+            NULL_DATA,
+            EXIT_STATUS,
+            JOBS,
+            MODE,
+            Opt { long: "version", short: Some('V'), value: None, help: "Print the version" },
+        ],
+        examples: &[
+            "json_parser data.json details.city 1",
+            "cat data.json | json_parser - grades",
+            "json_parser -s \"search_value\" data.json",
+            "json_parser get details.city data.json",
+            "json_parser set data.json details.city[0] '\"Lyon\"' --in-place",
+        ],
+    };
+
+    const GET: Spec = Spec {
+        name: "get",
+        about: "Print the value at a path",
+        arguments: &[PATH, INPUTS],
+        options: &[RAW_OUTPUT, JOIN, NULL_DATA, EXIT_STATUS, RECURSIVE, INCLUDE, EXCLUDE, WITH_FILENAME, MODE],
+        examples: &[
+            "json_parser get details.city data.json",
+            "cat data.json | json_parser get grades[1]",
+            "json_parser get -R service.port configs/",
+        ],
+    };
+
+    const SEARCH: Spec = Spec {
+        name: "search",
+        about: "Find the strings equal to a value, ignoring case",
+        arguments: &[Arg { name: "VALUE", help: "String to look for", required: true, multiple: false }, INPUTS],
+        options: &[RECURSIVE, INCLUDE, EXCLUDE, WITH_FILENAME, JOBS, MODE],
+        examples: &[
+            "json_parser search paris data.json",
+            "json_parser search admin events.ndjson --jobs 8",
+            "json_parser search -R --include '*.json' --exclude node_modules admin .",
+        ],
+    };
+
+    const QUERY: Spec = Spec {
+        name: "query",
+        about: "Print every value matched by a path, where * matches any member or element",
+        arguments: &[PATH, INPUTS],
+        options: &[
+            Opt { long: "paths", short: Some('p'), value: None, help: "Print the path of each value before it" },
+            RAW_OUTPUT,
+            JOIN,
+            NULL_DATA,
+            EXIT_STATUS,
+            RECURSIVE,
+            INCLUDE,
+            EXCLUDE,
+            WITH_FILENAME,
+            MODE,
+        ],
+        examples: &["json_parser query 'users[*].name' data.json", "json_parser query -p 'details.*' data.json"],
+    };
+
+    const FMT: Spec = Spec {
+        name: "fmt",
+        about: "Reformat documents",
+        arguments: &[INPUTS],
+        options: &[
+            Opt { long: "write", short: Some('w'), value: None, help: "Rewrite the files that are not formatted" },
+            Opt { long: "check", short: None, value: None, help: "List the files that are not formatted, and fail" },
+            Opt { long: "indent", short: None, value: Some("N"), help: "Spaces per level [default: 4]" },
+            Opt { long: "compact", short: Some('c'), value: None, help: "Write everything on one line" },
+            Opt { long: "sort-keys", short: Some('S'), value: None, help: "Sort object members by key" },
+            Opt {
+                long: "wrap-width",
+                short: None,
+                value: Some("N"),
+                help: "Keep arrays that fit in N columns on one line [default: 0, never]",
+            },
+            Opt { long: "no-final-newline", short: None, value: None, help: "Do not end the output with a newline" },
+            Opt { long: "config", short: None, value: Some("FILE"), help: "Read settings from FILE [default: .jsonfmt.json]" },
+            Opt { long: "no-config", short: None, value: None, help: "Ignore .jsonfmt.json" },
+            RECURSIVE,
+            INCLUDE,
+            EXCLUDE,
+            MODE,
+        ],
+        examples: &[
+            "json_parser fmt --indent 2 data.json",
+            "json_parser fmt -c -m jsonc tsconfig.json",
+            "json_parser fmt --check -R .",
+            "json_parser fmt --write config/*.json",
+        ],
+    };
+
+    const MINIFY: Spec = Spec {
+        name: "minify",
+        about: "Write documents in their smallest form and report the sizes",
+        arguments: &[INPUTS],
+        options: &[
+            Opt { long: "write", short: Some('w'), value: None, help: "Rewrite the files instead of printing them" },
+            RECURSIVE,
+            INCLUDE,
+            EXCLUDE,
+            MODE,
+        ],
+        examples: &["json_parser minify data.json > data.min.json", "json_parser minify --write -R assets/"],
+    };
+
+    const STATS: Spec = Spec {
+        name: "stats",
+        about: "Show the memory a parsed document takes",
+        arguments: &[FILE],
+        options: &[MODE],
+        examples: &["json_parser stats dump.json"],
+    };
+
+    const VALIDATE: Spec = Spec {
+        name: "validate",
+        about: "Validate documents against a JSON Schema",
+        arguments: &[FILES],
+        options: &[
+            Opt { long: "schema", short: Some('s'), value: Some("FILE"), help: "Schema to validate against (required)" },
+            FORMAT,
+            MODE,
+        ],
+        examples: &["json_parser validate --schema schema.json data.json"],
+    };
+
+    const DIFF: Spec = Spec {
+        name: "diff",
+        about: "Show the structural differences between two documents",
+        arguments: &[OLD, NEW],
+        options: &[
+            Opt { long: "arrays", short: None, value: Some("MODE"), help: "Compare arrays by index, lcs or key:<field>" },
+            Opt { long: "tolerance", short: None, value: Some("NUMBER"), help: "Treat closer numbers as equal" },
+            Opt { long: "ignore-case", short: Some('I'), value: None, help: "Compare strings ignoring case" },
+            FORMAT,
+            MODE,
+        ],
+        examples: &["json_parser diff old.json new.json --arrays key:id --format json"],
+    };
+
+    const PATCH: Spec = Spec {
+        name: "patch",
+        about: "Apply a JSON Patch or Merge Patch",
+        arguments: &[TARGET, Arg { name: "PATCH", help: "Patch document", required: true, multiple: false }],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser patch old.json changes.json --in-place"],
+    };
+
+    const MKPATCH: Spec = Spec {
+        name: "mkpatch",
+        about: "Write the JSON Patch that turns one document into another",
+        arguments: &[OLD, NEW],
+        options: &[MODE],
+        examples: &["json_parser mkpatch old.json new.json > changes.json"],
+    };
+
+    const MERGE: Spec = Spec {
+        name: "merge",
+        about: "Deep-merge documents, later ones taking precedence",
+        arguments: &[FILES],
+        options: &[
+            Opt {
+                long: "arrays",
+                short: None,
+                value: Some("MODE"),
+                help: "Merge arrays with replace, concat, union or key:<field>",
+            },
+            Opt { long: "null-deletes", short: None, value: None, help: "Remove members set to null" },
+            Opt { long: "origins", short: None, value: None, help: "Print the file each value comes from" },
+            Opt { long: "conflicts", short: None, value: None, help: "Report overridden values on stderr" },
+            MODE,
+        ],
+        examples: &["json_parser merge base.json env.json local.json --arrays union --origins"],
+    };
+
+    const INFER_SCHEMA: Spec = Spec {
+        name: "infer-schema",
+        about: "Draft a JSON Schema from sample documents",
+        arguments: &[FILES],
+        options: &[MODE],
+        examples: &["json_parser infer-schema samples/*.json > schema.json"],
+    };
+
+    const CODEGEN: Spec = Spec {
+        name: "codegen",
+        about: "Generate type definitions from sample documents",
+        arguments: &[FILES],
+        options: &[
+            Opt { long: "lang", short: Some('l'), value: Some("LANG"), help: "Target language [default: rust]" },
+            Opt { long: "name", short: None, value: Some("NAME"), help: "Name of the root type [default: Root]" },
+            MODE,
+        ],
+        examples: &["json_parser codegen --lang rust --name Config data.json"],
+    };
+
+    const SET: Spec = Spec {
+        name: "set",
+        about: "Set the value at a path, keeping the formatting",
+        arguments: &[TARGET, PATH, VALUE],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser set data.json details.city[0] '\"Lyon\"' --in-place"],
+    };
+
+    const DEL: Spec = Spec {
+        name: "del",
+        about: "Delete the value at a path, keeping the formatting",
+        arguments: &[TARGET, PATH],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser del data.json mail --dry-run"],
+    };
+
+    const INSERT: Spec = Spec {
+        name: "insert",
+        about: "Insert a value before an array element or as a new member",
+        arguments: &[TARGET, PATH, VALUE],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser insert data.json skills[1] '\"Go\"'"],
+    };
+
+    const APPEND: Spec = Spec {
+        name: "append",
+        about: "Append a value to an array",
+        arguments: &[TARGET, PATH, VALUE],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser append data.json skills '\"C\"'"],
+    };
+
+    const RENAME: Spec = Spec {
+        name: "rename",
+        about: "Rename an object member",
+        arguments: &[TARGET, PATH, Arg { name: "NEW_KEY", help: "New name of the member", required: true, multiple: false }],
+        options: &[IN_PLACE, DRY_RUN, MODE],
+        examples: &["json_parser rename data.json mail.email address"],
+    };
+
+    type Builder = fn(&Spec, &Matches, ParseMode) -> Result<Command, Failure>;
+
+    const COMMANDS: &[(Spec, Builder)] = &[
+        (GET, get_get_arguments),
+        (SEARCH, get_search_arguments),
+        (QUERY, get_query_arguments),
+        (FMT, get_fmt_arguments),
+        (MINIFY, get_minify_arguments),
+        (STATS, get_stats_arguments),
+        (VALIDATE, get_validate_arguments),
+        (DIFF, get_diff_arguments),
+        (PATCH, get_patch_arguments),
+        (MKPATCH, get_make_patch_arguments),
+        (MERGE, get_merge_arguments),
+        (INFER_SCHEMA, get_infer_schema_arguments),
+        (CODEGEN, get_codegen_arguments),
+        (SET, get_edit_arguments),
+        (DEL, get_edit_arguments),
+        (INSERT, get_edit_arguments),
+        (APPEND, get_edit_arguments),
+        (RENAME, get_edit_arguments),
+    ];
+
+    /// How standard input is named in multi-file output, as in grep.
+    const STDIN: &str = "(standard input)";
+
+    /// Turns a read error into an I/O or, for undecodable text, a parse failure.
+    fn read_failure(source: &str, err: ReadError) -> Failure {
+        let message = format!("Error reading {}: {}", source, err);
+        match err {
+            ReadError::Io(_) => Failure::io(message),
+            ReadError::Decode(_) => Failure::parse(message),
+        }
+    }
+
+    /// Reads standard input to the end, however long the producer takes.
+    fn read_stdin() -> Result<String, Failure> {
+        let input = read_text(io::stdin().lock()).map_err(|err| read_failure("standard input", err))?;
+        if input.trim().is_empty() {
+            return Err(Failure::io("No data on standard input."));
+        }
+        Ok(input)
+    }
+
+    /// Reads a file as text. Failing to read it is an I/O error; content
+    /// that is not valid text is a parse error.
+    pub(crate) fn read_path(path: &str) -> Result<String, Failure> {
+        read_file(path).map_err(|err| read_failure(&format!("file {}", path), err))
+    }
+
+    /// Reads a named input, where `-` is standard input.
+    fn read_input(path: &str) -> Result<String, Failure> {
+        if path == "-" {
+            read_stdin()
+        } else {
+            read_path(path)
+        }
+    }
+
+    /// Reads the optional input of a single-document command: the file
+    /// given, or standard input when it is piped.
+    fn read_document(spec: &Spec, path: Option<&str>) -> Result<String, Failure> {
+        match path {
+            Some(path) => read_input(path),
+            None if !io::stdin().is_terminal() => read_stdin(),
+            None => Err(spec.error("No input: give a file, or '-' to read standard input").into()),
+        }
+    }
+
+    /// Reads the documents of a command that takes several: the files
+    /// given, the files under directories with `--recursive`, or standard
+    /// input when no file is given. Files that cannot be read are kept, with
+    /// their error, so that the others still run.
+    fn read_documents(spec: &Spec, matches: &Matches, paths: &[String]) -> Result<Inputs, Failure> {
+        if paths.is_empty() {
+            return Ok(Inputs::single(STDIN, read_document(spec, None)?));
+        }
+        let recursive = matches.flag("recursive");
+        let filter = Filter {
+            include: matches.values("include").into_iter().map(String::from).collect(),
+            exclude: matches.values("exclude").into_iter().map(String::from).collect(),
+        };
+        let mut files = Vec::new();
+        for path in paths {
+            if path == "-" {
+                files.push(Input { name: STDIN.to_string(), content: read_stdin() });
             } else {
-                let key = if args.len() >= 2 { Some(args[1].clone()) } else { None };
-                let index = if args.len() >= 3 {
-                    args[2].parse::<usize>().ok()
-                } else {
-                    None
-                };
-                return Ok(Command::Parse(input, key, index));
+                collect(path, recursive, &filter, &mut files);
             }
         }
-    
-        if args.len() >= 2 {
-            if args[1] == "-s" || args[1] == "--search" {
-                if args.len() < 3 {
-                    return Err("Search mode requires a value to search for.".to_string());
-                }
-                let search_value = args[2].clone();
-                let file_path = if args.len() > 3 { Some(&args[3]) } else { None };
-                let content = if let Some(path) = file_path {
-                    fs::read_to_string(path)
-                        .map_err(|err| format!("Error reading file {}: {}", path, err))?
-                } else {
-                    return Err("No file provided for search mode, and no data found on stdin.".to_string());
-                };
-                return Ok(Command::Search(content, search_value));
+        let with_names = matches.flag("with-filename")
+            || paths.len() > 1
+            || recursive && paths.iter().any(|path| Path::new(path).is_dir());
+        Ok(Inputs { files, with_names })
+    }
+
+    fn read_inputs(paths: &[String]) -> Result<Vec<(String, String)>, Failure> {
+        paths.iter().map(|path| Ok((path.clone(), read_input(path)?))).collect()
+    }
+
+    fn get_mode(spec: &Spec, matches: &Matches) -> Result<ParseMode, String> {
+        matches.value("mode").map_or(Ok(ParseMode::Strict), |mode| mode.parse().map_err(|e: String| spec.error(&e)))
+    }
+
+    fn get_jobs(spec: &Spec, matches: &Matches) -> Result<usize, String> {
+        match matches.value("jobs") {
+            Some(jobs) => match jobs.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(spec.error(&format!("Invalid --jobs value '{}' (expected a positive number)", jobs))),
+            },
+            None => Ok(default_jobs()),
+        }
+    }
+
+    fn get_format(spec: &Spec, matches: &Matches) -> Result<OutputFormat, String> {
+        match matches.value("format") {
+            None | Some("text") => Ok(OutputFormat::Text),
+            Some("json") => Ok(OutputFormat::Json),
+            Some(other) => Err(spec.error(&format!("Unknown format '{}' (expected text or json)", other))),
+        }
+    }
+
+    fn get_output_options(matches: &Matches) -> OutputOptions {
+        OutputOptions {
+            raw: matches.flag("raw-output"),
+            join: matches.flag("join"),
+            null_data: matches.flag("null-data"),
+            exit_status: matches.flag("exit-status"),
+        }
+    }
+
+    fn get_edit_output(spec: &Spec, matches: &Matches) -> Result<EditOutput, String> {
+        match (matches.flag("dry-run"), matches.flag("in-place")) {
+            (true, true) => Err(spec.error("--dry-run and --in-place cannot be used together")),
+            (true, false) => Ok(EditOutput::DryRun),
+            (false, true) => Ok(EditOutput::InPlace),
+            (false, false) => Ok(EditOutput::Stdout),
+        }
+    }
+
+    fn get_get_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let inputs = read_documents(spec, matches, &matches.positionals[1..])?;
+        let path = matches.positionals[0].clone();
+        Ok(Command::Get(inputs, Some(path), None, get_output_options(matches), mode))
+    }
+
+    fn get_search_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let jobs = get_jobs(spec, matches)?;
+        let inputs = read_documents(spec, matches, &matches.positionals[1..])?;
+        Ok(Command::Search(inputs, matches.positionals[0].clone(), mode, jobs))
+    }
+
+    fn get_query_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let inputs = read_documents(spec, matches, &matches.positionals[1..])?;
+        let path = matches.positionals[0].clone();
+        Ok(Command::Query(inputs, path, matches.flag("paths"), get_output_options(matches), mode))
+    }
+
+    /// Settings come from the defaults, then the config file, then the
+    /// options given.
+    fn get_fmt_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let output = match (matches.flag("write"), matches.flag("check")) {
+            (true, true) => return Err(spec.error("--write and --check cannot be used together").into()),
+            (true, false) => FormatOutput::Write,
+            (false, true) => FormatOutput::Check,
+            (false, false) => FormatOutput::Stdout,
+        };
+        let paths = &matches.positionals;
+        if output != FormatOutput::Stdout && (paths.is_empty() || paths.iter().any(|path| path == "-")) {
+            return Err(spec.error("--write and --check need files, not standard input").into());
+        }
+        if output == FormatOutput::Stdout && (paths.len() > 1 || matches.flag("recursive")) {
+            return Err(spec.error("Formatting several files needs --write or --check").into());
+        }
+
+        let mut settings = FormatSettings::default();
+        let config = match matches.value("config") {
+            Some(path) => Some(path.into()),
+            None if matches.flag("no-config") => None,
+            None => env::current_dir().ok().and_then(|directory| find_config(&directory)),
+        };
+        if let Some(config) = config {
+            settings.load(&config.to_string_lossy())?;
+        }
+        if let Some(indent) = matches.value("indent") {
+            let indent: usize = indent.parse().map_err(|_| spec.error(&format!("Invalid --indent value '{}'", indent)))?;
+            settings.options.indent = Some(" ".repeat(indent));
+        }
+        if matches.flag("compact") {
+            settings.options.indent = None;
+        }
+        if matches.flag("sort-keys") {
+            settings.options.sort_keys = true;
+        }
+        if let Some(width) = matches.value("wrap-width") {
+            let width: usize =
+                width.parse().map_err(|_| spec.error(&format!("Invalid --wrap-width value '{}'", width)))?;
+            settings.options.wrap_width = (width > 0).then_some(width);
+        }
+        if matches.flag("no-final-newline") {
+            settings.final_newline = false;
+        }
+        Ok(Command::Format(read_documents(spec, matches, paths)?, settings, output, mode))
+    }
+
+    fn get_minify_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let write = matches.flag("write");
+        let paths = &matches.positionals;
+        if write && (paths.is_empty() || paths.iter().any(|path| path == "-")) {
+            return Err(spec.error("--write needs files, not standard input").into());
+        }
+        if !write && (paths.len() > 1 || matches.flag("recursive")) {
+            return Err(spec.error("Minifying several files needs --write").into());
+        }
+        Ok(Command::Minify(read_documents(spec, matches, paths)?, write, mode))
+    }
+
+    fn get_stats_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        Ok(Command::Stats(read_document(spec, matches.positional(0))?, mode))
+    }
+
+    fn get_validate_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let Some(schema) = matches.value("schema") else {
+            return Err(spec.error("Missing option --schema").into());
+        };
+        let format = get_format(spec, matches)?;
+        Ok(Command::Validate(read_input(schema)?, read_inputs(&matches.positionals)?, format, mode))
+    }
+
+    fn get_diff_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let mut options = DiffOptions::default();
+        if let Some(arrays) = matches.value("arrays") {
+            options.arrays = arrays.parse().map_err(|e: String| spec.error(&e))?;
+        }
+        if let Some(tolerance) = matches.value("tolerance") {
+            options.number_tolerance = tolerance
+                .parse()
+                .map_err(|_| spec.error(&format!("Invalid tolerance '{}'", tolerance)))?;
+        }
+        options.ignore_case = matches.flag("ignore-case");
+        let format = get_format(spec, matches)?;
+        let old = read_input(&matches.positionals[0])?;
+        let new = read_input(&matches.positionals[1])?;
+        Ok(Command::Diff(old, new, options, format, mode))
+    }
+
+    fn get_patch_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let output = get_edit_output(spec, matches)?;
+        let patch = read_input(&matches.positionals[1])?;
+        Ok(Command::Patch(matches.positionals[0].clone(), patch, output, mode))
+    }
+
+    fn get_make_patch_arguments(_: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let old = read_input(&matches.positionals[0])?;
+        let new = read_input(&matches.positionals[1])?;
+        Ok(Command::MakePatch(old, new, mode))
+    }
+
+    fn get_merge_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        if matches.positionals.len() < 2 {
+            return Err(spec.error("Merging needs at least two files").into());
+        }
+        let mut options = MergeOptions::default();
+        if let Some(arrays) = matches.value("arrays") {
+            options.arrays = arrays.parse().map_err(|e: String| spec.error(&e))?;
+        }
+        options.null_deletes = matches.flag("null-deletes");
+        let files = read_inputs(&matches.positionals)?;
+        Ok(Command::Merge(files, options, matches.flag("origins"), matches.flag("conflicts"), mode))
+    }
+
+    fn get_infer_schema_arguments(_: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        Ok(Command::InferSchema(read_inputs(&matches.positionals)?, mode))
+    }
+
+    fn get_codegen_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let language = match matches.value("lang") {
+            Some(language) => language.parse().map_err(|e: String| spec.error(&e))?,
+            None => Language::Rust,
+        };
+        let name = matches.value("name").unwrap_or("Root").to_string();
+        Ok(Command::Codegen(read_inputs(&matches.positionals)?, language, name, mode))
+    }
+
+    fn get_edit_arguments(spec: &Spec, matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        let output = get_edit_output(spec, matches)?;
+        let path = matches.positionals[1].clone();
+        parse_path(&path)?;
+        let value = matches.positionals.get(2).cloned().unwrap_or_default();
+        let action = match spec.name {
+            "set" => EditAction::Set(path, value),
+            "del" => EditAction::Delete(path),
+            "insert" => EditAction::Insert(path, value),
+            "append" => EditAction::Append(path, value),
+            _ => EditAction::Rename(path, value),
+        };
+        Ok(Command::Edit(matches.positionals[0].clone(), action, output, mode))
+    }
+
+    /// The command without a subcommand: `[FILE] [KEY] [INDEX]`, `-s VALUE`
+    /// or `--stats`.
+    fn get_main_arguments(matches: &Matches, mode: ParseMode) -> Result<Command, Failure> {
+        if matches.flag("version") {
+            return Ok(Command::Version);
+        }
+
+        if let Some(search_value) = matches.value("search") {
+            if matches.positionals.len() > 1 {
+                return Err(MAIN.error(&format!("Unexpected argument '{}'", matches.positionals[1])).into());
             }
-    
-            let file_path = &args[1];
-            let content = fs::read_to_string(file_path)
-                .map_err(|err| format!("Error reading file {}: {}", file_path, err))?;
-            let key = if args.len() >= 3 { Some(args[2].clone()) } else { None };
-            let index = if args.len() == 4 {
-                args[3].parse::<usize>().ok()
-            } else {
-                None
+            let jobs = get_jobs(&MAIN, matches)?;
+            let name = matches.positional(0).unwrap_or(STDIN);
+            let content = read_document(&MAIN, matches.positional(0))?;
+            return Ok(Command::Search(Inputs::single(name, content), search_value.to_string(), mode, jobs));
+        }
+
+        // The first argument is always the document, `-` for standard input;
+        // only without arguments is piped input read implicitly.
+        let positional = &matches.positionals[..];
+        let (name, content, rest) = match positional.first() {
+            Some(path) => (path.as_str(), read_input(path)?, &positional[1..]),
+            None => (STDIN, read_document(&MAIN, None)?, positional),
+        };
+        if matches.flag("stats") {
+            return Ok(Command::Stats(content, mode));
+        }
+        if rest.len() > 2 {
+            return Err(MAIN.error(&format!("Unexpected argument '{}'", rest[2])).into());
+        }
+        let key = rest.first().cloned();
+        let index = match rest.get(1) {
+            Some(index) => Some(index.parse::<usize>().map_err(|_| MAIN.error(&format!("Invalid index '{}'", index)))?),
+            None => None,
+        };
+        Ok(Command::Get(Inputs::single(name, content), key, index, get_output_options(matches), mode))
+    }
+
+    fn main_help() -> String {
+        let width = COMMANDS.iter().map(|(spec, _)| spec.name.len()).max().unwrap_or(0);
+        let mut help = MAIN.help();
+        help.push_str("\nCommands:\n");
+        for (spec, _) in COMMANDS {
+            help.push_str(&format!("  {:<width$}  {}\n", spec.name, spec.about, width = width));
+        }
+        help.push_str("\nSee 'json_parser <COMMAND> --help' for the options of a command.\n");
+        help
+    }
+
+    pub fn get_arguments() -> Result<Command, Failure> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        if let Some((spec, build)) = COMMANDS.iter().find(|(spec, _)| args.first() == Some(&spec.name.to_string())) {
+            let Some(matches) = spec.parse(&args[1..])? else {
+                return Ok(Command::Help(spec.help()));
             };
-            return Ok(Command::Parse(content, key, index));
-        }
-    
-        Err(format!(
-            "Usage: <file> [key] [index] or <standard input> [key] [index] \nExamples:\n\
-            ./json_parser data.json\n\
-            ./json_parser data.json grades\n\
-            ./json_parser data.json grades 2\n\
-            ./json_parser data.json details.city\n\
-            ./json_parser data.json details.city 1\n\
-            ./json_parser -s \"search_value\" data.json\n\
-            cat data.json | ./json_parser -s \"search_value\"\n\
-            cat data.json | ./json_parser grades\n\
-            cat data.json | ./json_parser grades 2\n\
-            cat data.json | ./json_parser details.city\n\
-            cat data.json | ./json_parser details.city 1"
-        ))
-    } 
\ No newline at end of file
+            return build(spec, &matches, get_mode(spec, &matches)?);
+        }
+        let Some(matches) = MAIN.parse(&args)? else {
+            return Ok(Command::Help(main_help()));
+        };
+        get_main_arguments(&matches, get_mode(&MAIN, &matches)?)
+    }
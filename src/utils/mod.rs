@@ -1 +1,9 @@
-pub mod handle_arguments;
\ No newline at end of file
+pub mod handle_arguments;
+pub mod cli;
+pub mod exit;
+pub mod output;
+pub mod files;
+pub mod format;
+pub mod atomic_write;
+pub mod text_diff;
+pub mod edit;
\ No newline at end of file
@@ -0,0 +1,111 @@
+    use std::io::{self, BufWriter, IsTerminal, StdoutLock, Write};
+
+    use crate::libs::json_parser::JsonValue;
+    use crate::libs::serializer::{serialize, SerializeOptions};
+    use crate::utils::exit::{Failure, Status};
+
+    /// How the results of `get` and `query` are written.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct OutputOptions {
+        /// Write strings as their text, without quotes or escapes.
+        pub raw: bool,
+        /// Write nothing between results. Implies `raw`.
+        pub join: bool,
+        /// End each result with NUL instead of a newline. Implies `raw`.
+        pub null_data: bool,
+        /// Exit with status 1 when the last result is null or false.
+        pub exit_status: bool,
+    }
+
+    impl OutputOptions {
+        fn is_raw(&self) -> bool {
+            self.raw || self.join || self.null_data
+        }
+    }
+
+    /// Writes results to stdout, formatted for a terminal or for other
+    /// programs.
+    ///
+    /// Colors are only used on a terminal and without raw output; other
+    /// values are plain JSON, on a single line when results are joined or
+    /// NUL-separated. Once the reader closes the pipe, further results are
+    /// dropped so that `| head` ends the command quietly.
+    pub struct Output {
+        options: OutputOptions,
+        out: BufWriter<StdoutLock<'static>>,
+        color: bool,
+        closed: bool,
+        last_falsy: bool,
+    }
+
+    impl Output {
+        pub fn new(options: OutputOptions) -> Self {
+            let stdout = io::stdout();
+            let color = stdout.is_terminal() && !options.is_raw();
+            Self { options, out: BufWriter::new(stdout.lock()), color, closed: false, last_falsy: false }
+        }
+
+        /// Writes one result, after the name of its `file` and its `label`
+        /// (such as its path) if given.
+        pub fn result(&mut self, file: Option<&str>, label: Option<&str>, value: &JsonValue) -> Result<(), Failure> {
+            self.last_falsy = matches!(value, JsonValue::Null | JsonValue::Boolean(false));
+            let text = match value {
+                JsonValue::String(s) if self.options.is_raw() => {
+                    if self.options.null_data && s.contains('\0') {
+                        return Err(Failure::new(
+                            Status::Usage,
+                            "A string result contains NUL, which --null-data cannot separate",
+                        ));
+                    }
+                    s.clone()
+                }
+                _ if self.color => value.to_string(),
+                _ if self.options.join || self.options.null_data => serialize(value, &SerializeOptions::compact()),
+                _ => serialize(value, &SerializeOptions::default()),
+            };
+            let separator = if self.options.null_data {
+                "\0"
+            } else if self.options.join {
+                ""
+            } else {
+                "\n"
+            };
+            let file = file.map(|file| format!("{}:", file)).unwrap_or_default();
+            match label {
+                Some(label) => self.write(&format!("{}{} = {}{}", file, label, text, separator)),
+                None => self.write(&format!("{}{}{}", file, text, separator)),
+            }
+        }
+
+        /// Flushes the output and returns the exit status for the results.
+        pub fn finish(mut self) -> Result<Status, Failure> {
+            if !self.closed {
+                let flushed = self.out.flush();
+                self.check(flushed)?;
+            }
+            if self.options.exit_status && self.last_falsy {
+                Ok(Status::NoMatch)
+            } else {
+                Ok(Status::Success)
+            }
+        }
+
+        fn write(&mut self, text: &str) -> Result<(), Failure> {
+            if self.closed {
+                return Ok(());
+            }
+            let written = self.out.write_all(text.as_bytes());
+            self.check(written)
+        }
+
+        fn check(&mut self, result: io::Result<()>) -> Result<(), Failure> {
+            match result {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                    self.closed = true;
+                    Ok(())
+                }
+                Err(err) => Err(Failure::io(format!("Error writing output: {}", err))),
+                Ok(()) => Ok(()),
+            }
+        }
+    }
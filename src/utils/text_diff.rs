@@ -0,0 +1,105 @@
+    const CONTEXT_LINES: usize = 3;
+
+    enum Line<'a> {
+        Same(&'a str),
+        Removed(&'a str),
+        Added(&'a str),
+    }
+
+    /// Line by line edit script between `old` and `new`.
+    ///
+    /// The common prefix and suffix are stripped first so that the quadratic
+    /// LCS table only covers the region that actually changed.
+    fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_mid = &old[prefix..old.len() - suffix];
+        let new_mid = &new[prefix..new.len() - suffix];
+
+        let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut lines: Vec<Line> = old[..prefix].iter().map(|l| Line::Same(l)).collect();
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                lines.push(Line::Same(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < old_mid.len() && (j == new_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                lines.push(Line::Removed(old_mid[i]));
+                i += 1;
+            } else {
+                lines.push(Line::Added(new_mid[j]));
+                j += 1;
+            }
+        }
+        lines.extend(old[old.len() - suffix..].iter().map(|l| Line::Same(l)));
+        lines
+    }
+
+    /// Renders a unified diff of two texts. Returns an empty string when
+    /// they are identical.
+    pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+        if old == new {
+            return String::new();
+        }
+        let old_lines: Vec<&str> = old.lines().collect();
+        let new_lines: Vec<&str> = new.lines().collect();
+        let lines = diff_lines(&old_lines, &new_lines);
+
+        let changed: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Line::Same(_)))
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+        let mut k = 0;
+        while k < changed.len() {
+            let start = changed[k].saturating_sub(CONTEXT_LINES);
+            let mut end = changed[k];
+            while k < changed.len() && changed[k] <= end + 2 * CONTEXT_LINES {
+                end = changed[k];
+                k += 1;
+            }
+            let end = (end + CONTEXT_LINES + 1).min(lines.len());
+
+            let count = |keep: fn(&Line) -> bool| lines[..start].iter().filter(|l| keep(l)).count();
+            let old_start = count(|l| !matches!(l, Line::Added(_)));
+            let new_start = count(|l| !matches!(l, Line::Removed(_)));
+            let hunk = &lines[start..end];
+            let old_len = hunk.iter().filter(|l| !matches!(l, Line::Added(_))).count();
+            let new_len = hunk.iter().filter(|l| !matches!(l, Line::Removed(_))).count();
+
+            output.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                old_start + 1,
+                old_len,
+                new_start + 1,
+                new_len
+            ));
+            for line in hunk {
+                match line {
+                    Line::Same(text) => output.push_str(&format!(" {}\n", text)),
+                    Line::Removed(text) => output.push_str(&format!("-{}\n", text)),
+                    Line::Added(text) => output.push_str(&format!("+{}\n", text)),
+                }
+            }
+        }
+        output
+    }
@@ -0,0 +1,125 @@
+//! Argument parsing of the command line: subcommands, options, help and
+//! error messages.
+
+mod common;
+
+use common::{run, stderr, stdout, TempDir};
+
+const DOCUMENT: &str = r#"{"name": "Ada", "tags": ["x", "y"], "details": {"city": "Paris", "zip": "75001"}}"#;
+
+#[test]
+fn version_is_printed() {
+    let output = run(&["--version"]);
+    assert_eq!(stdout(&output), format!("json_parser {}\n", env!("CARGO_PKG_VERSION")));
+    assert_eq!(stdout(&run(&["-V"])), stdout(&output));
+}
+
+#[test]
+fn help_lists_the_commands() {
+    let help = stdout(&run(&["--help"]));
+    for command in ["get", "search", "query", "fmt", "validate", "diff", "merge"] {
+        assert!(help.contains(&format!("\n  {} ", command)), "missing {} in:\n{}", command, help);
+    }
+}
+
+#[test]
+fn each_command_has_its_own_help() {
+    let help = stdout(&run(&["fmt", "--help"]));
+    assert!(help.contains("Usage: json_parser fmt [OPTIONS] [FILE]"), "{}", help);
+    assert!(help.contains("--sort-keys"), "{}", help);
+    assert!(stdout(&run(&["query", "-h"])).contains("Usage: json_parser query [OPTIONS] <PATH> [FILE]"));
+}
+
+#[test]
+fn unknown_options_are_errors() {
+    let output = run(&["get", "--bogus", "name"]);
+    assert!(stdout(&output).is_empty());
+    let error = stderr(&output);
+    assert!(error.starts_with("Unknown option '--bogus'\nUsage: json_parser get"), "{}", error);
+    assert!(error.contains("See 'json_parser get --help'"), "{}", error);
+    assert!(stderr(&run(&["fmt", "-x"])).starts_with("Unknown option '-x'"));
+}
+
+#[test]
+fn missing_and_extra_arguments_are_errors() {
+    assert!(stderr(&run(&["get"])).starts_with("Missing argument <PATH>"));
+    assert!(stderr(&run(&["mkpatch", "a.json"])).starts_with("Missing argument <NEW>"));
+    assert!(stderr(&run(&["stats", "a", "b"])).starts_with("Unexpected argument 'b'"));
+    assert!(stderr(&run(&["fmt", "--compact=yes"])).starts_with("Option '--compact' does not take a value"));
+    assert!(stderr(&run(&["search", "x", "--jobs"])).starts_with("Option '--jobs' requires a value"));
+}
+
+#[test]
+fn double_dash_ends_the_options() {
+    let directory = TempDir::new("double_dash");
+    let file = directory.write("-s", DOCUMENT);
+    let output = run(&["get", "name", "--", &file]);
+    assert!(stdout(&output).contains("Ada"), "{}", stderr(&output));
+}
+
+#[test]
+fn options_can_come_anywhere_and_be_grouped() {
+    let directory = TempDir::new("grouped");
+    let file = directory.write("data.json", DOCUMENT);
+    let output = run(&["fmt", &file, "-cS"]);
+    assert_eq!(
+        stdout(&output),
+        "{\"details\":{\"city\":\"Paris\",\"zip\":\"75001\"},\"name\":\"Ada\",\"tags\":[\"x\",\"y\"]}\n"
+    );
+    let output = run(&["fmt", "--indent=2", "--mode", "jsonc", &file]);
+    assert!(stdout(&output).starts_with("{\n  \"name\": \"Ada\","), "{}", stdout(&output));
+}
+
+#[test]
+fn get_search_and_query() {
+    let directory = TempDir::new("lookups");
+    let file = directory.write("data.json", DOCUMENT);
+    assert!(stdout(&run(&["get", "details.city", &file])).contains("Paris"));
+    assert!(stdout(&run(&["search", "PARIS", &file])).starts_with("Found 1 occurrences of 'PARIS':"));
+    assert_eq!(stdout(&run(&["query", "tags[*]", &file])), "\"x\"\n\"y\"\n");
+    assert_eq!(
+        stdout(&run(&["query", "--paths", "details.*", &file])),
+        "details.city = \"Paris\"\ndetails.zip = \"75001\"\n"
+    );
+}
+
+#[test]
+fn search_prints_the_matched_strings() {
+    let directory = TempDir::new("search");
+    let file = directory.write("data.json", r#"{"users":[{"name":"bob"}],"a.b":"bob","x":{"y":["Bob"]}}"#);
+    assert_eq!(
+        stdout(&run(&["search", "bob", &file])),
+        "Found 3 occurrences of 'bob':\n - users.name : \"bob\"\n - a.b : \"bob\"\n - x.y at index 0 : \"Bob\"\n"
+    );
+}
+
+#[test]
+fn searching_a_top_level_array_does_not_depend_on_the_jobs() {
+    let directory = TempDir::new("search_array");
+    let file = directory.write("data.json", r#"[{"name":"bob"},"Bob",["bob"]]"#);
+    let expected = "Found 3 occurrences of 'bob':\n - name : \"bob\"\n -  at index 1 : \"Bob\"\n -  at index 0 : \"bob\"\n";
+    for jobs in ["1", "4"] {
+        assert_eq!(stdout(&run(&["search", "bob", &file, "--jobs", jobs])), expected);
+    }
+}
+
+#[test]
+fn the_command_without_subcommand_still_works() {
+    let directory = TempDir::new("legacy");
+    let file = directory.write("data.json", DOCUMENT);
+    assert!(stdout(&run(&[&file, "tags", "2"])).contains("\"y\""));
+    assert!(stdout(&run(&["-s", "ada", &file])).starts_with("Found 1 occurrences"));
+    assert!(stderr(&run(&[&file, "tags", "two"])).starts_with("Invalid index 'two'"));
+    // The first argument names the document even when it does not exist.
+    let output = directory.run(&["tags", "2"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).starts_with("Error reading file tags: "), "{}", stderr(&output));
+}
+
+#[test]
+fn negative_numbers_are_values() {
+    let directory = TempDir::new("negative");
+    let file = directory.write("data.json", DOCUMENT);
+    let output = run(&["set", &file, "details.zip", "-5"]);
+    assert!(stdout(&output).contains("\"zip\": -5"), "{}", stderr(&output));
+}
@@ -0,0 +1,80 @@
+//! Helpers shared by the integration tests: running the binary and a
+//! temporary directory of input files.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary with `args` and an empty standard input.
+pub fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_json_parser"))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// Runs the binary from `directory`.
+pub fn run_in(directory: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_json_parser"))
+        .args(args)
+        .current_dir(directory)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// A directory under the system temporary directory, removed on drop.
+/// `name` keeps the directories of tests running in parallel apart.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let test = env!("CARGO_CRATE_NAME");
+        let root = std::env::temp_dir().join(format!("json_parser_{}_{}_{}", test, std::process::id(), name));
+        fs::create_dir_all(&root).unwrap();
+        TempDir(root)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.0
+    }
+
+    /// The path of `relative` inside the directory, as an argument.
+    pub fn path(&self, relative: &str) -> String {
+        self.0.join(relative).to_str().unwrap().to_string()
+    }
+
+    /// Writes a file, creating its parent directories, and returns its path.
+    pub fn write(&self, relative: &str, content: &str) -> String {
+        let path = self.0.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        self.path(relative)
+    }
+
+    pub fn read(&self, relative: &str) -> String {
+        fs::read_to_string(self.0.join(relative)).unwrap()
+    }
+
+    /// Runs the binary from inside the directory.
+    pub fn run(&self, args: &[&str]) -> Output {
+        run_in(&self.0, args)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
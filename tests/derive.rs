@@ -0,0 +1,163 @@
+//! `#[derive(ToJson, FromJson)]`: field attributes, enums, generics and
+//! error paths.
+
+#![cfg(feature = "derive")]
+
+use std::collections::BTreeMap;
+
+use json_parser::libs::json_parser::{JsonParser, JsonValue};
+use json_parser::{FromJson, ToJson};
+
+fn parse(input: &str) -> JsonValue {
+    JsonParser::new(input).parse().unwrap()
+}
+
+fn fallback_port() -> u16 {
+    8080
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Server {
+    host: String,
+    #[json(default = "fallback_port")]
+    port: u16,
+    #[json(rename = "tls-enabled")]
+    tls: bool,
+    #[json(default)]
+    aliases: Vec<String>,
+    #[json(skip)]
+    connections: usize,
+    r#type: Option<String>,
+    #[json(flatten)]
+    limits: Limits,
+}
+
+#[derive(Debug, Default, PartialEq, ToJson, FromJson)]
+struct Limits {
+    #[json(default)]
+    max_body: u64,
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Meters(f64);
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Point(i32, i32, #[json(skip)] u8);
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Marker;
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+enum Shape {
+    Empty,
+    #[json(rename = "circle")]
+    Circle(Meters),
+    Line(Point, Point),
+    Rect {
+        width: f64,
+        #[json(rename = "h")]
+        height: f64,
+    },
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Page<T> {
+    items: Vec<T>,
+    totals: BTreeMap<String, u32>,
+}
+
+#[test]
+fn structs_use_the_field_attributes() {
+    let server = Server {
+        host: "example.com".to_string(),
+        port: 443,
+        tls: true,
+        aliases: vec!["www".to_string()],
+        connections: 7,
+        r#type: None,
+        limits: Limits { max_body: 1024 },
+    };
+    let json = server.to_json();
+    assert_eq!(
+        json.to_json_string(),
+        r#"{"host":"example.com","port":443,"tls-enabled":true,"aliases":["www"],"type":null,"max_body":1024}"#
+    );
+    assert_eq!(Server::from_json(&json), Ok(Server { connections: 0, ..server }));
+}
+
+#[test]
+fn missing_members_use_their_defaults() {
+    let server = Server::from_json(&parse(r#"{"host": "a", "tls-enabled": false, "type": "edge", "connections": 3}"#));
+    assert_eq!(
+        server,
+        Ok(Server {
+            host: "a".to_string(),
+            port: 8080,
+            tls: false,
+            aliases: Vec::new(),
+            connections: 0,
+            r#type: Some("edge".to_string()),
+            limits: Limits::default(),
+        })
+    );
+    let error = Server::from_json(&parse(r#"{"host": "a"}"#)).unwrap_err();
+    assert_eq!(error.to_string(), "tls-enabled: missing field");
+}
+
+#[test]
+fn tuple_and_unit_structs() {
+    assert_eq!(Meters(2.5).to_json().to_json_string(), "2.5");
+    assert_eq!(Meters::from_json(&parse("2.5")), Ok(Meters(2.5)));
+    assert_eq!(Point(1, -2, 9).to_json().to_json_string(), "[1,-2]");
+    assert_eq!(Point::from_json(&parse("[1, -2]")), Ok(Point(1, -2, 0)));
+    assert!(Point::from_json(&parse("[1, 2, 3]")).is_err());
+    assert_eq!(Marker.to_json(), JsonValue::Null);
+    assert_eq!(Marker::from_json(&JsonValue::Null), Ok(Marker));
+    assert_eq!(Marker::from_json(&parse("1")).unwrap_err().to_string(), "(root): expected null, found integer");
+}
+
+#[test]
+fn enums_are_strings_or_single_key_objects() {
+    let shapes = vec![
+        Shape::Empty,
+        Shape::Circle(Meters(1.0)),
+        Shape::Line(Point(0, 0, 0), Point(1, 1, 0)),
+        Shape::Rect { width: 2.0, height: 3.0 },
+    ];
+    let json = shapes.to_json();
+    assert_eq!(
+        json.to_json_string(),
+        r#"["Empty",{"circle":1},{"Line":[[0,0],[1,1]]},{"Rect":{"width":2,"h":3}}]"#
+    );
+    assert_eq!(Vec::<Shape>::from_json(&json), Ok(shapes));
+}
+
+#[test]
+fn unknown_enum_variants_are_errors() {
+    let error = Shape::from_json(&parse(r#""Square""#)).unwrap_err();
+    assert_eq!(error.to_string(), "(root): unknown variant 'Square' (expected one of Empty, circle, Line, Rect)");
+    let error = Shape::from_json(&parse(r#"{"Circle": 1}"#)).unwrap_err();
+    assert!(error.message.starts_with("unknown variant 'Circle'"), "{}", error);
+    let error = Shape::from_json(&parse(r#"{"Empty": null, "Rect": {}}"#)).unwrap_err();
+    assert_eq!(error.to_string(), "(root): expected string or object with a single key, found object");
+}
+
+#[test]
+fn generic_types_get_bounds() {
+    let page = Page {
+        items: vec![Meters(1.5)],
+        totals: BTreeMap::from([("all".to_string(), 1)]),
+    };
+    let json = page.to_json();
+    assert_eq!(json.to_json_string(), r#"{"items":[1.5],"totals":{"all":1}}"#);
+    assert_eq!(Page::<Meters>::from_json(&json), Ok(page));
+}
+
+#[test]
+fn errors_carry_the_path_of_the_member() {
+    let error = Page::<Shape>::from_json(&parse(r#"{"items": ["Empty", {"Rect": {"width": 1, "h": "tall"}}], "totals": {}}"#))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "items[1].Rect.h: expected number, found string");
+    let error = Server::from_json(&parse(r#"{"host": "a", "tls-enabled": true, "port": 70000}"#)).unwrap_err();
+    assert!(error.to_string().starts_with("port: "), "{}", error);
+}
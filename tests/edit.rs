@@ -0,0 +1,85 @@
+//! The editing commands: `set`, `del`, `insert`, `append` and `rename`,
+//! printed, written in place or shown as a diff.
+
+mod common;
+
+use common::{run, stderr, stdout, TempDir};
+
+const DOCUMENT: &str = "{\n  // settings\n  \"a.b\": 1,\n  \"list\": [1, 2]\n}\n";
+
+#[test]
+fn edits_keep_comments_and_layout() {
+    let directory = TempDir::new("print");
+    let file = directory.write("data.jsonc", DOCUMENT);
+    let output = run(&["set", &file, r#"["a.b"]"#, "2", "--mode", "jsonc"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), DOCUMENT.replace("\"a.b\": 1", "\"a.b\": 2"));
+    let output = run(&["append", &file, "list", "3", "--mode", "jsonc"]);
+    assert_eq!(stdout(&output), DOCUMENT.replace("[1, 2]", "[1, 2, 3]"));
+    assert_eq!(directory.read("data.jsonc"), DOCUMENT);
+}
+
+#[test]
+fn in_place_rewrites_the_file() {
+    let directory = TempDir::new("in_place");
+    let file = directory.write("data.jsonc", DOCUMENT);
+    let output = run(&["del", &file, "list[0]", "-i", "--mode", "jsonc"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert_eq!(directory.read("data.jsonc"), DOCUMENT.replace("[1, 2]", "[2]"));
+}
+
+#[test]
+fn dry_run_prints_a_diff_and_writes_nothing() {
+    let directory = TempDir::new("dry_run");
+    let file = directory.write("data.jsonc", DOCUMENT);
+    let output = run(&["rename", &file, "list", "items", "-n", "--mode", "jsonc"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let diff = stdout(&output);
+    assert!(diff.contains("-  \"list\": [1, 2]\n+  \"items\": [1, 2]\n"), "{}", diff);
+    assert_eq!(directory.read("data.jsonc"), DOCUMENT);
+}
+
+#[test]
+fn dry_run_and_in_place_are_exclusive() {
+    let directory = TempDir::new("exclusive");
+    let file = directory.write("data.jsonc", DOCUMENT);
+    for args in [["-n", "-i"], ["--dry-run", "--in-place"]] {
+        let output = run(&["del", &file, "list", args[0], args[1], "--mode", "jsonc"]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(stderr(&output).starts_with("--dry-run and --in-place cannot be used together"));
+    }
+    assert_eq!(directory.read("data.jsonc"), DOCUMENT);
+}
+
+#[test]
+fn malformed_paths_are_usage_errors() {
+    let directory = TempDir::new("paths");
+    let file = directory.write("data.jsonc", DOCUMENT);
+    let output = run(&["set", &file, "a..b", "2", "--mode", "jsonc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Empty key in path 'a..b'"), "{}", stderr(&output));
+    assert_eq!(run(&["get", "a..b", &file, "--mode", "jsonc"]).status.code(), Some(2));
+    assert_eq!(stdout(&run(&["get", r#"["a.b"]"#, &file, "--mode", "jsonc"])), "1\n");
+}
+
+#[test]
+fn patch_applies_json_patch_and_merge_patch() {
+    let directory = TempDir::new("patch");
+    let file = directory.write("data.json", r#"{"a": {"b": 1, "c": 2}, "list": [1]}"#);
+    let operations = directory.write("operations.json", r#"[{"op": "add", "path": "/list/-", "value": 2}]"#);
+    let output = run(&["patch", &file, &operations]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "{\n    \"a\": {\n        \"b\": 1,\n        \"c\": 2\n    },\n    \"list\": [\n        1,\n        2\n    ]\n}\n");
+
+    let merge = directory.write("merge.json", r#"{"a": {"b": null, "d": 3}, "list": ["x"]}"#);
+    let output = run(&["patch", &file, &merge, "-i"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(
+        directory.read("data.json"),
+        "{\n    \"a\": {\n        \"c\": 2,\n        \"d\": 3\n    },\n    \"list\": [\n        \"x\"\n    ]\n}\n"
+    );
+
+    let scalar = directory.write("scalar.json", "1");
+    assert!(stderr(&run(&["patch", &file, &scalar])).contains("A JSON Patch must be an array of operations"));
+}
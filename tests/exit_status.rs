@@ -0,0 +1,74 @@
+//! Exit codes: 0 success, 1 no match, 2 usage error, 3 parse error and
+//! 4 I/O error, with diagnostics only on stderr.
+
+mod common;
+
+use common::{run, TempDir};
+
+fn code(args: &[&str]) -> i32 {
+    run(args).status.code().unwrap()
+}
+
+/// A directory with a valid document and a broken one, and their paths.
+fn documents(name: &str) -> (TempDir, String, String) {
+    let directory = TempDir::new(name);
+    let data = directory.write("data.json", r#"{"name": "Ada", "admin": false, "manager": null}"#);
+    let broken = directory.write("broken.json", r#"{"name": "#);
+    (directory, data, broken)
+}
+
+#[test]
+fn success_and_no_match() {
+    let (_directory, data, _) = documents("match");
+    assert_eq!(code(&["get", "name", &data]), 0);
+    assert_eq!(code(&["search", "ada", &data]), 0);
+
+    let output = run(&["get", "missing", &data]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Key 'missing' not found."));
+
+    assert_eq!(code(&[&data, "name", "3"]), 1);
+    assert_eq!(code(&["search", "nobody", &data]), 1);
+    assert_eq!(code(&["query", "users[*]", &data]), 1);
+    assert_eq!(code(&["del", &data, "missing"]), 1);
+}
+
+#[test]
+fn exit_status_flag_fails_on_null_and_false() {
+    let (_directory, data, _) = documents("flag");
+    assert_eq!(code(&["get", "admin", &data]), 0);
+    assert_eq!(code(&["get", "-e", "admin", &data]), 1);
+    assert_eq!(code(&["get", "--exit-status", "manager", &data]), 1);
+    assert_eq!(code(&["get", "-e", "name", &data]), 0);
+    assert_eq!(code(&["query", "-e", "admin", &data]), 1);
+    assert_eq!(code(&["-e", &data, "manager"]), 1);
+}
+
+#[test]
+fn usage_errors() {
+    let (_directory, data, _) = documents("usage");
+    assert_eq!(code(&["get", "--bogus", "name", &data]), 2);
+    assert_eq!(code(&["get"]), 2);
+    assert_eq!(code(&["search", "x", "--jobs", "0", &data]), 2);
+    assert_eq!(code(&["get", "a[", &data]), 2);
+}
+
+#[test]
+fn parse_errors() {
+    let (_directory, data, broken) = documents("parse");
+    let output = run(&["get", "name", &broken]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error parsing JSON"));
+    assert_eq!(code(&["fmt", &broken]), 3);
+    assert_eq!(code(&["merge", &data, &broken]), 3);
+}
+
+#[test]
+fn io_errors() {
+    let output = run(&["get", "name", "/nonexistent/data.json"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error reading file /nonexistent/data.json"));
+    assert_eq!(code(&["get", "name"]), 4);
+}
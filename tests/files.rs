@@ -0,0 +1,99 @@
+//! Several input files and directories in one run, with file name prefixes
+//! and a summary.
+
+mod common;
+
+use common::{run, TempDir};
+
+/// A directory tree of service configs:
+///
+/// ```text
+/// api/config.json       {"port": 8080, "owner": "admin"}
+/// api/notes.txt         not JSON
+/// db/config.json        {"port": 5432}
+/// db/broken.json        {"port":
+/// vendor/config.json    {"port": 1, "owner": "Admin"}
+/// ```
+fn tree(name: &str) -> TempDir {
+    let tree = TempDir::new(name);
+    tree.write("api/config.json", r#"{"port": 8080, "owner": "admin"}"#);
+    tree.write("api/notes.txt", "not JSON");
+    tree.write("db/config.json", r#"{"port": 5432}"#);
+    tree.write("db/broken.json", r#"{"port":"#);
+    tree.write("vendor/config.json", r#"{"port": 1, "owner": "Admin"}"#);
+    tree
+}
+
+#[test]
+fn several_files_carry_their_names() {
+    let tree = tree("several");
+    let api = tree.path("api/config.json");
+    let db = tree.path("db/config.json");
+    let output = run(&["get", "port", &api, &db]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}:8080\n{}:5432\n", api, db));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "2 files scanned, 2 with matches, 0 failed to parse\n");
+
+    let output = run(&["query", "-rp", "owner", &api, &db]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}:owner = admin\n", api));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("{}: No value matches 'owner'.", db)));
+    assert!(stderr.ends_with("2 files scanned, 1 with matches, 0 failed to parse\n"));
+}
+
+#[test]
+fn single_file_output_is_unchanged() {
+    let tree = tree("single");
+    let api = tree.path("api/config.json");
+    let output = run(&["get", "port", &api]);
+    assert_eq!(output.stdout, b"8080\n");
+    assert!(output.stderr.is_empty());
+
+    let output = run(&["get", "-H", "port", &api]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}:8080\n", api));
+}
+
+#[test]
+fn recursion_reports_parse_failures_and_continues() {
+    let tree = tree("recursive");
+    let output = run(&["get", "-R", "port", &tree.path("")]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{}:8080\n{}:5432\n{}:1\n",
+            tree.path("api/config.json"),
+            tree.path("db/config.json"),
+            tree.path("vendor/config.json")
+        )
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with(&format!("{}: Error parsing JSON", tree.path("db/broken.json"))));
+    assert!(stderr.ends_with("4 files scanned, 3 with matches, 1 failed to parse\n"));
+}
+
+#[test]
+fn globs_filter_directory_entries() {
+    let tree = tree("globs");
+    let root = tree.path("");
+    let output = run(&["search", "-R", "--exclude", "vendor", "--exclude", "broken*", "admin", &root]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let api = tree.path("api/config.json");
+    assert_eq!(stdout, format!("{}:Found 1 occurrences of 'admin':\n{}: - owner : \"admin\"\n", api, api));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "2 files scanned, 1 with matches, 0 failed to parse\n");
+
+    let output = run(&["get", "-R", "--include", "*.txt", "port", &root]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("{}: Error parsing JSON", tree.path("api/notes.txt"))));
+    assert!(stderr.ends_with("1 file scanned, 0 with matches, 1 failed to parse\n"));
+}
+
+#[test]
+fn directories_need_recursive() {
+    let tree = tree("directory");
+    let output = run(&["get", "port", &tree.path("api")]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("is a directory (use --recursive"));
+}
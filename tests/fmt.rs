@@ -0,0 +1,112 @@
+//! `fmt` as a repository formatter: --write, --check, array wrapping and
+//! the .jsonfmt.json config file.
+
+mod common;
+
+use common::{run_in, TempDir};
+
+const MESSY: &str = r#"{"tags":["a","b"],"name":"Ada"}"#;
+const FORMATTED: &str = "{\n    \"tags\": [\n        \"a\",\n        \"b\"\n    ],\n    \"name\": \"Ada\"\n}\n";
+
+/// A project with a formatted and an unformatted file under `data/`.
+fn project(name: &str) -> TempDir {
+    let project = TempDir::new(name);
+    project.write("data/messy.json", MESSY);
+    project.write("data/clean.json", FORMATTED);
+    project
+}
+
+#[test]
+fn check_lists_unformatted_files() {
+    let project = project("check");
+    let output = project.run(&["fmt", "--check", "-R", "data"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "data/messy.json\n");
+    assert_eq!(project.read("data/messy.json"), MESSY);
+
+    let output = project.run(&["fmt", "--check", "data/clean.json"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn write_rewrites_files_in_place() {
+    let project = project("write");
+    let output = project.run(&["fmt", "--write", "data/messy.json", "data/clean.json"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(project.read("data/messy.json"), FORMATTED);
+    assert_eq!(project.read("data/clean.json"), FORMATTED);
+    assert_eq!(project.run(&["fmt", "--check", "-R", "."]).status.code(), Some(0));
+}
+
+#[test]
+fn broken_files_are_reported_and_left_alone() {
+    let project = project("broken");
+    project.write("data/broken.json", "{\"a\": ");
+    let output = project.run(&["fmt", "-w", "-R", "data"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("data/broken.json: Error parsing JSON"));
+    assert_eq!(project.read("data/broken.json"), "{\"a\": ");
+    assert_eq!(project.read("data/messy.json"), FORMATTED);
+}
+
+#[test]
+fn wrap_width_keeps_short_arrays_on_one_line() {
+    let project = project("wrap");
+    let output = project.run(&["fmt", "--wrap-width", "22", "data/messy.json"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n    \"tags\": [\"a\", \"b\"],\n    \"name\": \"Ada\"\n}\n"
+    );
+    let output = project.run(&["fmt", "--wrap-width", "21", "--no-final-newline", "data/messy.json"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), FORMATTED.trim_end());
+}
+
+#[test]
+fn settings_come_from_the_config_file() {
+    let project = project("config");
+    project.write(".jsonfmt.json", "// Project style\n{\"indent\": 2, \"sort-keys\": true, \"wrap-width\": 80}\n");
+    let expected = "{\n  \"name\": \"Ada\",\n  \"tags\": [\"a\", \"b\"]\n}\n";
+    // Found from subdirectories too, and skipped by the recursive walk.
+    let output = run_in(&project.root().join("data"), &["fmt", "messy.json"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    assert_eq!(project.run(&["fmt", "-w", "-R", "."]).status.code(), Some(0));
+    assert_eq!(project.read("data/clean.json"), expected);
+
+    // Options given on the command line win.
+    let output = project.run(&["fmt", "--indent", "1", "--wrap-width", "0", "data/clean.json"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{\n \"name\": \"Ada\",\n \"tags\": [\n  \"a\",\n  \"b\"\n ]\n}\n");
+    let output = project.run(&["fmt", "--no-config", "data/clean.json"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{\n    \"name\": \"Ada\",\n    \"tags\": [\n        \"a\",\n        \"b\"\n    ]\n}\n");
+
+    project.write(".jsonfmt.json", "{\"indent\": \"two\"}");
+    let output = project.run(&["fmt", "data/clean.json"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Invalid value \"two\" for 'indent'"));
+}
+
+#[test]
+fn write_and_check_need_files() {
+    let project = project("usage");
+    assert_eq!(project.run(&["fmt", "--write"]).status.code(), Some(2));
+    assert_eq!(project.run(&["fmt", "--check", "-"]).status.code(), Some(2));
+    assert_eq!(project.run(&["fmt", "--write", "--check", "data/clean.json"]).status.code(), Some(2));
+    assert_eq!(project.run(&["fmt", "data/clean.json", "data/messy.json"]).status.code(), Some(2));
+}
+
+#[test]
+fn write_keeps_comments_and_number_lexemes() {
+    let project = project("lossless");
+    let source = "// Service settings\n{\"id\":12345678901234567890, // from the database\n\"ratio\":3.141592653589793238,\n/* defaults */ \"retries\":[1.0,2]}\n";
+    let expected = "// Service settings\n{\n    \"id\": 12345678901234567890, // from the database\n    \"ratio\": 3.141592653589793238,\n    /* defaults */\n    \"retries\": [\n        1,\n        2\n    ]\n}\n";
+    project.write("settings.jsonc", source);
+    let output = project.run(&["fmt", "--mode", "jsonc", "--write", "settings.jsonc"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(project.read("settings.jsonc"), expected);
+    assert_eq!(project.run(&["fmt", "--mode", "jsonc", "--check", "settings.jsonc"]).status.code(), Some(0));
+
+    project.write("big.json", "[12345678901234567890]");
+    let output = project.run(&["fmt", "-c", "big.json"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[12345678901234567890]\n");
+}
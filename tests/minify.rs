@@ -0,0 +1,73 @@
+//! `minify`: the smallest encoding of a document, with the sizes before and
+//! after.
+
+mod common;
+
+use std::fs;
+
+use common::{run, TempDir};
+
+const DOCUMENT: &str = "{\n    \"big\": 1000000000000000000000,\n    \"small\": 0.0000001,\n    \"round\": 1000,\n    \"half\": 0.50,\n    \"text\": \"caf\\u00e9 \\/ \\\"q\\\"\\n\",\n    \"list\": [ 1.0, 25, true, null ]\n}\n";
+const MINIFIED: &str = r#"{"big":1e21,"small":1e-7,"round":1e3,"half":0.5,"text":"café / \"q\"\n","list":[1,25,true,null]}"#;
+
+#[test]
+fn minified_text_is_the_smallest_encoding() {
+    let files = TempDir::new("smallest");
+    let path = files.write("data.json", DOCUMENT);
+    let output = run(&["minify", &path]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), MINIFIED);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{}: {} -> {} bytes (-43.6%)\n", path, DOCUMENT.len(), MINIFIED.len())
+    );
+
+    // The minified text parses back to the same values.
+    let minified = files.write("minified.json", MINIFIED);
+    for key in ["big", "small", "round", "half", "text", "list"] {
+        let original = run(&["get", "-j", key, &path]);
+        assert_eq!(original.status.code(), Some(0));
+        assert_eq!(original.stdout, run(&["get", "-j", key, &minified]).stdout, "{}", key);
+    }
+}
+
+#[test]
+fn write_minifies_several_files_in_place() {
+    let files = TempDir::new("write");
+    let first = files.write("first.json", DOCUMENT);
+    let second = files.write("second.json", "[ 1, 2 ]\n");
+    let broken = files.write("broken.json", "[1, ");
+    let output = run(&["minify", "--write", &first, &second, &broken]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(&first).unwrap(), MINIFIED);
+    assert_eq!(fs::read_to_string(&second).unwrap(), "[1,2]");
+    assert_eq!(fs::read_to_string(&broken).unwrap(), "[1, ");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("{}: 9 -> 5 bytes (-44.4%)\n", second)), "{}", stderr);
+    assert!(stderr.contains(&format!("{}: Error parsing JSON", broken)), "{}", stderr);
+    let total = DOCUMENT.len() + 9;
+    assert!(stderr.ends_with(&format!("Total: {} -> {} bytes (-43.6%)\n", total, MINIFIED.len() + 5)), "{}", stderr);
+}
+
+#[test]
+fn several_files_need_write() {
+    let files = TempDir::new("usage");
+    let path = files.write("data.json", DOCUMENT);
+    assert_eq!(run(&["minify", &path, &path]).status.code(), Some(2));
+    assert_eq!(run(&["minify", "--write", "-"]).status.code(), Some(2));
+}
+
+#[test]
+fn numbers_are_never_changed() {
+    let files = TempDir::new("exact");
+    let path = files.write("data.json", "[ 12345678901234567890, 3.141592653589793238, 1e400, 0.10, 1000 ]");
+    let output = run(&["minify", &path]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[12345678901234567890,3.141592653589793238,1e400,0.1,1e3]");
+
+    let path = files.write("data.jsonc", "// ids\n{\"id\": 12345678901234567890, /* n */ \"n\": 2.50}\n");
+    let output = run(&["minify", "--mode", "jsonc", &path]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), r#"{"id":12345678901234567890,"n":2.5}"#);
+}
@@ -0,0 +1,53 @@
+//! Result output for shell consumption: raw strings, joined results and
+//! NUL-separated results.
+
+mod common;
+
+use common::{run, stdout, TempDir};
+
+const DOCUMENT: &str = r#"{"db": {"host": "db.local", "port": 5432}, "tags": ["a b", "tab\there", "quote\"d"], "off": false}"#;
+
+#[test]
+fn plain_output_is_json_without_colors() {
+    let directory = TempDir::new("plain");
+    let file = directory.write("data.json", DOCUMENT);
+    assert_eq!(stdout(&run(&["get", "db.host", &file])), "\"db.local\"\n");
+    assert_eq!(stdout(&run(&["get", "db", &file])), "{\n    \"host\": \"db.local\",\n    \"port\": 5432\n}\n");
+}
+
+#[test]
+fn raw_output_prints_strings_unquoted_and_unescaped() {
+    let directory = TempDir::new("raw");
+    let file = directory.write("data.json", DOCUMENT);
+    assert_eq!(stdout(&run(&["get", "-r", "db.host", &file])), "db.local\n");
+    assert_eq!(stdout(&run(&["get", "--raw-output", "tags[1]", &file])), "tab\there\n");
+    assert_eq!(stdout(&run(&["-r", &file, "tags", "3"])), "quote\"d\n");
+    // Other values stay JSON.
+    assert_eq!(stdout(&run(&["get", "-r", "db.port", &file])), "5432\n");
+    assert_eq!(stdout(&run(&["query", "-r", "tags[*]", &file])), "a b\ntab\there\nquote\"d\n");
+}
+
+#[test]
+fn join_prints_results_back_to_back() {
+    let directory = TempDir::new("join");
+    let file = directory.write("data.json", DOCUMENT);
+    assert_eq!(stdout(&run(&["query", "-j", "db.*", &file])), "db.local5432");
+    assert_eq!(stdout(&run(&["get", "--join", "db", &file])), "{\"host\":\"db.local\",\"port\":5432}");
+}
+
+#[test]
+fn null_data_separates_results_with_nul() {
+    let directory = TempDir::new("null_data");
+    let file = directory.write("data.json", DOCUMENT);
+    assert_eq!(stdout(&run(&["query", "--null-data", "tags[*]", &file])), "a b\0tab\there\0quote\"d\0");
+    assert_eq!(stdout(&run(&["query", "--null-data", "-p", "db.*", &file])), "db.host = db.local\0db.port = 5432\0");
+}
+
+#[test]
+fn raw_output_keeps_the_exit_status() {
+    let directory = TempDir::new("exit_status");
+    let file = directory.write("data.json", DOCUMENT);
+    let output = run(&["get", "-re", "off", &file]);
+    assert_eq!(output.stdout, b"false\n");
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,145 @@
+//! The serde bridge: `from_str` and `to_string` with derived types, big
+//! integers, extended syntax and `JsonValue` itself.
+
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use json_parser::libs::de::from_str_with_mode;
+use json_parser::libs::json_parser::{JsonParser, JsonValue, ParseMode};
+use json_parser::libs::ser::to_string_with_options;
+use json_parser::libs::serializer::SerializeOptions;
+use json_parser::{from_str, to_string, to_string_pretty};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    #[serde(rename = "max-size")]
+    max_size: u64,
+    ratio: f64,
+    tags: Vec<String>,
+    owner: Option<Owner>,
+    #[serde(default)]
+    limits: BTreeMap<String, i32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Owner {
+    id: i64,
+    admin: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Start,
+    Move(i32, i32),
+    Rename(String),
+    Resize { width: u32, height: u32 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Unit;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Wrapper(char);
+
+fn config() -> Config {
+    Config {
+        name: "line\n\"quoted\"".to_string(),
+        max_size: 10,
+        ratio: 0.5,
+        tags: vec!["a".to_string()],
+        owner: Some(Owner { id: -3, admin: true }),
+        limits: BTreeMap::from([("cpu".to_string(), 2)]),
+    }
+}
+
+#[test]
+fn structs_round_trip() {
+    let text = to_string(&config()).unwrap();
+    assert_eq!(
+        text,
+        r#"{"name":"line\n\"quoted\"","max-size":10,"ratio":0.5,"tags":["a"],"owner":{"id":-3,"admin":true},"limits":{"cpu":2}}"#
+    );
+    assert_eq!(from_str::<Config>(&text), Ok(config()));
+}
+
+#[test]
+fn missing_and_null_members() {
+    let config: Config = from_str(r#"{"name": "x", "max-size": 1, "ratio": 1, "tags": [], "owner": null}"#).unwrap();
+    assert_eq!(config.owner, None);
+    assert!(config.limits.is_empty());
+    let error = from_str::<Config>(r#"{"name": "x"}"#).unwrap_err();
+    assert!(error.to_string().starts_with("missing field `max-size`"), "{}", error);
+}
+
+#[test]
+fn enums_use_the_external_representation() {
+    let events = vec![
+        Event::Start,
+        Event::Move(1, -1),
+        Event::Rename("b".to_string()),
+        Event::Resize { width: 2, height: 3 },
+    ];
+    let text = to_string(&events).unwrap();
+    assert_eq!(text, r#"["Start",{"Move":[1,-1]},{"Rename":"b"},{"Resize":{"width":2,"height":3}}]"#);
+    assert_eq!(from_str::<Vec<Event>>(&text), Ok(events));
+    assert!(from_str::<Event>(r#""Stop""#).is_err());
+    assert_eq!(to_string(&Unit).unwrap(), "null");
+    assert_eq!(from_str::<Unit>("null"), Ok(Unit));
+    assert_eq!(to_string(&Wrapper('é')).unwrap(), r#""é""#);
+    assert_eq!(from_str::<Wrapper>(r#""é""#), Ok(Wrapper('é')));
+}
+
+#[test]
+fn large_integers_keep_their_precision() {
+    assert_eq!(from_str::<u64>("18446744073709551615"), Ok(u64::MAX));
+    assert_eq!(from_str::<i64>("-9223372036854775808"), Ok(i64::MIN));
+    assert_eq!(to_string(&u64::MAX).unwrap(), "18446744073709551615");
+    assert_eq!(from_str::<f64>("1.5e3"), Ok(1500.0));
+    assert!(from_str::<u8>("256").is_err());
+    assert!(from_str::<u32>("-1").is_err());
+}
+
+#[test]
+fn errors_report_their_position() {
+    let error = from_str::<Vec<u32>>("[1, 2,, 3]").unwrap_err();
+    assert_eq!(error.position(), Some(6));
+    let error = from_str::<Vec<u32>>("[1] [2]").unwrap_err();
+    assert_eq!(error.to_string(), "Trailing characters after the JSON value at position 4");
+    assert!(from_str::<Vec<u32>>("[1, 2,]").is_err());
+}
+
+#[test]
+fn extended_syntax_needs_its_mode() {
+    let text = "// settings\n{name: 'x', 'max-size': 0x10, ratio: .5, tags: ['a',], owner: null,}";
+    assert!(from_str::<Config>(text).is_err());
+    assert!(from_str_with_mode::<Config>(text, ParseMode::Jsonc).is_err());
+    let config: Config = from_str_with_mode(text, ParseMode::Json5).unwrap();
+    assert_eq!((config.name.as_str(), config.max_size, config.ratio), ("x", 16, 0.5));
+    let list: Vec<i32> = from_str_with_mode("[1, /* two */ 2,]", ParseMode::Jsonc).unwrap();
+    assert_eq!(list, [1, 2]);
+}
+
+#[test]
+fn output_follows_the_options() {
+    let limits = BTreeMap::from([("b".to_string(), vec![1]), ("a".to_string(), vec![])]);
+    assert_eq!(to_string_pretty(&limits).unwrap(), "{\n    \"a\": [],\n    \"b\": [\n        1\n    ]\n}");
+    let options = SerializeOptions { indent: Some("  ".to_string()), ..SerializeOptions::compact() };
+    assert_eq!(to_string_with_options(&Owner { id: 1, admin: false }, options).unwrap(), "{\n  \"id\": 1,\n  \"admin\": false\n}");
+    let sorted = SerializeOptions { sort_keys: true, ..SerializeOptions::compact() };
+    assert_eq!(to_string_with_options(&Owner { id: 1, admin: false }, sorted).unwrap(), r#"{"admin":false,"id":1}"#);
+}
+
+#[test]
+fn json_values_convert_both_ways() {
+    let text = r#"{"a":[1,2.5,"x",null,true],"b":{}}"#;
+    let value: JsonValue = from_str(text).unwrap();
+    assert_eq!(value, JsonParser::new(text).parse().unwrap());
+    assert_eq!(to_string(&value).unwrap(), text);
+    let members: BTreeMap<String, JsonValue> = from_str(r#"{"id": 1, "admin": true}"#).unwrap();
+    assert_eq!(members["admin"], JsonValue::Boolean(true));
+    // Like `JsonValue` output, numbers JSON cannot represent become null.
+    assert_eq!(to_string(&[f64::NAN, f64::INFINITY]).unwrap(), "[null,null]");
+}
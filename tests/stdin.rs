@@ -0,0 +1,113 @@
+//! How the command line picks its input: the first argument is the file,
+//! `-` reads standard input, and piped input is only read implicitly when
+//! no argument is given, whatever the timing of the producer.
+
+mod common;
+
+use std::io::Write;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{stderr, stdout, TempDir};
+
+const DOCUMENT: &str = r#"{"name": "Ada", "grades": [12, 17]}"#;
+
+fn json_parser(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_json_parser"));
+    command.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    command
+}
+
+/// Runs the binary with `input` piped to it.
+fn piped(args: &[&str], input: &str) -> Output {
+    let mut child = json_parser(args).stdin(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    finish(child)
+}
+
+/// Waits for the child, failing instead of hanging if it never exits.
+fn finish(mut child: Child) -> Output {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait().unwrap().is_none() {
+        assert!(Instant::now() < deadline, "json_parser did not exit");
+        thread::sleep(Duration::from_millis(10));
+    }
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn slow_stdin_is_read_to_the_end() {
+    let mut child = json_parser(&["-", "name"]).stdin(Stdio::piped()).spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let (head, tail) = DOCUMENT.split_at(10);
+    stdin.write_all(head.as_bytes()).unwrap();
+    thread::sleep(Duration::from_millis(500));
+    stdin.write_all(tail.as_bytes()).unwrap();
+    drop(stdin);
+
+    let output = finish(child);
+    assert!(stdout(&output).contains("Ada"), "stdout: {}", stdout(&output));
+}
+
+#[test]
+fn empty_stdin_is_reported() {
+    for args in [&[][..], &["-", "name"]] {
+        let output = finish(json_parser(args).stdin(Stdio::null()).spawn().unwrap());
+        assert_eq!(output.status.code(), Some(4));
+        assert!(stderr(&output).contains("No data on standard input"), "stderr: {}", stderr(&output));
+    }
+}
+
+#[test]
+fn dash_reads_stdin() {
+    let output = piped(&["-", "grades", "2"], DOCUMENT);
+    assert!(stdout(&output).contains("17"), "stdout: {}", stdout(&output));
+}
+
+#[test]
+fn piped_stdin_is_read_without_arguments() {
+    let output = piped(&[], DOCUMENT);
+    assert!(output.status.success(), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).contains("\"grades\""), "stdout: {}", stdout(&output));
+}
+
+#[test]
+fn first_argument_is_always_a_file() {
+    let directory = TempDir::new("missing");
+    // With a document piped in, a missing file is still an error rather
+    // than a key to look up in standard input.
+    let output = piped(&[&directory.path("name")], DOCUMENT);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).starts_with("Error reading file "), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = piped(&["name"], DOCUMENT);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).starts_with("Error reading file name: "), "stderr: {}", stderr(&output));
+}
+
+#[test]
+fn file_argument_wins_over_open_stdin() {
+    let directory = TempDir::new("file_argument");
+    let file = directory.write("data.json", DOCUMENT);
+    // Standard input stays open and empty: reading it would never finish.
+    let mut child = json_parser(&[&file, "name"]).stdin(Stdio::piped()).spawn().unwrap();
+    let stdin = child.stdin.take();
+
+    let output = finish(child);
+    drop(stdin);
+    assert!(stdout(&output).contains("Ada"), "stdout: {}", stdout(&output));
+}
+
+#[test]
+fn search_reads_slow_stdin() {
+    let mut child = json_parser(&["-s", "ada"]).stdin(Stdio::piped()).spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    thread::sleep(Duration::from_millis(500));
+    stdin.write_all(DOCUMENT.as_bytes()).unwrap();
+    drop(stdin);
+
+    let output = finish(child);
+    assert!(stdout(&output).contains("Found 1 occurrences"), "stdout: {}", stdout(&output));
+}